@@ -1,24 +1,28 @@
 use std::borrow::Cow;
 use std::io::{self, Read};
-use std::path::Path;
-use std::sync::Arc;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 
 use reflexo_typst::config::entry::{EntryOpts, MEMORY_MAIN_ENTRY};
 use reflexo_typst::config::CompileOpts;
+use reflexo_typst::metrics::{
+    CompileMetrics, EvictMetrics, JsonMetricsSink, MetricsSink, PrometheusMetricsSink,
+};
 use reflexo_typst::path::PathClean;
-use reflexo_typst::DynSystemComputation;
+use reflexo_typst::task::CancelToken;
 use reflexo_typst::{
-    CompilationHandle, CompileActor, CompileServerOpts, CompilerFeat, DynComputation, EntryManager,
-    EntryReader, ShadowApi, TypstSystemUniverse, WorldComputeGraph,
+    CompilationHandle, CompileActor, CompileServerOpts, EntryManager, EntryReader, ShadowApi,
+    SystemCompilerFeat, TypstSystemUniverse, WorldComputeGraph,
 };
 use tokio::sync::mpsc;
 use typst::diag::{FileError, FileResult};
 use typst::foundations::{Bytes, Dict, IntoValue};
 
+use crate::export::{DynExportComputation, ExportSummary};
 use crate::font::fonts;
 use crate::{
     utils::{self, UnwrapOrExit},
-    CompileArgs, CompileOnceArgs,
+    CompileArgs, CompileOnceArgs, MetricsFormat,
 };
 
 pub fn resolve_universe(args: CompileOnceArgs) -> TypstSystemUniverse {
@@ -112,29 +116,89 @@ pub fn resolve_universe(args: CompileOnceArgs) -> TypstSystemUniverse {
     verse
 }
 
-pub fn compile_export(args: CompileArgs, exporter: DynSystemComputation) -> ! {
+pub fn compile_export(args: CompileArgs, exporter: DynExportComputation) -> ! {
     let (intr_tx, intr_rx) = mpsc::unbounded_channel();
 
+    let metrics = metrics_sink(&args);
     let verse = resolve_universe(args.compile);
 
-    let handle = Arc::new(CompileHandler { exporter });
+    let handle = Arc::new(CompileHandler {
+        exporter,
+        last_export: Mutex::new(Some(ExportSummary::default())),
+    });
 
     let actor = CompileActor::new_with(
         verse,
         intr_tx,
         intr_rx,
         CompileServerOpts {
-            compile_handle: handle,
+            compile_handle: handle.clone(),
+            metrics,
             ..Default::default()
         },
     )
     .with_watch(args.watch);
 
     utils::async_continue(async move {
-        utils::logical_exit(actor.run().await.unwrap_or_exit());
+        let is_success = actor.run().await.unwrap_or_exit();
+        // Fails if the last export failed, even if the compilation succeeded.
+        utils::logical_exit(is_success && handle.is_export_success());
     })
 }
 
+/// Creates the sink of the metrics from the arguments.
+fn metrics_sink(args: &CompileArgs) -> Option<Arc<dyn MetricsSink>> {
+    let path = args.metrics.clone()?;
+
+    Some(match args.metrics_format {
+        MetricsFormat::Json => {
+            let file = std::fs::File::create(&path).unwrap_or_else(|err| {
+                clap::Error::raw(
+                    clap::error::ErrorKind::Io,
+                    format!("create metrics file failed: {err}\n"),
+                )
+                .exit()
+            });
+            Arc::new(JsonMetricsSink::new(file))
+        }
+        MetricsFormat::Prometheus => Arc::new(PrometheusFileSink {
+            sink: PrometheusMetricsSink::default(),
+            path,
+        }),
+    })
+}
+
+/// Rewrites a file with the aggregated metrics in the Prometheus text format,
+/// e.g. for the textfile collector of the node exporter.
+struct PrometheusFileSink {
+    sink: PrometheusMetricsSink,
+    path: PathBuf,
+}
+
+impl PrometheusFileSink {
+    fn flush(&self) {
+        // Renames a complete file to avoid exposing a partial one.
+        let tmp = self.path.with_extension("prom.tmp");
+        let res = std::fs::write(&tmp, self.sink.render())
+            .and_then(|_| std::fs::rename(&tmp, &self.path));
+        if let Err(err) = res {
+            log::warn!("failed to write metrics to {}: {err}", self.path.display());
+        }
+    }
+}
+
+impl MetricsSink for PrometheusFileSink {
+    fn compile(&self, metrics: &CompileMetrics) {
+        self.sink.compile(metrics);
+        self.flush();
+    }
+
+    fn evict(&self, metrics: &EvictMetrics) {
+        self.sink.evict(metrics);
+        self.flush();
+    }
+}
+
 /// Read from stdin.
 fn read_from_stdin() -> FileResult<Vec<u8>> {
     let mut buf = Vec::new();
@@ -148,17 +212,33 @@ fn read_from_stdin() -> FileResult<Vec<u8>> {
     Ok(buf)
 }
 
-pub struct CompileHandler<F: CompilerFeat> {
-    exporter: DynComputation<F>,
+pub struct CompileHandler {
+    exporter: DynExportComputation,
+    /// The outcomes of the last export, or `None` if it failed to run.
+    last_export: Mutex<Option<ExportSummary>>,
+}
+
+impl CompileHandler {
+    /// Whether all the tasks of the last export succeeded.
+    fn is_export_success(&self) -> bool {
+        let last_export = self.last_export.lock().unwrap();
+        last_export.as_ref().is_some_and(ExportSummary::is_success)
+    }
 }
 
-impl<F: CompilerFeat + 'static> CompilationHandle<F> for CompileHandler<F> {
+impl CompilationHandle<SystemCompilerFeat> for CompileHandler {
     fn status(&self, _revision: usize, _rep: reflexo_typst::CompileReport) {}
 
-    fn notify_compile(&self, g: &Arc<WorldComputeGraph<F>>) {
+    fn notify_compile(&self, g: &Arc<WorldComputeGraph<SystemCompilerFeat>>) {
         let res = (self.exporter)(g);
-        if let Err(err) = res {
-            eprintln!("export failed: {err}");
+        // The newer compilation reports the status instead.
+        if CancelToken::of(g).is_cancelled() {
+            return;
         }
+        // The failed tasks are already reported by the exporter.
+        let summary = res
+            .inspect_err(|err| eprintln!("export failed: {err}"))
+            .ok();
+        *self.last_export.lock().unwrap() = summary;
     }
 }
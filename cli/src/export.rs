@@ -1,18 +1,22 @@
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use reflexo_typst::error::prelude::*;
+use reflexo_typst::metrics::MetricsRecorder;
 use reflexo_typst::program_meta::REPORT_BUG_MESSAGE;
 use reflexo_typst::svg::DefaultExportFeature;
-use reflexo_typst::task::{ExportHtmlTask, ExportPdfTask, ExportTextTask};
+use reflexo_typst::task::{CancelToken, ExportHtmlTask, ExportPdfTask, ExportTextTask};
 use reflexo_typst::{
-    AstExport, Bytes, CompilationTask, CompileReport, ConfigTask, DiagnosticHandler,
-    DiagnosticsTask, DynSvgModuleExport, DynSystemComputation, ExportAstTask, ExportComputation,
-    ExportDynSvgModuleTask, ExportWebSvgHtmlTask, ExportWebSvgModuleTask, ExportWebSvgTask,
-    FlagTask, HtmlCompilationTask, HtmlExport, OptionDocumentTask, PagedCompilationTask, PdfExport,
-    SystemCompilerFeat, TakeAs, TextExport, WebSvgExport, WebSvgHtmlExport, WebSvgModuleExport,
-    WorldComputable, WorldComputeGraph,
+    AdaptiveLayoutWidths, AstExport, Bytes, CompileReport, ConfigTask, DiagnosticHandler,
+    DiagnosticsTask, DynSvgModuleExport, ExportAstTask, ExportComputation, ExportDynSvgModuleTask,
+    ExportPdfOutputTask, ExportTextJsonTask, ExportWebPngTask, ExportWebSvgHtmlTask,
+    ExportWebSvgModuleTask, ExportWebSvgTask, FlagTask, HtmlCompilationTask, HtmlExport,
+    PagedCompilationTask, PdfOutputExport, SystemCompilerFeat, TextExport, TextJsonExport,
+    TypstAbs, TypstHtmlDocument, TypstPagedDocument, WebPngExport, WebPngPages, WebSvgExport,
+    WebSvgHtmlExport, WebSvgModuleExport, WorldComputable, WorldComputeGraph,
 };
+use serde::Serialize;
 use typst::World;
 
 use crate::{utils::current_dir, CompileArgs};
@@ -23,11 +27,13 @@ pub static AVAILABLE_FORMATS: &[(/* format name */ &str, /* feature hint */ &str
     ("ast", REPORT_BUG_MESSAGE),
     ("nothing", REPORT_BUG_MESSAGE),
     ("pdf", "pdf"),
+    ("png", "png"),
     ("svg", "svg"),
     ("svg_html", "svg"),
     ("sir", "svg"),
     ("vector", "svg"),
     ("text", "text"),
+    ("text-json", "text"),
 ];
 
 /// Hint the user that the given format is not enable or not available.
@@ -60,19 +66,23 @@ fn exit_by_unknown_format(f: &str) -> ! {
 #[derive(Clone)]
 pub enum ReflexoTask {
     Ast(ExportAstTask),
-    Pdf(ExportPdfTask),
+    Pdf(ExportPdfOutputTask),
     Html(ExportHtmlTask),
     WebSvg(ExportWebSvgTask),
     WebSvgHtml(ExportWebSvgHtmlTask),
     WebSvgModule(ExportWebSvgModuleTask),
     DynSvgModule(ExportDynSvgModuleTask),
     Text(ExportTextTask),
+    TextJson(ExportTextJsonTask),
+    Png(ExportWebPngTask),
 }
 
 #[derive(Default, Clone)]
 pub struct ReflexoTaskBuilder {
     diag_handler: DiagnosticHandler,
     output_path: PathBuf,
+    page_template: Option<String>,
+    summary_path: Option<PathBuf>,
     tasks: Vec<ReflexoTask>,
 }
 
@@ -102,6 +112,8 @@ impl ReflexoTaskBuilder {
                 None => dir.join("main"),
             }
         };
+        self.page_template = args.export.page_template.clone();
+        self.summary_path = args.export.export_summary.clone();
         let mut formats = {
             // If formats are specified, use them.
             let mut formats = args.format.clone();
@@ -123,9 +135,15 @@ impl ReflexoTaskBuilder {
                 }
                 #[cfg(feature = "pdf")]
                 "pdf" => {
-                    self.add_pdf(ExportPdfTask {
-                        creation_timestamp: args.export.creation_timestamp,
-                        ..ExportPdfTask::default()
+                    self.add_pdf(ExportPdfOutputTask {
+                        export: ExportPdfTask {
+                            pdf_standards: args.export.pdf_standards.clone(),
+                            creation_timestamp: args.export.creation_timestamp,
+                            ..ExportPdfTask::default()
+                        },
+                        pages: args.export.pages.clone(),
+                        no_tags: args.export.no_pdf_tags,
+                        ident: args.export.pdf_ident.clone(),
                     });
                 }
                 #[cfg(feature = "html")]
@@ -134,11 +152,17 @@ impl ReflexoTaskBuilder {
                 }
                 #[cfg(feature = "svg")]
                 "svg" => {
-                    self.add_web_svg(ExportWebSvgTask::default());
+                    self.add_web_svg(ExportWebSvgTask {
+                        options: args.export.svg_options(),
+                        ..ExportWebSvgTask::default()
+                    });
                 }
                 #[cfg(feature = "svg")]
                 "svg_html" => {
-                    self.add_web_svg_html(ExportWebSvgHtmlTask::default());
+                    self.add_web_svg_html(ExportWebSvgHtmlTask {
+                        options: args.export.svg_options(),
+                        ..ExportWebSvgHtmlTask::default()
+                    });
                 }
                 #[cfg(feature = "svg")]
                 "sir" | "vector" => {
@@ -148,13 +172,38 @@ impl ReflexoTaskBuilder {
                 "text" => {
                     self.add_text(ExportTextTask::default());
                 }
+                #[cfg(feature = "text")]
+                "text-json" => {
+                    self.add_text_json(ExportTextJsonTask::default());
+                }
+                #[cfg(feature = "png")]
+                "png" => {
+                    self.add_png(ExportWebPngTask {
+                        ppi: args.export.ppi,
+                        pages: args.export.pages.clone(),
+                        ..ExportWebPngTask::default()
+                    });
+                }
                 format => exit_by_unknown_format(format),
             }
         }
 
         // todo: dynamic layout of other formats
         if args.dynamic_layout {
-            self.add_dyn_svg_module(ExportDynSvgModuleTask::default());
+            let mut config = ExportDynSvgModuleTask::default();
+            for (key, values) in args.layout_axes.iter() {
+                let values = values.iter().map(|value| value.as_str().into()).collect();
+                config.add_layout_axis(key.as_str().into(), values);
+            }
+            if let Some((min, max)) = args.adaptive_layout_widths {
+                config.set_adaptive_widths(Some(AdaptiveLayoutWidths {
+                    min: TypstAbs::pt(min),
+                    max: TypstAbs::pt(max),
+                    budget: args.layout_budget,
+                    ..AdaptiveLayoutWidths::default()
+                }));
+            }
+            self.add_dyn_svg_module(config);
         }
 
         self
@@ -165,7 +214,7 @@ impl ReflexoTaskBuilder {
         self
     }
 
-    pub fn add_pdf(&mut self, config: ExportPdfTask) -> &mut Self {
+    pub fn add_pdf(&mut self, config: ExportPdfOutputTask) -> &mut Self {
         self.tasks.push(ReflexoTask::Pdf(config));
         self
     }
@@ -200,8 +249,24 @@ impl ReflexoTaskBuilder {
         self
     }
 
-    pub fn build(self) -> DynSystemComputation {
-        prepare_exporters_impl(self.diag_handler, self.output_path, self.tasks)
+    pub fn add_text_json(&mut self, config: ExportTextJsonTask) -> &mut Self {
+        self.tasks.push(ReflexoTask::TextJson(config));
+        self
+    }
+
+    pub fn add_png(&mut self, config: ExportWebPngTask) -> &mut Self {
+        self.tasks.push(ReflexoTask::Png(config));
+        self
+    }
+
+    pub fn build(self) -> DynExportComputation {
+        prepare_exporters_impl(
+            self.diag_handler,
+            self.output_path,
+            self.page_template,
+            self.summary_path,
+            self.tasks,
+        )
     }
 
     pub fn set_output_path(&mut self, output_path: PathBuf) {
@@ -214,35 +279,179 @@ impl ReflexoTaskBuilder {
     }
 }
 
+/// Runs the export tasks on a compilation, returning the outcome of each task.
+///
+/// The failures of the export tasks are reported in the summary, while the
+/// error is only returned if the compilation cannot be checked at all.
+pub type DynExportComputation =
+    Arc<dyn Fn(&Arc<WorldComputeGraph<SystemCompilerFeat>>) -> Result<ExportSummary> + Send + Sync>;
+
+/// The status of an export task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ExportStatus {
+    /// The outputs are written.
+    Success,
+    /// The document is not available, e.g. due to compile errors.
+    Skipped,
+    /// The export or the write of the outputs failed.
+    Failed,
+}
+
+/// A file written by an export task.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportOutput {
+    pub path: PathBuf,
+    /// The size of the file in bytes.
+    pub size: usize,
+}
+
+/// The outcome of an export task.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportOutcome {
+    /// The format of the task, e.g. `pdf`.
+    pub format: &'static str,
+    pub status: ExportStatus,
+    pub outputs: Vec<ExportOutput>,
+    /// The time spent on the task in milliseconds, excluding the compilation
+    /// of the document.
+    pub duration_ms: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// The outcomes of the export tasks of a compilation.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ExportSummary {
+    pub tasks: Vec<ExportOutcome>,
+}
+
+impl ExportSummary {
+    fn push(
+        &mut self,
+        format: &'static str,
+        result: Result<Option<Vec<ExportOutput>>>,
+        elapsed: Duration,
+    ) {
+        let (status, outputs, error) = match result {
+            Ok(Some(outputs)) => (ExportStatus::Success, outputs, None),
+            Ok(None) => (ExportStatus::Skipped, vec![], None),
+            Err(err) => (ExportStatus::Failed, vec![], Some(err.to_string())),
+        };
+
+        self.tasks.push(ExportOutcome {
+            format,
+            status,
+            outputs,
+            duration_ms: elapsed.as_secs_f64() * 1000.0,
+            error,
+        });
+    }
+
+    /// Iterates the failed tasks.
+    pub fn failures(&self) -> impl Iterator<Item = &ExportOutcome> {
+        self.tasks
+            .iter()
+            .filter(|t| t.status == ExportStatus::Failed)
+    }
+
+    /// Whether none of the tasks failed.
+    pub fn is_success(&self) -> bool {
+        self.failures().next().is_none()
+    }
+
+    /// Writes the summary as JSON to the path, or to stdout if the path is
+    /// `-`.
+    fn write_to(&self, path: &Path) -> Result<()> {
+        let summary = serde_json::to_string_pretty(self).context("failed to serialize summary")?;
+        if path == Path::new("-") {
+            println!("{summary}");
+            return Ok(());
+        }
+
+        std::fs::write(path, summary)
+            .map_err(|err| error_once!("failed to write summary", path: path.display(), err: err))
+    }
+}
+
 /// With the given arguments, prepare exporters for the compilation.
 fn prepare_exporters_impl(
     diag_handler: DiagnosticHandler,
     out: PathBuf,
+    page_template: Option<String>,
+    summary_path: Option<PathBuf>,
     tasks: Vec<ReflexoTask>,
-) -> DynSystemComputation {
+) -> DynExportComputation {
     type EF = DefaultExportFeature;
 
-    fn export_to_path(result: Result<Option<Bytes>>, output_path: PathBuf) {
-        let result = match result {
-            Ok(Some(bytes)) => bytes,
-            Ok(None) => return,
-            Err(err) => {
-                eprintln!("export failed: {err}");
-                return;
-            }
+    fn write_to_path(bytes: &Bytes, output_path: PathBuf) -> Result<ExportOutput> {
+        if let Err(err) = std::fs::write(&output_path, bytes.as_slice()) {
+            let path = output_path.display();
+            return Err(error_once!("failed to write output", path: path, err: err));
+        }
+
+        Ok(ExportOutput {
+            path: output_path,
+            size: bytes.len(),
+        })
+    }
+
+    fn export_to_path(
+        result: Result<Option<Bytes>>,
+        output_path: PathBuf,
+    ) -> Result<Option<Vec<ExportOutput>>> {
+        let Some(bytes) = result? else {
+            return Ok(None);
+        };
+
+        Ok(Some(vec![write_to_path(&bytes, output_path)?]))
+    }
+
+    /// Writes each page to a path created from the page template.
+    #[cfg(feature = "png")]
+    fn export_pages_to_path(
+        result: Result<Option<WebPngPages>>,
+        output_path: &Path,
+        template: Option<&str>,
+        total: usize,
+        extension: &str,
+    ) -> Result<Option<Vec<ExportOutput>>> {
+        let Some(pages) = result? else {
+            return Ok(None);
+        };
+
+        let stem = output_path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "main".to_owned());
+        let template = match template {
+            Some(template) => template.to_owned(),
+            None if pages.len() > 1 => format!("{stem}-{{n}}"),
+            None => stem,
         };
+        if pages.len() > 1 && !template.contains("{n}") {
+            return Err(error_once!(
+                "page template must contain the page number to export multiple pages",
+                template: template
+            ));
+        }
 
-        let err = std::fs::write(output_path, result.as_slice());
-        if let Err(err) = err {
-            eprintln!("export failed: {err}");
+        let mut outputs = Vec::with_capacity(pages.len());
+        for (idx, data) in pages {
+            let file_name = template
+                .replace("{n}", &(idx + 1).to_string())
+                .replace("{t}", &total.to_string());
+            let page_path = output_path.with_file_name(format!("{file_name}.{extension}"));
+            outputs.push(write_to_path(&data, page_path)?);
         }
+
+        Ok(Some(outputs))
     }
 
     fn compile_it<D: typst::Document + Send + Sync + 'static>(
         graph: &Arc<WorldComputeGraph<SystemCompilerFeat>>,
     ) -> Result<Option<Arc<D>>> {
-        let _ = graph.provide::<FlagTask<CompilationTask<D>>>(Ok(FlagTask::flag(true)));
-        graph.compute::<OptionDocumentTask<D>>().map(Arc::take)
+        MetricsRecorder::compile(graph)
     }
 
     fn export_bytes<
@@ -278,59 +487,106 @@ fn prepare_exporters_impl(
 
         diag_handler.status(&CompileReport::Stage(main, "compiling", start));
 
+        let cancel = CancelToken::of(graph);
+        let recorder = MetricsRecorder::of(graph);
+        let mut summary = ExportSummary::default();
         for task in tasks.iter() {
+            // Skips the stale exports, since a newer revision supersedes the compilation.
+            if cancel.is_cancelled() {
+                return Ok(summary);
+            }
+
             use ReflexoTask::*;
+            // Compiles the document ahead, so the time of the first export of each
+            // document excludes the compile time, which is recorded on its own.
             match task {
+                Ast(..) | DynSvgModule(..) => {}
+                Html(..) => {
+                    let _ = compile_it::<TypstHtmlDocument>(graph);
+                }
+                _ => {
+                    let _ = compile_it::<TypstPagedDocument>(graph);
+                }
+            }
+            let task_start = Instant::now();
+            let (format, result) = match task {
                 #[cfg(feature = "ast")]
                 Ast(_config) => {
                     let output_path = out.with_extension("ast.ansi.text");
                     let result = AstExport::compute(graph);
-                    export_to_path(result, output_path);
+                    ("ast", export_to_path(result, output_path))
                 }
                 #[cfg(feature = "pdf")]
                 Pdf(config) => {
                     let output_path = out.with_extension("pdf");
-                    let result = export_bytes::<_, PdfExport>(graph, config);
-                    export_to_path(result, output_path);
+                    let result = export_bytes::<_, PdfOutputExport>(graph, config);
+                    ("pdf", export_to_path(result, output_path))
                 }
                 #[cfg(feature = "html")]
                 Html(config) => {
                     let output_path = out.with_extension("html");
                     let result = export_string::<_, HtmlExport>(graph, config);
-                    export_to_path(result, output_path);
+                    ("html", export_to_path(result, output_path))
                 }
                 #[cfg(feature = "svg")]
                 WebSvg(config) => {
                     let output_path = out.with_extension("artifact.svg");
                     let result = export_string::<_, WebSvgExport<EF>>(graph, config);
-                    export_to_path(result, output_path);
+                    ("svg", export_to_path(result, output_path))
                 }
                 #[cfg(feature = "svg")]
                 WebSvgHtml(config) => {
                     let output_path = out.with_extension("artifact.svg.html");
                     let result = export_string::<_, WebSvgHtmlExport<EF>>(graph, config);
-                    export_to_path(result, output_path);
+                    ("svg_html", export_to_path(result, output_path))
                 }
                 #[cfg(feature = "svg")]
                 WebSvgModule(config) => {
                     let output_path = out.with_extension("artifact.sir.in");
                     let result = export_bytes::<_, WebSvgModuleExport<EF>>(graph, config);
-                    export_to_path(result, output_path);
+                    ("vector", export_to_path(result, output_path))
                 }
                 #[cfg(feature = "svg")]
                 DynSvgModule(config) => {
                     let output_path = out.with_extension("multi.sir.in");
                     let result = DynSvgModuleExport::run(graph, config);
                     let result = result.map(|d| d.map(|d| Bytes::new(d.to_bytes())));
-                    export_to_path(result, output_path);
+                    ("dyn-svg", export_to_path(result, output_path))
                 }
                 #[cfg(feature = "text")]
                 Text(config) => {
                     let output_path = out.with_extension("txt");
                     let result = export_string::<_, TextExport>(graph, config);
-                    export_to_path(result, output_path);
+                    ("text", export_to_path(result, output_path))
                 }
-            }
+                #[cfg(feature = "text")]
+                TextJson(config) => {
+                    let output_path = out.with_extension("text.json");
+                    let result = export_string::<_, TextJsonExport>(graph, config);
+                    ("text-json", export_to_path(result, output_path))
+                }
+                #[cfg(feature = "png")]
+                Png(config) => {
+                    let doc = compile_it::<TypstPagedDocument>(graph);
+                    let total = doc.as_ref().ok().and_then(Option::as_ref);
+                    let total = total.map_or(0, |doc| doc.pages.len());
+                    let result = doc.and_then(|doc| {
+                        let res = doc.map(|doc| WebPngExport::run(graph, &doc, config));
+                        res.transpose()
+                    });
+                    let template = page_template.as_deref();
+                    (
+                        "png",
+                        export_pages_to_path(result, &out, template, total, "png"),
+                    )
+                }
+            };
+            let elapsed = task_start.elapsed();
+            recorder.record_export(format, elapsed);
+            summary.push(format, result, elapsed);
+        }
+        if cancel.is_cancelled() {
+            return Ok(summary);
         }
 
         let _ = graph.provide::<FlagTask<PagedCompilationTask>>(Ok(FlagTask::flag(false)));
@@ -353,13 +609,66 @@ fn prepare_exporters_impl(
         // todo: export diagnostics.
         diag_handler.report(&graph.snap.world, diag.diagnostics());
 
-        Ok(())
+        for failure in summary.failures() {
+            let err = failure.error.as_deref().unwrap_or_default();
+            eprintln!("export failed ({}): {err}", failure.format);
+        }
+        if let Some(summary_path) = &summary_path {
+            summary.write_to(summary_path)?;
+        }
+
+        Ok(summary)
     })
 }
 
 /// Prepare exporters from command line arguments.
-pub fn prepare_exporters(args: &CompileArgs, entry_file: Option<&Path>) -> DynSystemComputation {
+pub fn prepare_exporters(args: &CompileArgs, entry_file: Option<&Path>) -> DynExportComputation {
     let mut tb = ReflexoTaskBuilder::new();
     tb.args(args, entry_file);
     tb.build()
 }
+
+#[cfg(all(test, feature = "text"))]
+mod tests {
+    use reflexo_typst::config::{entry::EntryOpts, CompileOpts};
+    use reflexo_typst::TypstSystemUniverse;
+
+    use super::*;
+
+    #[test]
+    fn test_export_time_excludes_compile_time() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let verse = TypstSystemUniverse::new(CompileOpts {
+            entry: EntryOpts::new_workspace(root.into()),
+            no_system_fonts: true,
+            ..CompileOpts::default()
+        })
+        .unwrap()
+        .with_entry_file(root.join("main.typ"));
+
+        // The document takes much longer to compile than to export as text.
+        let content = "#range(200000).fold(0, (acc, x) => acc + x)";
+        let snap = verse.snapshot_with_entry_content(Bytes::from_string(content.to_owned()), None);
+        let graph = WorldComputeGraph::new(snap);
+        let recorder = MetricsRecorder::default();
+        recorder.provide(&graph);
+
+        let output_path = std::env::temp_dir().join("typst-ts-cli-export-time");
+        let mut tb = ReflexoTaskBuilder::new();
+        tb.set_output_path(output_path.clone());
+        tb.add_text(ExportTextTask::default());
+        let summary = tb.build()(&graph).unwrap();
+        let _ = std::fs::remove_file(output_path.with_extension("txt"));
+        assert!(summary.is_success(), "{summary:?}");
+
+        let (compile, exports) = recorder.take();
+        let compile_ms = compile.as_secs_f64() * 1000.0;
+        assert_eq!(exports.len(), 1);
+        assert!(
+            exports[0].duration_ms < compile_ms,
+            "export {}ms, compile {compile_ms}ms",
+            exports[0].duration_ms
+        );
+        assert!(summary.tasks[0].duration_ms < compile_ms);
+    }
+}
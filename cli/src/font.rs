@@ -1,3 +1,16 @@
+use std::{borrow::Cow, path::PathBuf};
+
+use reflexo_typst::config::{entry::EntryOpts, CompileOpts};
+use reflexo_typst::debug_loc::{DataSource, FsDataSource};
+use reflexo_typst::error::prelude::*;
+use reflexo_typst::font::FontResolver;
+use reflexo_typst::hash::hash32;
+use reflexo_typst::TypstSystemUniverse;
+use serde::Serialize;
+use typst::text::{Font, FontInfo};
+
+use crate::MeasureFontsArgs;
+
 #[cfg(feature = "embedded-fonts")]
 pub fn fonts() -> impl Iterator<Item = &'static [u8]> {
     typst_assets::fonts()
@@ -8,3 +21,112 @@ pub fn fonts() -> impl Iterator<Item = &'static [u8]> {
     static EMBEDDED_FONT: &[&[u8]] = &[];
     EMBEDDED_FONT.iter().copied()
 }
+
+/// A measured font face, which is an entry of the font manifest.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MeasuredFont {
+    /// The font info, the same as the one returned by `get_font_info` in the
+    /// web compiler. It carries the family, variant and coverage of the face.
+    pub info: FontInfo,
+    /// The number of font units per em.
+    pub units_per_em: f64,
+    /// The index of the face in its font file, which is only nonzero for
+    /// font collections (e.g. `.ttc`). It is not an index into the font book.
+    pub collection_index: u32,
+    /// The hash referring the face in the vector format, i.e. `FontRef::hash`
+    /// before collision resolution.
+    pub hash: u32,
+    /// The path to the font file, or `None` for a font loaded from memory,
+    /// e.g. an embedded font.
+    pub path: Option<PathBuf>,
+}
+
+impl MeasuredFont {
+    fn new(font: &Font, path: Option<PathBuf>) -> Self {
+        Self {
+            info: font.info().clone(),
+            units_per_em: font.units_per_em(),
+            collection_index: font.index(),
+            hash: hash32(font),
+            path,
+        }
+    }
+}
+
+/// Measures every face discovered in the embedded fonts, the custom font
+/// paths and (optionally) the system font paths.
+pub fn measure_fonts(args: &MeasureFontsArgs) -> Result<Vec<MeasuredFont>> {
+    let verse = TypstSystemUniverse::new(CompileOpts {
+        // todo: should cover default workspace path
+        entry: EntryOpts::new_workspace(PathBuf::from("-").as_path().into()),
+        font_paths: args.font.paths.clone(),
+        no_system_fonts: args.no_system_fonts,
+        with_embedded_fonts: fonts().map(Cow::Borrowed).collect(),
+        ..CompileOpts::default()
+    })?;
+
+    Ok(measure_resolver(verse.font_resolver.as_ref()))
+}
+
+/// Measures every face known by the font resolver, in the order of the font
+/// book.
+fn measure_resolver(resolver: &impl FontResolver) -> Vec<MeasuredFont> {
+    let mut measured = Vec::new();
+
+    let book = resolver.font_book();
+    for idx in (0..).take_while(|&idx| book.info(idx).is_some()) {
+        let Some(font) = resolver.font(idx) else {
+            log::warn!("failed to load font face {idx}: {:?}", book.info(idx));
+            continue;
+        };
+
+        let path = match resolver.slot(idx).and_then(|slot| slot.description()) {
+            Some(DataSource::Fs(FsDataSource { path })) => Some(PathBuf::from(path)),
+            _ => None,
+        };
+        measured.push(MeasuredFont::new(&font, path));
+    }
+
+    measured
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn measure_embedded() -> Vec<MeasuredFont> {
+        measure_fonts(&MeasureFontsArgs {
+            font: Default::default(),
+            output: PathBuf::from("fonts.json"),
+            no_system_fonts: true,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_measure_embedded_fonts() {
+        let measured = measure_embedded();
+        assert_eq!(measured.is_empty(), fonts().next().is_none());
+
+        for font in &measured {
+            assert_eq!(font.path, None);
+            assert!(font.units_per_em > 0.);
+        }
+    }
+
+    #[cfg(feature = "embedded-fonts")]
+    #[test]
+    fn test_font_manifest_entry() {
+        let measured = measure_embedded();
+        let font = &measured[0];
+        let entry = serde_json::to_value(font).unwrap();
+
+        assert_eq!(entry["info"]["family"], font.info.family.as_str());
+        assert_eq!(entry["unitsPerEm"], font.units_per_em);
+        assert_eq!(entry["collectionIndex"], font.collection_index);
+        assert_eq!(entry["hash"], font.hash);
+        assert!(entry["path"].is_null());
+        assert!(entry.get("family").is_none());
+    }
+}
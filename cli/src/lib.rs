@@ -11,11 +11,15 @@ pub mod version;
 use core::fmt;
 use std::{
     borrow::Cow,
+    num::NonZeroUsize,
     path::{Path, PathBuf},
     sync::OnceLock,
 };
 
 use clap::{builder::ValueParser, ArgAction, Args, Command, Parser, Subcommand, ValueEnum};
+use reflexo_typst::diag::StructuredDiagnosticFormat;
+use reflexo_typst::svg::SvgExportOptions;
+use reflexo_typst::task::{Pages, PdfStandard};
 use reflexo_typst::{
     build_info::VERSION, vfs::WorkspaceResolver, DiagnosticHandler, ImmutPath, TypstFileId,
     MEMORY_MAIN_ENTRY,
@@ -90,6 +94,8 @@ pub enum Subcommands {
 pub enum FontSubCommands {
     /// List all discovered fonts in system and custom font paths
     List(ListFontsArgs),
+    /// Measure all discovered fonts and write a font manifest
+    Measure(MeasureFontsArgs),
 }
 
 #[derive(Debug, Subcommand)]
@@ -259,6 +265,37 @@ fn parse_input_pair(raw: &str) -> Result<(String, String), String> {
     Ok((key, val))
 }
 
+/// Parses a range of widths in pt in the form of `min-max`.
+fn parse_width_range(raw: &str) -> Result<(f64, f64), String> {
+    let (min, max) = raw
+        .split_once('-')
+        .ok_or("widths must be a minimum and a maximum separated by a dash")?;
+    let parse_width = |value: &str| {
+        let value = value.trim();
+        match value.parse::<f64>() {
+            Ok(width) if width.is_finite() && width > 0. => Ok(width),
+            _ => Err(format!("invalid width {value:?}")),
+        }
+    };
+    let (min, max) = (parse_width(min)?, parse_width(max)?);
+    if min > max {
+        return Err(format!("width range {min}-{max} is empty"));
+    }
+    Ok((min, max))
+}
+
+/// Parses a dimension of the dynamic layouts in the form of
+/// `key=value1,value2`.
+fn parse_layout_axis(raw: &str) -> Result<(String, Vec<String>), String> {
+    let (key, values) = parse_input_pair(raw)?;
+    let values = values.split(',').map(|value| value.trim().to_owned());
+    let values = values.filter(|value| !value.is_empty()).collect::<Vec<_>>();
+    if values.is_empty() {
+        return Err("the values were missing or empty".to_owned());
+    }
+    Ok((key, values))
+}
+
 #[derive(Default, Debug, Clone, Parser)]
 #[clap(next_help_heading = "Export options")]
 pub struct ExportArgs {
@@ -271,6 +308,139 @@ pub struct ExportArgs {
         value_name = "UNIX_TIMESTAMP"
     )]
     pub creation_timestamp: Option<i64>,
+
+    /// The resolution to render raster formats (`png`) with, in pixels per
+    /// inch.
+    #[clap(long = "ppi", default_value_t = 144.0)]
+    pub ppi: f32,
+
+    /// Which pages to export (`pdf`, `png`). When unspecified, all pages are
+    /// exported.
+    ///
+    /// Pages to export are separated by commas, and can be either simple page
+    /// numbers (e.g. `2,5` to export only pages 2 and 5) or page ranges (e.g.
+    /// `2,3-6,8-` to export page 2, pages 3 to 6 (inclusive), page 8 and any
+    /// pages after it).
+    #[clap(
+        long = "pages",
+        value_delimiter = ',',
+        value_parser = ValueParser::new(parse_page_range),
+    )]
+    pub pages: Option<Vec<Pages>>,
+
+    /// The file name template for formats exporting one file per page
+    /// (`png`), without the extension.
+    ///
+    /// `{n}` is replaced with the page number and `{t}` with the total number
+    /// of pages in the document. By default, it is the name of the entry file
+    /// followed by `-{n}` if multiple pages are exported.
+    #[clap(long = "page-template", value_name = "TEMPLATE")]
+    pub page_template: Option<String>,
+
+    /// Omits the script for interactive and responsive actions in the
+    /// exported svg (`svg_html`).
+    #[clap(long = "svg-no-js")]
+    pub svg_no_js: bool,
+
+    /// Omits the builtin css in the exported svg (`svg`, `svg_html`).
+    #[clap(long = "svg-no-css")]
+    pub svg_no_css: bool,
+
+    /// Omits the selectable and searchable text elements in the exported svg
+    /// (`svg`, `svg_html`).
+    #[clap(long = "svg-no-text")]
+    pub svg_no_text: bool,
+
+    /// Renders the text as images in the exported svg (`svg`, `svg_html`).
+    #[clap(long = "svg-rasterize-text")]
+    pub svg_rasterize_text: bool,
+
+    /// Uses the glyph ids allocated in the order of occurrence instead of the
+    /// stable ones hashed from the glyphs in the exported svg (`svg`,
+    /// `svg_html`).
+    #[clap(long = "svg-no-stable-glyph-id")]
+    pub svg_no_stable_glyph_id: bool,
+
+    /// One (or multiple comma-separated) PDF standards that the exported PDF
+    /// will enforce conformance with, e.g. `--pdf-standard 1.7,a-2b`.
+    #[clap(
+        long = "pdf-standard",
+        value_delimiter = ',',
+        action = ArgAction::Append,
+        value_parser = ValueParser::new(parse_pdf_standard),
+    )]
+    pub pdf_standards: Vec<PdfStandard>,
+
+    /// Omits the tags for accessibility in the exported PDF. A tagged PDF is
+    /// exported by default.
+    #[clap(long = "no-pdf-tags")]
+    pub no_pdf_tags: bool,
+
+    /// The identifier of the exported PDF, which should stay the same across
+    /// the revisions of the document. By default, it is derived from the
+    /// title and the authors of the document.
+    #[clap(long = "pdf-ident", value_name = "IDENT")]
+    pub pdf_ident: Option<String>,
+
+    /// Writes a JSON summary of the export tasks, listing the status, output
+    /// paths, sizes and durations of each task, to the given path or to
+    /// stdout if it is `-`.
+    #[clap(long = "export-summary", value_name = "PATH")]
+    pub export_summary: Option<PathBuf>,
+}
+
+impl ExportArgs {
+    /// Gets the runtime options of svg export.
+    pub fn svg_options(&self) -> SvgExportOptions {
+        SvgExportOptions {
+            render_text_element: self.svg_no_text.then_some(false),
+            builtin_css: self.svg_no_css.then_some(false),
+            responsive_js: self.svg_no_js.then_some(false),
+            rasterize_text: self.svg_rasterize_text.then_some(true),
+            stable_glyph_id: self.svg_no_stable_glyph_id.then_some(false),
+        }
+    }
+}
+
+/// Parses a PDF standard, e.g. `1.7` or `a-2b`.
+fn parse_pdf_standard(raw: &str) -> Result<PdfStandard, String> {
+    let value = serde_json::Value::String(raw.trim().to_owned());
+    serde_json::from_value(value).map_err(|err| format!("unknown pdf standard {raw:?}: {err}"))
+}
+
+/// Parses a page number (e.g. `2`) or an inclusive page range (e.g. `3-6`,
+/// `-3` or `8-`).
+fn parse_page_range(raw: &str) -> Result<Pages, String> {
+    fn parse_page_number(value: &str) -> Result<Option<NonZeroUsize>, String> {
+        let value = value.trim();
+        if value.is_empty() {
+            return Ok(None);
+        }
+        match value.parse::<usize>() {
+            Ok(0) => Err("page numbers start at one".to_owned()),
+            Ok(number) => Ok(NonZeroUsize::new(number)),
+            Err(err) => Err(format!("invalid page number {value:?}: {err}")),
+        }
+    }
+
+    let (start, end) = match raw.split_once('-') {
+        Some((start, end)) => (parse_page_number(start)?, parse_page_number(end)?),
+        None => {
+            let page = parse_page_number(raw)?;
+            if page.is_none() {
+                return Err("page number was missing or empty".to_owned());
+            }
+            (page, page)
+        }
+    };
+
+    if let (Some(start), Some(end)) = (start, end) {
+        if start > end {
+            return Err(format!("page range {start}-{end} is empty"));
+        }
+    }
+
+    Ok(Pages(start..=end))
 }
 
 #[derive(Default, Debug, Clone, Parser)]
@@ -287,30 +457,77 @@ pub struct CompileArgs {
     #[clap(long)]
     pub watch: bool,
 
+    /// Writes the metrics of the compilations in watch mode to the file, e.g.
+    /// the time waiting in the queue, compiling and exporting.
+    #[clap(long, value_name = "PATH", requires = "watch")]
+    pub metrics: Option<PathBuf>,
+
+    /// The format of the metrics file. The `json` file gets a line per
+    /// compilation or cache eviction, while the `prometheus` file is
+    /// rewritten with the aggregated metrics after each of them.
+    #[clap(long, default_value_t = MetricsFormat::Json, requires = "metrics")]
+    pub metrics_format: MetricsFormat,
+
     /// Generates dynamic layout representation.
     /// Note: this is an experimental feature and will be merged as
     ///   format `dyn-svg` in the future.
     #[clap(long)]
     pub dynamic_layout: bool,
 
-    /// Outputs format(s), possible values: `ast`, `pdf`, `svg`, and,
-    /// `svg_html`.
+    /// Adds a dimension of the dynamic layouts, along which the document is
+    /// compiled once per value of `sys.inputs.<key>`, e.g.
+    /// `--layout-axis x-target=web-light,web-dark`.
+    #[clap(
+        long = "layout-axis",
+        value_name = "key=value,..",
+        action = ArgAction::Append,
+        requires = "dynamic_layout",
+        value_parser = ValueParser::new(parse_layout_axis),
+    )]
+    pub layout_axes: Vec<(String, Vec<String>)>,
+
+    /// Discovers the widths of the dynamic layouts by bisecting between the
+    /// minimum and maximum widths in pt, e.g. `--adaptive-layout-widths
+    /// 360-750`, keeping only the widths at which the layout changes.
+    #[clap(
+        long,
+        value_name = "min-max",
+        requires = "dynamic_layout",
+        value_parser = ValueParser::new(parse_width_range),
+    )]
+    pub adaptive_layout_widths: Option<(f64, f64)>,
+
+    /// The maximum number of compilations to discover the widths of the
+    /// dynamic layouts, per combination of the layout axes.
+    #[clap(long, default_value_t = 40, requires = "adaptive_layout_widths")]
+    pub layout_budget: usize,
+
+    /// Outputs format(s), possible values: `ast`, `pdf`, `svg`, `svg_html`,
+    /// `text`, `text-json`, and, `png`.
     #[clap(long)]
     pub format: Vec<String>,
 
-    /// The format to emit diagnostics in
+    /// The format to emit diagnostics in. The `json` and `sarif` formats are
+    /// printed to stderr, or written to `--diagnostic-output`.
     #[clap(
         long,
         default_value_t = DiagnosticFormat::Human,
         value_parser = clap::value_parser!(DiagnosticFormat)
     )]
     pub diagnostic_format: DiagnosticFormat,
+
+    /// Writes the diagnostics in the `json` or `sarif` format to the given
+    /// path instead of stderr. The file is rewritten on each compilation.
+    #[clap(long, value_name = "PATH")]
+    pub diagnostic_output: Option<PathBuf>,
 }
 
 impl CompileArgs {
     pub fn diagnostics_handler(&self) -> DiagnosticHandler {
         DiagnosticHandler {
             diagnostic_format: self.diagnostic_format.into(),
+            structured_format: self.diagnostic_format.structured(),
+            diagnostic_output: self.diagnostic_output.clone(),
             print_compile_status: self.watch,
         }
     }
@@ -344,6 +561,42 @@ pub struct QueryArgs {
     /// Expect and retrieve exactly one element
     #[clap(long = "one", default_value = "false")]
     pub one: bool,
+
+    /// The document target to query against
+    #[clap(long = "target", value_enum, default_value_t = QueryTarget::Paged)]
+    pub target: QueryTarget,
+
+    /// The format to serialize the query result in, which is distinct from
+    /// the export formats given by `--format`
+    #[clap(
+        long = "output-format",
+        value_enum,
+        default_value_t = SerializationFormat::Json
+    )]
+    pub output_format: SerializationFormat,
+
+    /// Whether to pretty-print the serialized output
+    #[clap(long = "pretty", default_value_t = true, action = ArgAction::Set)]
+    pub pretty: bool,
+}
+
+/// The document target to query against.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, ValueEnum)]
+pub enum QueryTarget {
+    /// Queries the paged document.
+    Paged,
+    /// Queries the html document.
+    Html,
+}
+
+/// The format to serialize the query result in.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, ValueEnum)]
+pub enum SerializationFormat {
+    Json,
+    Yaml,
+    Toml,
+    /// Serializes each retrieved element as a json object in a single line.
+    JsonLines,
 }
 
 /// TODO: Repl Doc
@@ -446,12 +699,30 @@ pub struct GenPackagesDocArgs {
 pub enum DiagnosticFormat {
     Human,
     Short,
+    /// One JSON object per diagnostic per line, printed to stderr.
+    Json,
+    /// A SARIF 2.1.0 log per compilation, printed to stderr.
+    Sarif,
+}
+
+impl DiagnosticFormat {
+    /// Gets the machine-readable format, if any.
+    pub fn structured(self) -> Option<StructuredDiagnosticFormat> {
+        match self {
+            DiagnosticFormat::Human | DiagnosticFormat::Short => None,
+            DiagnosticFormat::Json => Some(StructuredDiagnosticFormat::Json),
+            DiagnosticFormat::Sarif => Some(StructuredDiagnosticFormat::Sarif),
+        }
+    }
 }
 
 impl From<DiagnosticFormat> for reflexo_typst::DiagnosticFormat {
     fn from(fmt: DiagnosticFormat) -> Self {
         match fmt {
-            DiagnosticFormat::Human => Self::Human,
+            // The machine-readable formats are printed by the handler instead.
+            DiagnosticFormat::Human | DiagnosticFormat::Json | DiagnosticFormat::Sarif => {
+                Self::Human
+            }
             DiagnosticFormat::Short => Self::Short,
         }
     }
@@ -472,7 +743,86 @@ impl fmt::Display for DiagnosticFormat {
     }
 }
 
+/// Which format to write the metrics in.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, ValueEnum)]
+pub enum MetricsFormat {
+    /// JSON lines.
+    #[default]
+    Json,
+    /// The Prometheus text format.
+    Prometheus,
+}
+
+impl fmt::Display for MetricsFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.to_possible_value()
+            .expect("no values are skipped")
+            .get_name()
+            .fmt(f)
+    }
+}
+
 pub fn get_cli(sub_command_required: bool) -> Command {
     let cli = Command::new("$").disable_version_flag(true);
     Opts::augment_args(cli).subcommand_required(sub_command_required)
 }
+
+#[cfg(test)]
+mod tests {
+    use clap::FromArgMatches;
+
+    use super::*;
+
+    fn page(number: usize) -> Option<NonZeroUsize> {
+        NonZeroUsize::new(number)
+    }
+
+    #[test]
+    fn test_parse_page_range() {
+        assert_eq!(parse_page_range("2").unwrap().0, page(2)..=page(2));
+        assert_eq!(parse_page_range("3-6").unwrap().0, page(3)..=page(6));
+        assert_eq!(parse_page_range(" 4 - 4 ").unwrap().0, page(4)..=page(4));
+        assert_eq!(parse_page_range("-3").unwrap().0, None..=page(3));
+        assert_eq!(parse_page_range("8-").unwrap().0, page(8)..=None);
+        assert_eq!(parse_page_range("-").unwrap().0, None..=None);
+    }
+
+    #[test]
+    fn test_parse_invalid_page_range() {
+        assert!(parse_page_range("").is_err());
+        assert!(parse_page_range("0").is_err());
+        assert!(parse_page_range("0-3").is_err());
+        assert!(parse_page_range("6-3").is_err());
+        assert!(parse_page_range("a-3").is_err());
+        assert!(parse_page_range("1-2-3").is_err());
+    }
+
+    fn parse_query(args: &[&str]) -> QueryArgs {
+        let args = ["$", "query", "--entry", "main.typ", "--selector", "heading"]
+            .iter()
+            .chain(args);
+        let matches = get_cli(true).try_get_matches_from(args).unwrap();
+        match Opts::from_arg_matches(&matches).unwrap().sub {
+            Some(Subcommands::Query(args)) => args,
+            _ => panic!("expected a query command"),
+        }
+    }
+
+    #[test]
+    fn test_parse_query_formats() {
+        let args = parse_query(&[]);
+        assert_eq!(args.output_format, SerializationFormat::Json);
+        assert!(args.compile.format.is_empty());
+
+        let args = parse_query(&["--output-format", "json-lines", "--format", "json"]);
+        assert_eq!(args.output_format, SerializationFormat::JsonLines);
+        assert_eq!(args.compile.format, vec!["json".to_owned()]);
+    }
+
+    #[test]
+    fn test_parse_query_format_yaml() {
+        let args = parse_query(&["--output-format", "yaml", "--target", "html"]);
+        assert_eq!(args.output_format, SerializationFormat::Yaml);
+        assert_eq!(args.target, QueryTarget::Html);
+    }
+}
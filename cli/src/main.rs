@@ -13,10 +13,14 @@ use reflexo_typst::{
     config::{entry::EntryOpts, CompileOpts},
     SystemCompilerFeat, WorldComputeGraph,
 };
-use reflexo_typst::{error::prelude::*, OptionDocumentTask, TypstPagedDocument};
+use reflexo_typst::{
+    error::prelude::*, CompilationTask, DiagnosticsTask, FlagTask, HtmlCompilationTask,
+    OptionDocumentTask, PagedCompilationTask,
+};
 use typst::{text::FontVariant, World};
 use typst_assets::fonts;
 use typst_ts_cli::compile::compile_export;
+use typst_ts_cli::export::{DynExportComputation, ExportSummary};
 use typst_ts_cli::manual::generate_manual;
 use typst_ts_cli::query::serialize;
 use typst_ts_cli::utils::*;
@@ -63,6 +67,7 @@ fn main() {
         },
         Some(Subcommands::Font(font_sub)) => match font_sub {
             FontSubCommands::List(args) => list_fonts(args),
+            FontSubCommands::Measure(args) => measure_fonts(args),
         },
         Some(Subcommands::Package(pkg_sub)) => match pkg_sub {
             PackageSubCommands::List(args) => list_packages(args),
@@ -105,45 +110,69 @@ fn compile(args: CompileArgs) -> ! {
     compile_export(args, exporter)
 }
 
-/// Execute a query command.
+/// Execute a query command, which is re-run on each change with `--watch`.
 pub fn query(args: QueryArgs) -> ! {
-    use reflexo_typst::query::retrieve;
-    use typst_ts_cli::query::format;
+    let diag_handler = args.compile.diagnostics_handler();
     let compile_args = args.compile.clone();
 
-    let exporter = Arc::new(
-        move |g: &Arc<WorldComputeGraph<SystemCompilerFeat>>| -> Result<()> {
-            // todo: html query
-            let output = g
-                .compute::<OptionDocumentTask<TypstPagedDocument>>()?
-                .as_ref()
-                .clone()
-                .context("no document found")?;
-            let output = TypstDocument::Paged(output);
-
-            if args.selector == "document_title" {
-                let title = output
-                    .info()
-                    .title
-                    .as_ref()
-                    .map(|e| e.as_str())
-                    .unwrap_or("null");
-                let serialized = serialize(&title, "json").context("serialize query")?;
-                println!("{serialized}");
-                return Ok(());
+    let exporter: DynExportComputation = Arc::new(
+        move |graph: &Arc<WorldComputeGraph<SystemCompilerFeat>>| -> Result<ExportSummary> {
+            let res = query_graph(graph, &args);
+
+            let _ = graph.provide::<FlagTask<PagedCompilationTask>>(Ok(FlagTask::flag(false)));
+            let _ = graph.provide::<FlagTask<HtmlCompilationTask>>(Ok(FlagTask::flag(false)));
+            match graph.compute::<DiagnosticsTask>() {
+                Ok(diag) => diag_handler.report(&graph.snap.world, diag.diagnostics()),
+                Err(err) => eprintln!("failed to collect diagnostics: {err}"),
             }
 
-            let world = &g.snap.world;
-            let data = retrieve(world, &args.selector, &output).context("query")?;
-            let serialized = format(data, &args).context("serialize query")?;
-            println!("{serialized}");
-            Ok(())
+            println!("{}", res?);
+            Ok(ExportSummary::default())
         },
     );
 
     compile_export(compile_args, exporter)
 }
 
+/// Queries the document compiled in the graph and serializes the result.
+fn query_graph(
+    graph: &Arc<WorldComputeGraph<SystemCompilerFeat>>,
+    args: &QueryArgs,
+) -> Result<String> {
+    use reflexo_typst::query::retrieve;
+    use typst_ts_cli::query::format;
+
+    let output = match args.target {
+        QueryTarget::Paged => TypstDocument::Paged(query_document(graph)?),
+        QueryTarget::Html => TypstDocument::Html(query_document(graph)?),
+    };
+
+    if args.selector == "document_title" {
+        let title = output
+            .info()
+            .title
+            .as_ref()
+            .map(|e| e.as_str())
+            .unwrap_or("null");
+        return serialize(&title, args.output_format, args.pretty).context("serialize query");
+    }
+
+    let world = &graph.snap.world;
+    let data = retrieve(world, &args.selector, &output).context("query")?;
+    format(data, args).context("serialize query")
+}
+
+/// Compiles the document of the given target for query.
+fn query_document<D: typst::Document + Send + Sync + 'static>(
+    g: &Arc<WorldComputeGraph<SystemCompilerFeat>>,
+) -> Result<Arc<D>> {
+    let _ = g.provide::<FlagTask<CompilationTask<D>>>(Ok(FlagTask::flag(true)));
+    g.compute::<OptionDocumentTask<D>>()?
+        .as_ref()
+        .clone()
+        .context("no document found")
+}
+
 fn query_repl(args: QueryReplArgs) -> ! {
     use typst_ts_cli::query_repl::start_repl_test;
     let compile_args = args.compile.clone();
@@ -193,6 +222,19 @@ fn list_fonts(command: ListFontsArgs) -> ! {
     exit(0)
 }
 
+fn measure_fonts(args: MeasureFontsArgs) -> ! {
+    let measured = typst_ts_cli::font::measure_fonts(&args).unwrap_or_exit();
+    let manifest = serde_json::to_vec_pretty(&measured).unwrap_or_exit();
+    std::fs::write(&args.output, manifest).unwrap_or_exit();
+
+    eprintln!(
+        "measured {} font faces into {}",
+        measured.len(),
+        args.output.display()
+    );
+    exit(0)
+}
+
 fn list_packages(args: ListPackagesArgs) -> ! {
     fn get_string(v: &toml::Value) -> &str {
         match v {
@@ -4,7 +4,7 @@ use typst::{
     foundations::{Content, IntoValue},
 };
 
-use crate::QueryArgs;
+use crate::{QueryArgs, SerializationFormat};
 
 /// Format the query result in the output format.
 pub fn format(elements: Vec<Content>, command: &QueryArgs) -> StrResult<String> {
@@ -21,16 +21,55 @@ pub fn format(elements: Vec<Content>, command: &QueryArgs) -> StrResult<String>
         .collect();
 
     if command.one {
-        serialize(&mapped[0], "json")
-    } else {
-        serialize(&mapped, "json")
+        return serialize(&mapped[0], command.output_format, command.pretty);
+    }
+
+    match command.output_format {
+        // Each element takes a line, so that the output can be streamed.
+        SerializationFormat::JsonLines => {
+            let lines = mapped
+                .iter()
+                .map(|value| serialize(value, SerializationFormat::JsonLines, false))
+                .collect::<StrResult<Vec<_>>>()?;
+            Ok(lines.join("\n"))
+        }
+        format => serialize(&mapped, format, command.pretty),
     }
 }
 
 /// Serialize data to the output format.
-pub fn serialize(data: &impl Serialize, format: &str) -> StrResult<String> {
+pub fn serialize(
+    data: &impl Serialize,
+    format: SerializationFormat,
+    pretty: bool,
+) -> StrResult<String> {
     match format {
-        "json" => serde_json::to_string_pretty(data).map_err(|e| eco_format!("{e}")),
-        _ => bail!("unsupported serialization format: {}", format),
+        SerializationFormat::Json if pretty => {
+            serde_json::to_string_pretty(data).map_err(|e| eco_format!("{e}"))
+        }
+        SerializationFormat::Json | SerializationFormat::JsonLines => {
+            serde_json::to_string(data).map_err(|e| eco_format!("{e}"))
+        }
+        SerializationFormat::Yaml => serde_yaml::to_string(data).map_err(|e| eco_format!("{e}")),
+        SerializationFormat::Toml if pretty => {
+            toml::to_string_pretty(data).map_err(|e| eco_format!("{e}"))
+        }
+        SerializationFormat::Toml => toml::to_string(data).map_err(|e| eco_format!("{e}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serialize_formats() {
+        let data = vec![1, 2];
+        let json = serialize(&data, SerializationFormat::Json, false).unwrap();
+        assert_eq!(json, "[1,2]");
+        let yaml = serialize(&data, SerializationFormat::Yaml, true).unwrap();
+        assert_eq!(yaml, "- 1\n- 2\n");
+        // toml only accepts tables at the top level
+        assert!(serialize(&data, SerializationFormat::Toml, true).is_err());
     }
 }
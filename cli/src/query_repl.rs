@@ -18,7 +18,7 @@ use typst::World;
 use typst_ide::{autocomplete, IdeWorld};
 
 use crate::query::serialize;
-use crate::CompileOnceArgs;
+use crate::{CompileOnceArgs, SerializationFormat};
 
 #[derive(Helper, Validator)]
 struct ReplContext {
@@ -314,7 +314,7 @@ impl ReplContext {
         };
 
         if let Some(compiled) = compiled {
-            let serialized = serialize(&compiled, "json").unwrap();
+            let serialized = serialize(&compiled, SerializationFormat::Json, true).unwrap();
             println!("{serialized}");
         }
     }
@@ -29,6 +29,8 @@ pub struct HastRoot {
 #[serde(rename_all = "camelCase")]
 pub struct HastText {
     pub value: EcoString,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<HastPosition>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -41,6 +43,8 @@ pub struct HastElement {
     // todo: data
     #[serde(skip_serializing_if = "Option::is_none")]
     pub data: Option<HastElementData>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<HastPosition>,
 }
 
 pub type HastElementProperties = std::collections::BTreeMap<EcoString, EcoString>;
@@ -50,3 +54,34 @@ pub type HastElementProperties = std::collections::BTreeMap<EcoString, EcoString
 pub struct HastElementData {
     pub hash: Option<EcoString>,
 }
+
+/// The location of a node in the source files.
+///
+/// See [unist](https://github.com/syntax-tree/unist#position).
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HastPosition {
+    pub start: HastPoint,
+    pub end: HastPoint,
+    /// The path of the source file, prefixed with the package specifier if
+    /// the file is in a package, e.g. `@preview/example:0.1.0/lib.typ`.
+    ///
+    /// This is not a part of unist.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<EcoString>,
+}
+
+/// A place in the source file.
+///
+/// See [unist](https://github.com/syntax-tree/unist#point).
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct HastPoint {
+    /// The 1-indexed line.
+    pub line: usize,
+    /// The 1-indexed column, counted in characters.
+    pub column: usize,
+    /// The 0-indexed UTF-8 byte offset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<usize>,
+}
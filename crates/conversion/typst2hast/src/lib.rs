@@ -2,76 +2,355 @@ use std::sync::Arc;
 
 use base64::Engine;
 use ecow::{eco_format, EcoString};
+use reflexo::path::unix_slash;
 use reflexo::typst::TypstHtmlDocument;
+#[cfg(feature = "svg-frames")]
+use reflexo_vec2svg::SvgFrames;
+use serde::{Deserialize, Serialize};
 use typst::diag::SourceResult;
 use typst::layout::Frame;
 use typst::syntax::Span;
+use typst::World;
 use typst_html::{HtmlElement, HtmlNode};
 
-use crate::hast::{HastElement, HastElementContent, HastText};
+use crate::hast::{
+    HastElement, HastElementContent, HastElementData, HastPoint, HastPosition, HastText,
+};
 
 pub mod hast;
 
+/// How to encode the frames embedded in a HTML document, e.g. equations.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HtmlFrameMode {
+    /// Encodes each frame by `typst-svg`, as a self-contained inline `<svg>`
+    /// in HTML, or as an `<img>` with a base64 `data:` URL in hast.
+    #[default]
+    Svg,
+    /// Encodes each frame as an inline `<svg>` element. The glyphs are shared
+    /// across the frames by a hidden `<svg>` at the start of the `<body>`.
+    #[cfg(feature = "svg-frames")]
+    Inline,
+    /// Encodes each frame as an `<img>` referencing an external SVG file, at
+    /// the path given by [`SvgFrames::path`].
+    #[cfg(feature = "svg-frames")]
+    Files,
+}
+
+/// The options to encode an HTML document into a Hast.
+#[derive(Clone, Copy, Default)]
+pub struct HastOptions<'a> {
+    /// The world to resolve the source locations of the nodes, if enabled.
+    pub world: Option<&'a dyn World>,
+    /// How to encode the frames.
+    pub frames: HtmlFrameMode,
+    /// The frames rendered by `reflexo-vec2svg`, which are required by
+    /// [`HtmlFrameMode::Inline`]. The frames missing from them are encoded as
+    /// in [`HtmlFrameMode::Svg`].
+    #[cfg(feature = "svg-frames")]
+    pub svg_frames: Option<&'a SvgFrames>,
+}
+
 /// Encodes an HTML document into a Hast.
 pub fn hast(document: &Arc<TypstHtmlDocument>) -> SourceResult<HastElementContent> {
-    write_element(&document.root)
+    hast_with(document, HastOptions::default())
 }
 
-/// Encode an HTML node into the writer.
-fn write_node(node: &HtmlNode, buf: &mut Vec<HastElementContent>) -> SourceResult<()> {
-    match node {
-        HtmlNode::Tag(_) => {}
-        HtmlNode::Text(text, span) => {
-            buf.push(write_text(text, *span)?);
-        }
-        HtmlNode::Element(element) => {
-            buf.push(write_element(element)?);
-        }
-        HtmlNode::Frame(frame) => {
-            write_frame(&frame.inner, buf);
+/// Encodes an HTML document into a Hast, attaching the source locations of
+/// the elements and texts as their `position`.
+pub fn hast_with_positions(
+    document: &Arc<TypstHtmlDocument>,
+    world: &dyn World,
+) -> SourceResult<HastElementContent> {
+    let options = HastOptions {
+        world: Some(world),
+        ..HastOptions::default()
+    };
+    hast_with(document, options)
+}
+
+/// Encodes an HTML document into a Hast with the options.
+pub fn hast_with(
+    document: &Arc<TypstHtmlDocument>,
+    options: HastOptions,
+) -> SourceResult<HastElementContent> {
+    let w = Writer {
+        world: options.world,
+        frames: options.frames,
+        #[cfg(feature = "svg-frames")]
+        svg_frames: options.svg_frames,
+    };
+    w.write_element(&document.root)
+}
+
+/// Collects the frames in an element, in the order of appearance.
+pub fn collect_frames<'a>(element: &'a HtmlElement, frames: &mut Vec<&'a Frame>) {
+    for node in &element.children {
+        match node {
+            HtmlNode::Element(element) => collect_frames(element, frames),
+            HtmlNode::Frame(frame) => frames.push(&frame.inner),
+            HtmlNode::Tag(_) | HtmlNode::Text(..) => {}
         }
     }
-    Ok(())
 }
 
-/// Encode plain text into the writer.
-fn write_text(text: &EcoString, _span: Span) -> SourceResult<HastElementContent> {
-    Ok(HastElementContent::Text(HastText {
-        value: EcoString::from(text),
-        // todo: span mapping
-    }))
+/// Resolves the source location of a span.
+///
+/// Returns `None` if the span is detached or the source is not available.
+pub fn position(world: &dyn World, span: Span) -> Option<HastPosition> {
+    let id = span.id()?;
+    let source = world.source(id).ok()?;
+    let range = source.range(span)?;
+
+    let lines = source.lines();
+    let point = |offset: usize| {
+        Some(HastPoint {
+            line: lines.byte_to_line(offset)? + 1,
+            column: lines.byte_to_column(offset)? + 1,
+            offset: Some(offset),
+        })
+    };
+
+    let path = unix_slash(id.vpath().as_rooted_path());
+    let file = match id.package() {
+        Some(package) => eco_format!("{package}{path}"),
+        None => path.into(),
+    };
+
+    Some(HastPosition {
+        start: point(range.start)?,
+        end: point(range.end)?,
+        file: Some(file),
+    })
+}
+
+struct Writer<'a> {
+    /// The world to resolve the source locations, if enabled.
+    world: Option<&'a dyn World>,
+    /// How to encode the frames.
+    frames: HtmlFrameMode,
+    /// The rendered frames, if required by the mode.
+    #[cfg(feature = "svg-frames")]
+    svg_frames: Option<&'a SvgFrames>,
 }
 
-/// Encode one element into the write.
-fn write_element(element: &HtmlElement) -> SourceResult<HastElementContent> {
-    write_element_with_tag(element, &element.tag.resolve())
+impl Writer<'_> {
+    /// Resolves the source location of a span, if enabled.
+    fn position(&self, span: Span) -> Option<HastPosition> {
+        position(self.world?, span)
+    }
+
+    /// Encode an HTML node into the writer.
+    fn write_node(&self, node: &HtmlNode, buf: &mut Vec<HastElementContent>) -> SourceResult<()> {
+        match node {
+            HtmlNode::Tag(_) => {}
+            HtmlNode::Text(text, span) => {
+                buf.push(self.write_text(text, *span)?);
+            }
+            HtmlNode::Element(element) => {
+                buf.push(self.write_element(element)?);
+            }
+            HtmlNode::Frame(frame) => {
+                self.write_frame(&frame.inner, buf);
+            }
+        }
+        Ok(())
+    }
+
+    /// Encode plain text into the writer.
+    fn write_text(&self, text: &EcoString, span: Span) -> SourceResult<HastElementContent> {
+        Ok(HastElementContent::Text(HastText {
+            value: EcoString::from(text),
+            position: self.position(span),
+        }))
+    }
+
+    /// Encode one element into the write.
+    fn write_element(&self, element: &HtmlElement) -> SourceResult<HastElementContent> {
+        self.write_element_with_tag(element, &element.tag.resolve())
+    }
+
+    /// Encode one element into the write.
+    fn write_element_with_tag(
+        &self,
+        element: &HtmlElement,
+        tag: &str,
+    ) -> SourceResult<HastElementContent> {
+        let properties = element
+            .attrs
+            .0
+            .iter()
+            .map(|(attr, value)| (attr.resolve().as_str().into(), value.clone()))
+            .collect();
+
+        // todo: ignored: tag::is_void(element.tag)
+
+        let mut buf = Vec::new();
+
+        // The frames reference the shared definitions by ids.
+        #[cfg(feature = "svg-frames")]
+        if tag == "body" && self.frames == HtmlFrameMode::Inline {
+            if let Some(defs) = self.svg_frames.and_then(|f| svg_to_hast(&f.defs)) {
+                buf.push(defs);
+            }
+        }
+
+        if !element.children.is_empty() {
+            for c in &element.children {
+                self.write_node(c, &mut buf)?;
+            }
+        }
+
+        Ok(HastElementContent::Element(Box::new(HastElement {
+            tag_name: EcoString::from(tag),
+            properties,
+            children: buf,
+            data: None,
+            position: self.position(element.span),
+        })))
+    }
+
+    /// Encode a laid out frame into the writer.
+    fn write_frame(&self, frame: &Frame, buf: &mut Vec<HastElementContent>) {
+        match self.frames {
+            HtmlFrameMode::Svg => {}
+            #[cfg(feature = "svg-frames")]
+            HtmlFrameMode::Inline => {
+                let svg = self.svg_frames.and_then(|f| f.get(frame));
+                if let Some(HastElementContent::Element(mut svg)) = svg.and_then(svg_to_hast) {
+                    svg.data = Some(frame_data(frame));
+                    buf.push(HastElementContent::Element(svg));
+                    return;
+                }
+            }
+            #[cfg(feature = "svg-frames")]
+            HtmlFrameMode::Files => {
+                buf.push(HastElementContent::Element(Box::new(HastElement {
+                    tag_name: EcoString::from("img"),
+                    properties: std::collections::BTreeMap::from([
+                        (EcoString::inline("class"), EcoString::inline("typst-frame")),
+                        (EcoString::inline("data-typst-doc"), EcoString::inline("1")),
+                        (EcoString::inline("src"), SvgFrames::path(frame).into()),
+                    ]),
+                    children: vec![],
+                    data: Some(frame_data(frame)),
+                    position: None,
+                })));
+                return;
+            }
+        }
+
+        write_frame(frame, buf);
+    }
 }
 
-/// Encode one element into the write.
-fn write_element_with_tag(element: &HtmlElement, tag: &str) -> SourceResult<HastElementContent> {
-    let properties = element
-        .attrs
-        .0
-        .iter()
-        .map(|(attr, value)| (attr.resolve().as_str().into(), value.clone()))
-        .collect();
+/// Provides a cheap hash of a frame.
+fn frame_data(frame: &Frame) -> HastElementData {
+    HastElementData {
+        hash: Some(eco_format!(
+            "siphash128_13:{:016x}",
+            reflexo::hash::hash128(&frame)
+        )),
+    }
+}
 
-    // todo: ignored: tag::is_void(element.tag)
+/// Parses a SVG into a Hast element.
+#[cfg(feature = "svg-frames")]
+fn svg_to_hast(svg: &str) -> Option<HastElementContent> {
+    use crate::hast::HastElementProperties;
+    use xmlparser::{ElementEnd, Token, Tokenizer};
 
-    let mut buf = Vec::new();
+    let qualified = |prefix: &str, local: &str| -> EcoString {
+        if prefix.is_empty() {
+            local.into()
+        } else {
+            eco_format!("{prefix}:{local}")
+        }
+    };
 
-    if !element.children.is_empty() {
-        for c in &element.children {
-            write_node(c, &mut buf)?;
+    let mut stack: Vec<HastElement> = vec![];
+    for token in Tokenizer::from(svg) {
+        match token.ok()? {
+            Token::ElementStart { prefix, local, .. } => stack.push(HastElement {
+                tag_name: qualified(&prefix, &local),
+                properties: HastElementProperties::new(),
+                children: vec![],
+                data: None,
+                position: None,
+            }),
+            Token::Attribute {
+                prefix,
+                local,
+                value,
+                ..
+            } => {
+                let key = qualified(&prefix, &local);
+                stack.last_mut()?.properties.insert(key, unescape(&value));
+            }
+            Token::ElementEnd {
+                end: ElementEnd::Close(..) | ElementEnd::Empty,
+                ..
+            } => {
+                let element = HastElementContent::Element(Box::new(stack.pop()?));
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(element),
+                    None => return Some(element),
+                }
+            }
+            Token::Text { text } | Token::Cdata { text, .. } => {
+                if let Some(parent) = stack.last_mut() {
+                    parent.children.push(HastElementContent::Text(HastText {
+                        value: unescape(&text),
+                        position: None,
+                    }));
+                }
+            }
+            _ => {}
         }
     }
 
-    Ok(HastElementContent::Element(Box::new(HastElement {
-        tag_name: EcoString::from(tag),
-        properties,
-        children: buf,
-        data: None,
-    })))
+    None
+}
+
+/// Decodes the character references in a text or an attribute value.
+#[cfg(feature = "svg-frames")]
+fn unescape(text: &str) -> EcoString {
+    let mut res = EcoString::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        res.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let Some(end) = rest.find(';') else {
+            break;
+        };
+        let decoded = match &rest[1..end] {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            code => code
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| code.strip_prefix('#').map(str::parse::<u32>))
+                .and_then(Result::ok)
+                .and_then(char::from_u32),
+        };
+
+        match decoded {
+            Some(c) => {
+                res.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                res.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    res.push_str(rest);
+    res
 }
 
 /// Encode a laid out frame into the writer.
@@ -94,12 +373,122 @@ fn write_frame(frame: &Frame, buf: &mut Vec<HastElementContent>) {
             ),
         ]),
         children: vec![],
-        data: Some(crate::hast::HastElementData {
-            // Provides a cheap hash
-            hash: Some(eco_format!(
-                "siphash128_13:{:016x}",
-                reflexo::hash::hash128(&frame)
-            )),
-        }),
+        data: Some(frame_data(frame)),
+        position: None,
     })));
 }
+
+#[cfg(all(test, feature = "svg-frames"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unescape() {
+        assert_eq!(unescape("a &amp; b &lt;c&gt;"), "a & b <c>");
+        assert_eq!(unescape("&quot;&apos;"), "\"'");
+        assert_eq!(unescape("&#x41;&#66;"), "AB");
+        // Unknown or unterminated references are kept as is.
+        assert_eq!(unescape("&unknown; &"), "&unknown; &");
+        assert_eq!(unescape("&amp y;"), "&amp y;");
+        assert_eq!(unescape("&#xD800;"), "&#xD800;");
+    }
+
+    #[test]
+    fn test_svg_to_hast() {
+        let svg = concat!(
+            r#"<svg class="typst-frame" viewBox="0 0 10 10">"#,
+            r#"<g title="a &amp; b"><use xlink:href="#g0"/></g>"#,
+            r#"<text>1 &lt; 2</text>"#,
+            r#"</svg>"#,
+        );
+        let hast = svg_to_hast(svg).expect("valid svg");
+        let expected = serde_json::json!({
+            "type": "element",
+            "tagName": "svg",
+            "properties": { "class": "typst-frame", "viewBox": "0 0 10 10" },
+            "children": [
+                {
+                    "type": "element",
+                    "tagName": "g",
+                    "properties": { "title": "a & b" },
+                    "children": [{
+                        "type": "element",
+                        "tagName": "use",
+                        "properties": { "xlink:href": "#g0" },
+                        "children": [],
+                    }],
+                },
+                {
+                    "type": "element",
+                    "tagName": "text",
+                    "properties": {},
+                    "children": [{ "type": "text", "value": "1 < 2" }],
+                },
+            ],
+        });
+        assert_eq!(serde_json::to_value(hast).unwrap(), expected);
+    }
+
+    /// Finds the elements of the tag in the tree.
+    fn find<'a>(node: &'a HastElementContent, tag: &str, found: &mut Vec<&'a HastElement>) {
+        if let HastElementContent::Element(element) = node {
+            if element.tag_name == tag {
+                found.push(element);
+            }
+            for child in &element.children {
+                find(child, tag, found);
+            }
+        }
+    }
+
+    #[test]
+    fn test_svg_frames_to_hast() {
+        use typst::introspection::Introspector;
+        use typst::layout::{Abs, FrameItem, Geometry, Point, Size};
+        use typst::visualize::Color;
+
+        let mut frame = Frame::soft(Size::new(Abs::pt(10.), Abs::pt(10.)));
+        let rect = Geometry::Rect(Size::new(Abs::pt(4.), Abs::pt(2.))).filled(Color::RED);
+        frame.push(
+            Point::new(Abs::pt(2.), Abs::pt(3.)),
+            FrameItem::Shape(rect, Span::detached()),
+        );
+
+        for shared_defs in [false, true] {
+            let frames = reflexo_vec2svg::SvgExporter::<reflexo_vec2svg::DefaultExportFeature>::render_frames(
+                &Introspector::default(),
+                [&frame],
+                shared_defs,
+                &Default::default(),
+            );
+
+            let svg = frames.get(&frame).expect("rendered frame");
+            let hast = svg_to_hast(svg).expect("valid svg");
+            let HastElementContent::Element(root) = &hast else {
+                panic!("expected an element, got {hast:?}");
+            };
+            assert_eq!(root.tag_name, "svg");
+            assert!(root.properties.contains_key("viewBox"), "{svg}");
+
+            let mut paths = vec![];
+            find(&hast, "path", &mut paths);
+            let is_rect = |path: &&HastElement| {
+                path.properties
+                    .get("class")
+                    .is_some_and(|c| c == "typst-shape")
+                    && path.properties.contains_key("d")
+            };
+            assert!(paths.iter().any(is_rect), "{svg}");
+
+            if shared_defs {
+                assert!(svg_to_hast(&frames.defs).is_some(), "{}", frames.defs);
+            }
+        }
+    }
+
+    #[test]
+    fn test_svg_to_hast_invalid() {
+        assert!(svg_to_hast("<svg><g></g>").is_none());
+        assert!(svg_to_hast("").is_none());
+    }
+}
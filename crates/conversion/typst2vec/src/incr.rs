@@ -1,6 +1,15 @@
+use std::path::Path;
+use std::sync::Arc;
+
 use reflexo::error::prelude::*;
 use reflexo::typst::TypstDocument;
-use reflexo::vector::ir::{ModuleMetadata, Page};
+use reflexo::vector::ir::{
+    LayoutRegionNode, LayoutSelectorExpr, Module, ModuleMetadata, ModuleStream, Outline, Page,
+};
+#[cfg(feature = "flat-vector")]
+use reflexo::vector::stream::BytesModuleStream;
+use reflexo::vector::wire::{self, FrameHeader, FrameKind};
+use reflexo::TakeAs;
 
 use super::ir::FlatModule;
 use super::pass::IncrTypst2VecPass;
@@ -15,6 +24,8 @@ pub struct IncrDocServer {
     /// The references of pages of the document.
     /// Initially it is None meaning no completed compilation.
     pages: Option<Vec<Page>>,
+    /// The outline of the document.
+    outline: Option<Arc<Outline>>,
 
     /// Maintaining typst -> vector status
     typst2vec: IncrTypst2VecPass,
@@ -28,9 +39,20 @@ impl IncrDocServer {
             .set_should_attach_debug_info(should_attach_debug_info);
     }
 
-    /// Pack the delta into a binary blob.
+    /// Set whether to retain the sent items, which is required by full frames
+    /// and snapshots. It should be set before the first compilation.
+    pub fn set_retain_items(&mut self, retain_items: bool) {
+        self.typst2vec.retain_items = retain_items;
+    }
+
+    /// Pack the delta into a delta frame, see [`reflexo::vector::wire`] for
+    /// the format.
+    ///
+    /// The revision of the server is the lifetime of the items, so a delta
+    /// frame applies to clients at the lifetime before packing.
     pub fn pack_delta(&mut self, output: &TypstDocument) -> Vec<u8> {
         self.typst2vec.spans.reset();
+        let base = self.typst2vec.lifetime;
 
         // Increment the lifetime of all items to touch.
         self.typst2vec.increment_lifetime();
@@ -41,6 +63,12 @@ impl IncrDocServer {
         // run typst2vec pass
         let pages = self.typst2vec.doc(output);
         self.pages = Some(pages.clone());
+        let outline = self.typst2vec.outline(output.introspector());
+        // Clients keep the last outline, so it is sent only when it changes.
+        let outline = (self.outline.as_deref() != Some(&outline)).then(|| Arc::new(outline));
+        if let Some(outline) = &outline {
+            self.outline = Some(outline.clone());
+        }
 
         // let new_items = builder.new_items.get_mut().len();
         // let new_fonts = builder.glyphs.new_fonts.get_mut().len();
@@ -79,27 +107,161 @@ impl IncrDocServer {
             // }
         }
 
-        let mut m = FlatModule::with_capacity(5);
+        let mut m = FlatModule::with_capacity(6);
         m.push(ModuleMetadata::GarbageCollection(gc_items));
         m.add_module(delta);
         m.add_single_layout(pages);
+        if let Some(outline) = outline {
+            m.push(ModuleMetadata::Outline(outline));
+        }
         let delta = m.to_bytes();
 
         // log::info!("svg render time (incremental bin): {:?}", instant.elapsed());
-        [b"diff-v1,", delta.as_slice()].concat()
+        let header = FrameHeader {
+            kind: FrameKind::Delta,
+            base,
+            revision: self.typst2vec.lifetime,
+        };
+        header.encode(&delta)
     }
 
-    /// Pack the current entirely into a binary blob.
+    /// Pack the current entirely into a full frame.
+    ///
+    /// Returns `None` if there is no completed compilation or the sent items
+    /// are not retained, see [`Self::set_retain_items`].
     pub fn pack_current(&mut self) -> Option<Vec<u8>> {
+        if !self.typst2vec.retain_items {
+            return None;
+        }
         let pages = self.pages.as_ref()?.clone();
         let full = self.typst2vec.finalize_ref();
 
-        let mut m = FlatModule::with_capacity(4);
+        let mut m = FlatModule::with_capacity(6);
+        m.push(ModuleMetadata::BuildVersion(Arc::new(wire::build_info())));
         m.add_module(full);
         m.add_single_layout(pages);
+        if let Some(outline) = &self.outline {
+            m.push(ModuleMetadata::Outline(outline.clone()));
+        }
         let full = m.to_bytes();
 
-        Some([b"new,", full.as_slice()].concat())
+        let header = FrameHeader {
+            kind: FrameKind::Full,
+            base: 0,
+            revision: self.typst2vec.lifetime,
+        };
+        Some(header.encode(&full))
+    }
+
+    /// Handles a resync request from a client by packing the current
+    /// entirely into a full frame.
+    ///
+    /// Returns `None` if the current cannot be packed, see
+    /// [`Self::pack_current`].
+    pub fn handle_resync(&mut self, request: &[u8]) -> Result<Option<Vec<u8>>> {
+        let (header, _) = FrameHeader::decode(request).map_err(
+            |err| error_once!("IncrDocServer: invalid resync request", err: err.to_string()),
+        )?;
+        if header.kind != FrameKind::Resync {
+            let kind = format!("{:?}", header.kind);
+            Err(error_once!("IncrDocServer: not a resync request", kind: kind))?;
+        }
+
+        Ok(self.pack_current())
+    }
+
+    /// Snapshots the state of the server into a binary blob, which can be
+    /// restored by [`Self::restore`] after the process restarts.
+    ///
+    /// Returns `None` if there is no completed compilation or the sent items
+    /// are not retained, see [`Self::set_retain_items`].
+    pub fn snapshot(&mut self) -> Option<Vec<u8>> {
+        if !self.typst2vec.retain_items {
+            return None;
+        }
+        let pages = self.pages.as_ref()?.clone();
+        let (lifetimes, module) = self.typst2vec.snapshot();
+
+        let mut m = FlatModule::with_capacity(7);
+        m.push(ModuleMetadata::BuildVersion(Arc::new(wire::build_info())));
+        m.push(ModuleMetadata::Lifetime(Arc::new(lifetimes)));
+        m.add_module(module);
+        m.add_single_layout(pages);
+        if let Some(outline) = &self.outline {
+            m.push(ModuleMetadata::Outline(outline.clone()));
+        }
+        Some(m.to_bytes())
+    }
+
+    /// Restores a server from a snapshot created by [`Self::snapshot`].
+    ///
+    /// The restored server keeps producing deltas against clients that
+    /// already hold the module at the time of the snapshot.
+    ///
+    /// The snapshot is validated, so it requires the `flat-vector` feature.
+    #[cfg(feature = "flat-vector")]
+    pub fn restore(data: &[u8]) -> Result<Self> {
+        let m = BytesModuleStream::from_slice(data).try_checkout_owned()?;
+
+        let mut build_info = None;
+        let mut lifetimes = None;
+        for metadata in &m.metadata {
+            match metadata {
+                ModuleMetadata::BuildVersion(info) => build_info = Some(info.clone()),
+                ModuleMetadata::Lifetime(data) => lifetimes = Some(data.clone()),
+                _ => {}
+            }
+        }
+
+        let expected = wire::build_info();
+        match build_info {
+            Some(info) if *info == expected => {}
+            info => Err(
+                error_once!("IncrDocServer: snapshot is built by another version",
+                    expected: expected.version,
+                    actual: info.map_or_else(|| "unknown".into(), |info| info.version.clone()),
+                ),
+            )?,
+        }
+        let lifetimes = lifetimes.context("IncrDocServer: snapshot has no lifetime data")?;
+
+        let module = Module {
+            fonts: (&m).fonts().take().items,
+            glyphs: (&m).glyphs().take().items,
+            items: (&m).items().0.into_iter().collect(),
+        };
+        let layouts = (&m).layouts();
+        let layout = layouts
+            .first()
+            .map(|l| l.by_selector(&LayoutSelectorExpr::Any));
+        let layout = layout.transpose()?;
+        let pages = layout.as_ref().and_then(LayoutRegionNode::pages_meta);
+        let pages = pages.map(<[Page]>::to_vec);
+
+        let mut server = Self::default();
+        server.typst2vec.restore(lifetimes.take(), module);
+        server.pages = pages;
+        server.outline = (&m).outline();
+        Ok(server)
+    }
+
+    /// Writes the snapshot of the server to the file.
+    ///
+    /// See [`Self::snapshot`] for more information.
+    pub fn save_snapshot(&mut self, path: &Path) -> Result<()> {
+        let data = self
+            .snapshot()
+            .context("IncrDocServer: no completed compilation or retained items to snapshot")?;
+        std::fs::write(path, data).context("IncrDocServer: cannot write snapshot")
+    }
+
+    /// Restores a server from the snapshot file.
+    ///
+    /// See [`Self::restore`] for more information.
+    #[cfg(feature = "flat-vector")]
+    pub fn load_snapshot(path: &Path) -> Result<Self> {
+        let data = std::fs::read(path).context("IncrDocServer: cannot read snapshot")?;
+        Self::restore(&data)
     }
 
     /// Gets element paths by the given span.
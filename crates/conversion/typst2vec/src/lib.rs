@@ -8,6 +8,7 @@ pub mod incr;
 pub mod ir;
 pub mod pass;
 mod path2d;
+pub mod text;
 pub mod utils;
 
 pub use cast::*;
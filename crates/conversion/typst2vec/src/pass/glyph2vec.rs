@@ -1,6 +1,6 @@
 //! Lowering Typst Document into SvgItem.
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::ops::DerefMut;
 use std::sync::Arc;
 
@@ -15,8 +15,10 @@ use typst::text::Font;
 use typst::visualize::Image;
 use typst::visualize::SvgImage;
 
+use reflexo::hash::{item_hash128, Fingerprint};
+
 use crate::font::GlyphProvider;
-use crate::ir::{self, FlatGlyphItem, FontItem, FontPack, FontRef, GlyphItem, GlyphRef};
+use crate::ir::{self, FlatGlyphItem, FontItem, FontPack, FontRef, GlyphItem, GlyphPack, GlyphRef};
 use crate::IntoTypst;
 
 pub type Glyph2VecPass = TGlyph2VecPass</* ENABLE_REF_CNT */ false>;
@@ -56,6 +58,11 @@ pub struct TGlyph2VecPass<const ENABLE_REF_CNT: bool = false> {
     /// for interning
     pub used_fonts: HashSet<FontRef>,
     pub used_glyphs: HashSet<GlyphRef>,
+
+    /// Fonts restored from a snapshot, see [`IncrGlyph2VecPass::restore`].
+    restored_fonts: Vec<FontItem>,
+    /// Glyphs restored from a snapshot, see [`IncrGlyph2VecPass::restore`].
+    restored_glyphs: HashMap<GlyphRef, FlatGlyphItem>,
 }
 
 impl<const ENABLE_REF_CNT: bool> TGlyph2VecPass<ENABLE_REF_CNT> {
@@ -72,16 +79,24 @@ impl<const ENABLE_REF_CNT: bool> TGlyph2VecPass<ENABLE_REF_CNT> {
             new_glyphs: Default::default(),
             used_fonts: Default::default(),
             used_glyphs: Default::default(),
+            restored_fonts: Default::default(),
+            restored_glyphs: Default::default(),
         }
     }
 
     pub fn finalize(&self) -> (FontPack, Vec<(GlyphRef, FlatGlyphItem)>) {
         let mut fonts = self.font_mapping.clone().into_iter().collect::<Vec<_>>();
         fonts.sort_by(|(_, a), (_, b)| a.idx.cmp(&b.idx));
-        let fonts = fonts.into_iter().map(|(a, _)| a.into_typst()).collect();
+        // Restored fonts keep their indices even if they are not used anymore.
+        let restored = self.restored_fonts.len();
+        let fonts = fonts
+            .into_iter()
+            .filter(|(_, b)| b.idx as usize >= restored)
+            .map(|(a, _)| a.into_typst());
+        let fonts = self.restored_fonts.iter().cloned().chain(fonts).collect();
 
         let glyphs = self.glyph_defs.clone().into_iter().collect::<Vec<_>>();
-        let glyphs = glyphs
+        let mut glyphs: Vec<_> = glyphs
             .into_par_iter()
             .flat_map(|(a, b)| {
                 self.inner.must_flat_glyph(&a).map(|g| {
@@ -96,6 +111,13 @@ impl<const ENABLE_REF_CNT: bool> TGlyph2VecPass<ENABLE_REF_CNT> {
             })
             .collect();
 
+        if !self.restored_glyphs.is_empty() {
+            let defined = glyphs.iter().map(|(id, _)| *id).collect::<HashSet<_>>();
+            let restored = self.restored_glyphs.iter();
+            let restored = restored.filter(|(id, _)| !defined.contains(id));
+            glyphs.extend(restored.map(|(id, glyph)| (*id, glyph.clone())));
+        }
+
         (fonts, glyphs)
     }
 
@@ -109,6 +131,18 @@ impl<const ENABLE_REF_CNT: bool> TGlyph2VecPass<ENABLE_REF_CNT> {
 
         let entry = self.font_mapping.entry(font.clone());
         let entry = entry.or_insert_with(|| {
+            // The font is known by clients if it is restored from a snapshot.
+            let fingerprint = Fingerprint::from_u128(item_hash128(font));
+            let restored = self.restored_fonts.iter();
+            if let Some(idx) = restored.clone().position(|f| f.fingerprint == fingerprint) {
+                let hash = self.restored_fonts[idx].hash;
+                self.font_conflict_checker.insert(hash, font.clone());
+                return FontRef {
+                    hash,
+                    idx: idx as u32,
+                };
+            }
+
             let font_index = font_index_lock.deref_mut();
             let mut abs_ref = FontRef {
                 hash: reflexo::hash::hash32(font),
@@ -118,6 +152,11 @@ impl<const ENABLE_REF_CNT: bool> TGlyph2VecPass<ENABLE_REF_CNT> {
 
             // Detect font short hash conflict
             'conflict_detection: loop {
+                if restored.clone().any(|f| f.hash == abs_ref.hash) {
+                    abs_ref.hash += 1;
+                    continue 'conflict_detection;
+                }
+
                 if let Some(conflict) = self.font_conflict_checker.get(&abs_ref.hash) {
                     if *conflict != *font {
                         log::error!(
@@ -163,6 +202,7 @@ impl<const ENABLE_REF_CNT: bool> TGlyph2VecPass<ENABLE_REF_CNT> {
             .glyph_defs
             .insert(glyph.clone(), (abs_ref, font_ref))
             .is_some()
+            || self.restored_glyphs.contains_key(&abs_ref)
         {
             return abs_ref;
         }
@@ -185,6 +225,16 @@ impl<const ENABLE_REF_CNT: bool> TGlyph2VecPass<ENABLE_REF_CNT> {
 }
 
 impl IncrGlyph2VecPass {
+    /// Restores the fonts and glyphs which are already held by clients, so
+    /// that they are not sent again in later deltas.
+    ///
+    /// It must be called before building any font or glyph.
+    pub fn restore(&mut self, fonts: FontPack, glyphs: GlyphPack) {
+        *self.font_index.get_mut() = fonts.len();
+        self.restored_fonts = fonts;
+        self.restored_glyphs = glyphs.into_iter().collect();
+    }
+
     pub fn finalize_delta(&self) -> (FontPack, Vec<(GlyphRef, FlatGlyphItem)>) {
         let fonts = std::mem::take(self.new_fonts.lock().deref_mut());
         let glyphs = std::mem::take(self.new_glyphs.lock().deref_mut());
@@ -35,17 +35,17 @@ impl Default for LazyVec {
 }
 
 /// A Enum representing [`SourceNodeKind::Text`] or [`SourceNodeKind::Char`].
-const SOURCE_MAPPING_TYPE_TEXT: u32 = 0;
+pub const SOURCE_MAPPING_TYPE_TEXT: u32 = 0;
 /// A Enum representing [`SourceNodeKind::Group`].
-const SOURCE_MAPPING_TYPE_GROUP: u32 = 1;
+pub const SOURCE_MAPPING_TYPE_GROUP: u32 = 1;
 /// A Enum representing [`SourceNodeKind::Image`].
-const SOURCE_MAPPING_TYPE_IMAGE: u32 = 2;
+pub const SOURCE_MAPPING_TYPE_IMAGE: u32 = 2;
 /// A Enum representing [`SourceNodeKind::Shape`].
-const SOURCE_MAPPING_TYPE_SHAPE: u32 = 3;
+pub const SOURCE_MAPPING_TYPE_SHAPE: u32 = 3;
 /// A Enum representing [`SourceNodeKind::Page`].
-const SOURCE_MAPPING_TYPE_PAGE: u32 = 4;
+pub const SOURCE_MAPPING_TYPE_PAGE: u32 = 4;
 /// A Enum representing internal glyph offset of [`SOURCE_MAPPING_TYPE_TEXT`].
-const SOURCE_MAPPING_TYPE_CHAR_INDEX: u32 = 5;
+pub const SOURCE_MAPPING_TYPE_CHAR_INDEX: u32 = 5;
 
 impl LazyVec {
     fn new() -> Self {
@@ -15,13 +15,13 @@ use reflexo::typst::{TypstDocument, TypstHtmlDocument, TypstPagedDocument};
 use reflexo::ImmutStr;
 use ttf_parser::{GlyphId, OutlineBuilder};
 use typst::{
-    foundations::{Bytes, Smart},
-    introspection::{Introspector, Tag},
+    foundations::{Bytes, NativeElement, Selector, Smart, StyleChain},
+    introspection::{Introspector, Location, Tag},
     layout::{
-        Abs as TypstAbs, Axes, Dir, Frame, FrameItem, FrameKind, Position, Ratio as TypstRatio,
-        Size as TypstSize, Transform as TypstTransform,
+        Abs as TypstAbs, Axes, Dir, Frame, FrameItem, FrameKind, Page as TypstPage, Position,
+        Ratio as TypstRatio, Size as TypstSize, Transform as TypstTransform,
     },
-    model::Destination,
+    model::{Destination, HeadingElem, Numbering},
     syntax::Span,
     text::TextItem as TypstTextItem,
     visualize::{
@@ -114,6 +114,10 @@ pub struct Typst2VecPassImpl<const ENABLE_REF_CNT: bool = false> {
     pub cache_items: RefItemMapT<(AtomicU64, Fingerprint, VecItem)>,
     pub items: RefItemMapSync,
     pub new_items: Mutex<Vec<(Fingerprint, VecItem)>>,
+    /// Whether to keep the items in [`Self::items`] when the reference
+    /// counting is enabled. Otherwise, only the new items are kept until they
+    /// are finalized into a delta, and [`Self::finalize_ref`] cannot be used.
+    pub retain_items: bool,
 
     pub command_executor: Arc<dyn CommandExecutor + Send + Sync>,
 
@@ -137,6 +141,7 @@ impl<const ENABLE_REF_CNT: bool> Default for Typst2VecPassImpl<ENABLE_REF_CNT> {
             cache_items: Default::default(),
             items: Default::default(),
             new_items: Default::default(),
+            retain_items: false,
             fingerprint_builder: Default::default(),
             command_executor: Arc::new(()),
         }
@@ -208,6 +213,16 @@ impl Typst2VecPass {
 impl<const ENABLE_REF_CNT: bool> Typst2VecPassImpl<ENABLE_REF_CNT> {
     pub fn reset(&mut self) {}
 
+    /// Gets the item to keep in the map when the reference counting is
+    /// enabled, see [`Self::retain_items`].
+    fn retained(&self, item: &VecItem) -> VecItem {
+        if self.retain_items {
+            item.clone()
+        } else {
+            VecItem::None
+        }
+    }
+
     pub fn finalize(self) -> Module {
         let (fonts, glyphs) = self.glyphs.finalize();
         Module {
@@ -264,6 +279,7 @@ impl<const ENABLE_REF_CNT: bool> Typst2VecPassImpl<ENABLE_REF_CNT> {
         let root = Page {
             content: abs_ref,
             size: Size::new(Scalar(1e11 + 4.), Scalar(1e11 + 4.)),
+            label: None,
         };
 
         self.spans
@@ -273,6 +289,46 @@ impl<const ENABLE_REF_CNT: bool> Typst2VecPassImpl<ENABLE_REF_CNT> {
         vec![root]
     }
 
+    /// Converts standalone frames, e.g. the frames embedded in a HTML
+    /// document, into pages sharing the module.
+    pub fn frames<'f>(
+        &self,
+        introspector: &Introspector,
+        frames: impl IntoIterator<Item = &'f Frame>,
+    ) -> Vec<Page> {
+        let doc_reg = self.spans.start();
+
+        let pages = frames
+            .into_iter()
+            .enumerate()
+            .map(|(idx, frame)| {
+                let page_reg = self.spans.start();
+
+                let state = State::new(introspector, frame.size().into_typst());
+                let abs_ref = self.frame_(state, frame, page_reg, idx, None);
+
+                self.spans.push_span(SourceRegion {
+                    region: doc_reg,
+                    idx: idx as u32,
+                    kind: SourceNodeKind::Page { region: page_reg },
+                    item: abs_ref,
+                });
+
+                Page {
+                    content: abs_ref,
+                    size: frame.size().into_typst(),
+                    label: None,
+                }
+            })
+            .collect();
+
+        self.spans
+            .doc_region
+            .store(doc_reg, std::sync::atomic::Ordering::SeqCst);
+
+        pages
+    }
+
     pub fn paged(&self, doc: &TypstPagedDocument) -> Vec<Page> {
         let doc_reg = self.spans.start();
 
@@ -296,6 +352,7 @@ impl<const ENABLE_REF_CNT: bool> Typst2VecPassImpl<ENABLE_REF_CNT> {
                 Page {
                     content: abs_ref,
                     size: p.frame.size().into_typst(),
+                    label: page_label(p),
                 }
             })
             .collect();
@@ -307,6 +364,33 @@ impl<const ENABLE_REF_CNT: bool> Typst2VecPassImpl<ENABLE_REF_CNT> {
         pages
     }
 
+    /// Lowers the bookmarked headings in the document into an outline.
+    pub fn outline(&self, introspector: &Introspector) -> ir::Outline {
+        let styles = StyleChain::default();
+        let items = introspector
+            .query(&HeadingElem::ELEM.select())
+            .iter()
+            .filter_map(|elem| {
+                let heading = elem.to_packed::<HeadingElem>()?;
+                let bookmarked = heading.bookmarked.get(styles);
+                if !bookmarked.unwrap_or_else(|| heading.outlined.get(styles)) {
+                    return None;
+                }
+
+                let pos = introspector.position(elem.location()?);
+                Some(OutlineItem {
+                    title: heading.body.plain_text().as_str().into(),
+                    level: heading.resolve_level(styles).get() as u32,
+                    page: pos.page.get() as u32,
+                    offset: Scalar(pos.point.y.to_f32()),
+                    span: elem.span().into_raw().get(),
+                })
+            })
+            .collect();
+
+        ir::Outline { items }
+    }
+
     fn frame(&self, state: State, frame: &Frame, parent: usize, index: usize) -> Fingerprint {
         self.frame_(state, frame, parent, index, None)
     }
@@ -463,11 +547,11 @@ impl<const ENABLE_REF_CNT: bool> Typst2VecPassImpl<ENABLE_REF_CNT> {
                     is_link = true;
                     self.store(match lnk {
                         Destination::Url(url) => self.link(url, *size),
-                        Destination::Position(dest) => self.position(*dest, *size),
+                        Destination::Position(dest) => self.position(*dest, *size, None),
                         Destination::Location(loc) => {
-                            // todo: process location before lowering
                             let dest = state.introspector.position(*loc);
-                            self.position(dest, *size)
+                            let target = link_target(state.introspector, *loc, dest);
+                            self.position(dest, *size, Some(target))
                         }
                     })
                 }
@@ -479,7 +563,6 @@ impl<const ENABLE_REF_CNT: bool> Typst2VecPassImpl<ENABLE_REF_CNT> {
                     self.store(VecItem::ContentHint('\n'))
                 }
                 FrameItem::Tag(Tag::End(..)) => return None,
-                // todo: support page label
             };
 
             Some(((*pos).into_typst(), is_link, item))
@@ -600,8 +683,10 @@ impl<const ENABLE_REF_CNT: bool> Typst2VecPassImpl<ENABLE_REF_CNT> {
         }
 
         let item_resolution = if ENABLE_REF_CNT {
-            self.new_items.lock().push((fg, item.into_owned()));
-            (AtomicU64::new(self.lifetime), VecItem::None)
+            let item = item.into_owned();
+            let retained = self.retained(&item);
+            self.new_items.lock().push((fg, item));
+            (AtomicU64::new(self.lifetime), retained)
         } else {
             (AtomicU64::new(0), item.into_owned())
         };
@@ -649,8 +734,10 @@ impl<const ENABLE_REF_CNT: bool> Typst2VecPassImpl<ENABLE_REF_CNT> {
             }
             Vacant(pos) => {
                 let item_resolution = if ENABLE_REF_CNT {
-                    self.new_items.lock().push((fg, item.into_owned()));
-                    (AtomicU64::new(self.lifetime), VecItem::None)
+                    let item = item.into_owned();
+                    let retained = self.retained(&item);
+                    self.new_items.lock().push((fg, item));
+                    (AtomicU64::new(self.lifetime), retained)
                 } else {
                     (AtomicU64::new(0), item.into_owned())
                 };
@@ -951,12 +1038,13 @@ impl<const ENABLE_REF_CNT: bool> Typst2VecPassImpl<ENABLE_REF_CNT> {
         VecItem::Link(LinkItem {
             href: url.into(),
             size: size.into_typst(),
+            target: None,
         })
     }
 
     // /// Convert a document position into vector item.
     // #[comemo::memoize]
-    fn position(&self, pos: Position, size: TypstSize) -> VecItem {
+    fn position(&self, pos: Position, size: TypstSize, target: Option<Arc<LinkTarget>>) -> VecItem {
         let lnk = LinkItem {
             href: format!(
                 "@typst:handleTypstLocation(this, {}, {}, {})",
@@ -966,6 +1054,7 @@ impl<const ENABLE_REF_CNT: bool> Typst2VecPassImpl<ENABLE_REF_CNT> {
             )
             .into(),
             size: size.into_typst(),
+            target,
         };
 
         VecItem::Link(lnk)
@@ -1219,6 +1308,51 @@ impl IncrTypst2VecPass {
         Arc::try_unwrap(gc_items).unwrap().into_inner()
     }
 
+    /// Snapshots the items and their lifetimes, which can be restored by
+    /// [`Self::restore`].
+    pub fn snapshot(&mut self) -> (IncrLifetimePack, Module) {
+        let module = self.finalize_ref();
+
+        let mut items = Vec::with_capacity(module.items.len());
+        for shard in self.items.as_mut_slice() {
+            let shard = shard.read();
+            for (fg, (lifetime, _item)) in shard.iter() {
+                items.push((*fg, lifetime.load(std::sync::atomic::Ordering::Relaxed)));
+            }
+        }
+
+        let lifetimes = IncrLifetimePack {
+            lifetime: self.lifetime,
+            items,
+        };
+        (lifetimes, module)
+    }
+
+    /// Restores the items and their lifetimes from a snapshot, so that items
+    /// held by clients are not sent again.
+    ///
+    /// It must be called on a fresh pass. The items are retained afterwards.
+    pub fn restore(&mut self, lifetimes: IncrLifetimePack, module: Module) {
+        let Module {
+            fonts,
+            glyphs,
+            mut items,
+        } = module;
+
+        self.retain_items = true;
+        self.lifetime = lifetimes.lifetime;
+        self.glyphs.lifetime = lifetimes.lifetime;
+        self.glyphs.restore(fonts, glyphs);
+
+        for (fg, lifetime) in lifetimes.items {
+            let Some(item) = items.remove(&fg) else {
+                continue;
+            };
+            let mut shard = self.items.shard(fg).write();
+            shard.insert(fg, (AtomicU64::new(lifetime), item));
+        }
+    }
+
     /// Finalize modules containing new vector items.
     pub fn finalize_delta(&mut self) -> Module {
         // filter glyphs by lifetime
@@ -1235,6 +1369,32 @@ impl IncrTypst2VecPass {
     }
 }
 
+/// Gets the logical page number of a page, e.g. `iii` or `A-1`.
+///
+/// Function numberings can only be evaluated with an engine during layout,
+/// so pages numbered by functions have no label, as in the PDF export of
+/// typst. Renderers then fall back to the physical page number.
+fn page_label(page: &TypstPage) -> Option<ImmutStr> {
+    match page.numbering.as_ref()? {
+        Numbering::Pattern(pattern) => Some(pattern.apply(&[page.number]).as_str().into()),
+        Numbering::Func(..) => None,
+    }
+}
+
+/// Resolves the target of a link to a location in the document.
+fn link_target(introspector: &Introspector, loc: Location, pos: Position) -> Arc<LinkTarget> {
+    let label = introspector
+        .query_first(&Selector::Location(loc))
+        .and_then(|elem| elem.label());
+
+    Arc::new(LinkTarget {
+        page: pos.page.get() as u32,
+        point: pos.point.into_typst(),
+        anchor: format!("loc-{:032x}", loc.hash()).into(),
+        label: label.map(|label| label.resolve().as_str().into()),
+    })
+}
+
 // impl<'m, const ENABLE_REF_CNT: bool> ItemIndice<'m> for
 // ConvertImpl<ENABLE_REF_CNT> {     fn get_item(&self, value: &Fingerprint) ->
 // Option<&'m VecItem> {         self.items.get(value).map(|item| &item.1)
@@ -0,0 +1,119 @@
+//! Traversal of the text items laid out in vector documents.
+
+use reflexo::hash::Fingerprint;
+use reflexo::vector::ir::{self, FontItem, ImmutStr, Module, Point, Rect, Scalar, VecItem};
+
+use crate::pass::{SOURCE_MAPPING_TYPE_GROUP, SOURCE_MAPPING_TYPE_PAGE, SOURCE_MAPPING_TYPE_TEXT};
+
+/// A text item laid out on a page.
+pub struct TextRun<'a> {
+    /// The fingerprint of the text item.
+    pub fg: Fingerprint,
+    pub text: &'a ir::TextItem,
+    /// The transform from the text item to the page.
+    pub ts: ir::Transform,
+    /// The labels of the elements containing the text item, from the outermost
+    /// one.
+    pub labels: Vec<ImmutStr>,
+    /// The element path of the text item, which can be resolved by
+    /// [`crate::pass::Span2VecPass::query`].
+    pub path: Vec<u32>,
+}
+
+impl TextRun<'_> {
+    /// Gets the bounding box of the text from `lo` to `hi` along the baseline,
+    /// spanning from the cap height to the descender, on the page.
+    pub fn bbox(&self, font: &FontItem, lo: Scalar, hi: Scalar) -> Rect {
+        let size = self.text.shape.size;
+        let top = -(font.cap_height * size).0;
+        let bottom = top + size.0;
+
+        let ts: tiny_skia::Transform = self.ts.into();
+        let mut corners = [
+            tiny_skia::Point::from_xy(lo.0, top),
+            tiny_skia::Point::from_xy(hi.0, top),
+            tiny_skia::Point::from_xy(lo.0, bottom),
+            tiny_skia::Point::from_xy(hi.0, bottom),
+        ];
+        ts.map_points(&mut corners);
+
+        let (mut min, mut max) = ((f32::MAX, f32::MAX), (f32::MIN, f32::MIN));
+        for p in corners {
+            min = (min.0.min(p.x), min.1.min(p.y));
+            max = (max.0.max(p.x), max.1.max(p.y));
+        }
+        Rect {
+            lo: Point::new(Scalar(min.0), Scalar(min.1)),
+            hi: Point::new(Scalar(max.0), Scalar(max.1)),
+        }
+    }
+}
+
+/// Collects the text runs of a page in the order they are laid out.
+pub fn page_text_runs<'a>(
+    module: &'a Module,
+    page: usize,
+    content: &Fingerprint,
+) -> Vec<TextRun<'a>> {
+    let mut collector = TextRunCollector {
+        module,
+        labels: vec![],
+        runs: vec![],
+    };
+    let mut path = vec![SOURCE_MAPPING_TYPE_PAGE, page as u32];
+    collector.collect(ir::Transform::identity(), content, 0, &mut path);
+    collector.runs
+}
+
+struct TextRunCollector<'a> {
+    module: &'a Module,
+    labels: Vec<ImmutStr>,
+    runs: Vec<TextRun<'a>>,
+}
+
+impl TextRunCollector<'_> {
+    fn collect(&mut self, ts: ir::Transform, fg: &Fingerprint, index: u32, path: &mut Vec<u32>) {
+        let module = self.module;
+        let Some(item) = module.get_item(fg) else {
+            return;
+        };
+
+        match item {
+            VecItem::Group(t) => {
+                path.extend([SOURCE_MAPPING_TYPE_GROUP, index]);
+                // Links and content hints have no source mapping, so they are
+                // not counted in the element path.
+                let children = t.0.iter().filter(|(_, child)| {
+                    let child = module.get_item(child);
+                    !matches!(child, Some(VecItem::Link(..) | VecItem::ContentHint(..)))
+                });
+                for (idx, (pos, child)) in children.enumerate() {
+                    let ts = ts.pre_translate(pos.x.0, pos.y.0);
+                    self.collect(ts, child, idx as u32, path);
+                }
+                path.truncate(path.len() - 2);
+            }
+            VecItem::Item(t) => {
+                let ts = ts.pre_concat(t.0.clone().into());
+                self.collect(ts, &t.1, index, path);
+            }
+            VecItem::Labelled(t) => {
+                self.labels.push(t.0.clone());
+                self.collect(ts, &t.1, index, path);
+                self.labels.pop();
+            }
+            VecItem::Text(t) => {
+                let mut path = path.clone();
+                path.extend([SOURCE_MAPPING_TYPE_TEXT, index]);
+                self.runs.push(TextRun {
+                    fg: *fg,
+                    text: t,
+                    ts,
+                    labels: self.labels.clone(),
+                    path,
+                });
+            }
+            _ => {}
+        }
+    }
+}
@@ -2,7 +2,12 @@ use std::collections::HashMap;
 
 use tiny_skia as sk;
 
+use reflexo::debug_loc::ElementPoint;
 use reflexo::{hash::Fingerprint, vector::ir::*};
+use reflexo_typst2vec::pass::{
+    SOURCE_MAPPING_TYPE_CHAR_INDEX, SOURCE_MAPPING_TYPE_GROUP, SOURCE_MAPPING_TYPE_IMAGE,
+    SOURCE_MAPPING_TYPE_PAGE, SOURCE_MAPPING_TYPE_SHAPE, SOURCE_MAPPING_TYPE_TEXT,
+};
 
 #[derive(Default)]
 pub struct Vec2BBoxPass {
@@ -13,45 +18,44 @@ impl Vec2BBoxPass {
     /// Calculate the bounding box of a vector item with a given transform.
     /// The transform is required to calculate the accurate bounding box for
     /// irregular shapes.
+    ///
+    /// The glyphs of the module should be prepared by
+    /// [`Module::prepare_glyphs`] to calculate the accurate bounding box of
+    /// texts, otherwise the font metrics are used.
     pub fn bbox_of(&mut self, module: &Module, v: Fingerprint, ts: Transform) -> Option<Rect> {
         if let Some(bbox) = self.bbox_caches.get(&(v, ts)) {
             return *bbox;
         }
 
         let bbox = self.bbox_of_(module, v, ts);
-        eprintln!("bbox_of({v:?}, {ts:?}) = {bbox:?}");
         self.bbox_caches.insert((v, ts), bbox);
         bbox
     }
 
     fn bbox_of_(&mut self, module: &Module, v: Fingerprint, ts: Transform) -> Option<Rect> {
-        let item = module.get_item(&v).unwrap();
+        let item = module.get_item(&v)?;
         match item {
             VecItem::Item(item) => {
                 let ts_group: Transform = item.0.clone().into();
-                let ts = ts.pre_concat(ts_group);
-                self.bbox_of(module, item.1, ts)
+                let bbox = self.bbox_of(module, item.1, ts.pre_concat(ts_group));
+                match &item.0 {
+                    TransformItem::Clip(clip) => intersect(bbox, self.path(clip, ts)),
+                    _ => bbox,
+                }
             }
             VecItem::Labelled(item) => self.bbox_of(module, item.1, ts),
             VecItem::Group(g) => {
-                let mut r = Rect::default();
+                let mut r = None;
                 for (p, f) in g.0.iter() {
-                    let sub_bbox = self.bbox_of(module, *f, ts);
-                    if let Some(sub_bbox) = sub_bbox {
-                        union(&mut r, *p, sub_bbox);
-                    }
+                    let sub_bbox = self.bbox_of(module, *f, ts.pre_translate(p.x.0, p.y.0));
+                    r = union(r, sub_bbox);
                 }
-                Some(r)
+                r
             }
             VecItem::Image(ImageItem { size, .. })
             | VecItem::Link(LinkItem { size, .. })
             | VecItem::SizedRawHtml(SizedRawHtmlItem { size, .. }) => self.rect(*size, ts),
-            // todo: I'm writing this in my leg
-            VecItem::Text(t) => {
-                let width = t.width();
-                let height = t.shape.size.0;
-                tiny_skia_path::Rect::from_xywh(0.0, 0.0, width.0, height).map(|e| e.into())
-            }
+            VecItem::Text(t) => self.text(module, t, ts),
             VecItem::Path(p) => self.path(p, ts),
             VecItem::ContentHint(..)
             | VecItem::ColorTransform(..)
@@ -63,6 +67,133 @@ impl Vec2BBoxPass {
         }
     }
 
+    /// Calculate the bounding box of the glyph outlines in a text.
+    ///
+    /// Falls back to the box between the ascender and the descender if none
+    /// of the glyphs is available in the module.
+    pub fn text(&self, module: &Module, text: &TextItem, ts: Transform) -> Option<Rect> {
+        let font = module.get_font(&text.shape.font)?;
+        let upem = font.units_per_em;
+        let ppem = text.shape.ppem(upem.0).0;
+        let text_ts = sk::Transform::from(ts).pre_scale(ppem, -ppem);
+
+        let mut bbox = None;
+        let mut size = Axes { x: 0f32, y: 0f32 };
+        for (offset, glyph) in text.render_glyphs(upem, &mut size) {
+            let ts = text_ts.pre_translate(offset.x.0, offset.y.0);
+            let glyph_bbox = match font.get_glyph(glyph).map(|glyph| &**glyph) {
+                Some(FlatGlyphItem::Outline(glyph)) => {
+                    let ts = match &glyph.ts {
+                        Some(glyph_ts) => ts.pre_concat((**glyph_ts).into()),
+                        None => ts,
+                    };
+                    Self::simple_path_bbox(&glyph.d, ts)
+                }
+                Some(FlatGlyphItem::Image(glyph)) => {
+                    self.rect(glyph.image.size, ts.pre_concat(glyph.ts.into()).into())
+                }
+                Some(FlatGlyphItem::None) | None => None,
+            };
+            bbox = union(bbox, glyph_bbox);
+        }
+
+        bbox.or_else(|| {
+            let top = -(font.ascender * text.shape.size).0;
+            let bottom = -(font.descender * text.shape.size).0;
+            let r = tiny_skia_path::Rect::from_ltrb(0.0, top, text.width().0, bottom);
+            r.and_then(|e| e.transform(ts.into())).map(|e| e.into())
+        })
+    }
+
+    /// Finds the topmost element at the point on the page, returning its
+    /// element path, which can be queried by `Span2VecPass::query` for the
+    /// source location. The path ends with the index of the glyph if the
+    /// element is a text.
+    ///
+    /// Returns an empty path if no element is at the point.
+    pub fn hit_test(
+        &mut self,
+        module: &Module,
+        pages: &[Page],
+        page: usize,
+        point: Point,
+    ) -> Vec<ElementPoint> {
+        let Some(page_item) = pages.get(page) else {
+            return vec![];
+        };
+
+        let mut path = vec![element_point(SOURCE_MAPPING_TYPE_PAGE, page, None)];
+        let ts = Transform::identity();
+        if self.hit_item(module, page_item.content, 0, ts, point, &mut path) {
+            path
+        } else {
+            vec![]
+        }
+    }
+
+    fn hit_item(
+        &mut self,
+        module: &Module,
+        v: Fingerprint,
+        index: usize,
+        ts: Transform,
+        point: Point,
+        path: &mut Vec<ElementPoint>,
+    ) -> bool {
+        if !self
+            .bbox_of(module, v, ts)
+            .is_some_and(|bbox| contains(&bbox, point))
+        {
+            return false;
+        }
+
+        let Some(item) = module.get_item(&v) else {
+            return false;
+        };
+        match item {
+            VecItem::Item(item) => {
+                let ts = ts.pre_concat(item.0.clone().into());
+                self.hit_item(module, item.1, index, ts, point, path)
+            }
+            VecItem::Labelled(item) => self.hit_item(module, item.1, index, ts, point, path),
+            VecItem::Group(g) => {
+                path.push(element_point(SOURCE_MAPPING_TYPE_GROUP, index, Some(v)));
+                // Links and content hints have no source mapping, so they are
+                // not counted in the element path.
+                let children = g.0.iter().filter(|(_, child)| {
+                    let child = module.get_item(child);
+                    !matches!(child, Some(VecItem::Link(..) | VecItem::ContentHint(..)))
+                });
+                // The later children are painted above the earlier ones.
+                let children = children.enumerate().collect::<Vec<_>>();
+                for (idx, (p, child)) in children.into_iter().rev() {
+                    let ts = ts.pre_translate(p.x.0, p.y.0);
+                    if self.hit_item(module, *child, idx, ts, point, path) {
+                        return true;
+                    }
+                }
+                path.pop();
+                false
+            }
+            VecItem::Text(t) => {
+                path.push(element_point(SOURCE_MAPPING_TYPE_TEXT, index, Some(v)));
+                if let Some(glyph) = hit_glyph(t, ts, point) {
+                    path.push(element_point(SOURCE_MAPPING_TYPE_CHAR_INDEX, glyph, None));
+                }
+                true
+            }
+            VecItem::Image(..) => {
+                path.push(element_point(SOURCE_MAPPING_TYPE_IMAGE, index, Some(v)));
+                true
+            }
+            VecItem::Path(..) => {
+                path.push(element_point(SOURCE_MAPPING_TYPE_SHAPE, index, Some(v)));
+                true
+            }
+            _ => false,
+        }
+    }
+
     pub fn path(&mut self, p: &PathItem, ts: Transform) -> Option<Rect> {
         Self::path_bbox(p, ts.into())
     }
@@ -104,11 +235,51 @@ impl Vec2BBoxPass {
     }
 }
 
-fn union(r: &mut Rect, p: Axes<Scalar>, sub_bbox: Rect) {
-    *r = r.union(&sub_bbox.translate(p))
+fn union(x: Option<Rect>, y: Option<Rect>) -> Option<Rect> {
+    match (x, y) {
+        (Some(x), Some(y)) => Some(Rect {
+            lo: x.lo.min(&y.lo),
+            hi: x.hi.max(&y.hi),
+        }),
+        (x, y) => x.or(y),
+    }
+}
+
+fn intersect(x: Option<Rect>, y: Option<Rect>) -> Option<Rect> {
+    let r = x?.intersect(&y?);
+    (r.lo.x <= r.hi.x && r.lo.y <= r.hi.y).then_some(r)
+}
+
+fn contains(r: &Rect, p: Point) -> bool {
+    r.lo.x <= p.x && p.x <= r.hi.x && r.lo.y <= p.y && p.y <= r.hi.y
+}
+
+/// Finds the glyph whose advance covers the point horizontally.
+fn hit_glyph(text: &TextItem, ts: Transform, point: Point) -> Option<usize> {
+    let inv = sk::Transform::from(ts).invert()?;
+    let mut p = sk::Point::from_xy(point.x.0, point.y.0);
+    inv.map_point(&mut p);
+
+    let mut x = 0.;
+    for (idx, (_, advance, _)) in text.content.glyphs.iter().enumerate() {
+        if p.x < x + advance.x.0 {
+            return Some(idx);
+        }
+        x += advance.x.0;
+    }
+    text.content.glyphs.len().checked_sub(1)
 }
 
-fn convert_path(path_data: &str) -> Option<tiny_skia_path::Path> {
+fn element_point(kind: u32, index: usize, fg: Option<Fingerprint>) -> ElementPoint {
+    ElementPoint {
+        kind,
+        index: index as u32,
+        fingerprint: fg.map(|fg| fg.as_svg_id("")).unwrap_or_default(),
+    }
+}
+
+/// Converts a svg path data string into a [`tiny_skia_path::Path`].
+pub fn convert_path(path_data: &str) -> Option<tiny_skia_path::Path> {
     let mut builder = tiny_skia_path::PathBuilder::new();
     for segment in svgtypes::SimplifyingPathParser::from(path_data) {
         let segment = match segment {
@@ -165,4 +336,48 @@ mod tests {
 
         assert!(Vec2BBoxPass::path_bbox(&p, ts).is_some());
     }
+
+    #[test]
+    fn test_hit_test() {
+        let square = PathItem {
+            d: "M 0 0 L 10 0 L 10 10 L 0 10 Z".into(),
+            size: None,
+            styles: vec![],
+        };
+        let square_fg = Fingerprint::from_u128(1);
+        let group_fg = Fingerprint::from_u128(2);
+        let at = |x: f32, y: f32| Point::new(Scalar(x), Scalar(y));
+
+        let mut module = Module::default();
+        module.items.insert(square_fg, VecItem::Path(square));
+        let children = [(at(0., 0.), square_fg), (at(20., 5.), square_fg)];
+        let group = VecItem::Group(GroupRef(children.into_iter().collect()));
+        module.items.insert(group_fg, group);
+        let pages = [Page {
+            content: group_fg,
+            size: Size::new(Scalar(100.), Scalar(100.)),
+            label: None,
+        }];
+
+        let mut pass = Vec2BBoxPass::default();
+        let bbox = pass.bbox_of(&module, group_fg, Transform::identity());
+        assert_eq!(
+            bbox,
+            Some(Rect {
+                lo: at(0., 0.),
+                hi: at(30., 15.)
+            })
+        );
+
+        let path = pass.hit_test(&module, &pages, 0, at(25., 12.));
+        let path = path.iter().map(|p| (p.kind, p.index)).collect::<Vec<_>>();
+        let expected = [
+            (SOURCE_MAPPING_TYPE_PAGE, 0),
+            (SOURCE_MAPPING_TYPE_GROUP, 0),
+            (SOURCE_MAPPING_TYPE_SHAPE, 1),
+        ];
+        assert_eq!(path, expected);
+
+        assert!(pass.hit_test(&module, &pages, 0, at(15., 5.)).is_empty());
+    }
 }
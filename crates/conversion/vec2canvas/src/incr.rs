@@ -1,15 +1,21 @@
+#[cfg(feature = "web")]
 use tiny_skia as sk;
 
-use reflexo::{
-    error::prelude::*,
-    vector::{
-        incr::IncrDocClient,
-        ir::{ImmutStr, Module, Page, Rect},
-        vm::RenderVm,
-    },
+#[cfg(any(feature = "web", feature = "raster"))]
+use reflexo::error::prelude::*;
+#[cfg(feature = "web")]
+use reflexo::vector::ir::Rect;
+use reflexo::vector::{
+    incr::IncrDocClient,
+    ir::{ImmutStr, Module, Page},
+    vm::RenderVm,
 };
 
-use crate::{set_transform, CanvasDevice, CanvasOp, CanvasPage, CanvasTask, DefaultExportFeature};
+#[cfg(feature = "raster")]
+use crate::RasterCanvas;
+#[cfg(feature = "web")]
+use crate::{set_transform, CanvasDevice, CanvasOp};
+use crate::{CanvasPage, CanvasTask, DefaultExportFeature};
 
 /// Incremental pass from vector to canvas
 pub struct IncrVec2CanvasPass {
@@ -45,7 +51,7 @@ impl IncrVec2CanvasPass {
         let pages: Vec<CanvasPage> = pages
             .iter()
             .enumerate()
-            .map(|(idx, Page { content, size })| {
+            .map(|(idx, Page { content, size, .. })| {
                 if idx < self.pages.len() && self.pages[idx].content == *content {
                     return self.pages[idx].clone();
                 }
@@ -62,6 +68,7 @@ impl IncrVec2CanvasPass {
     }
 
     /// Flushes a page to the canvas with the given transform.
+    #[cfg(feature = "web")]
     pub async fn flush_page(&mut self, idx: usize, canvas: &dyn CanvasDevice, ts: sk::Transform) {
         let pg = &self.pages[idx];
 
@@ -73,6 +80,13 @@ impl IncrVec2CanvasPass {
 
         pg.elem.realize(ts, canvas).await;
     }
+
+    /// Rasterizes a page without a browser, see [`Self::flush_page`].
+    #[cfg(feature = "raster")]
+    pub fn rasterize_page(&self, idx: usize) -> Option<RasterCanvas> {
+        let pg = self.pages.get(idx)?;
+        pg.rasterize(self.pixel_per_pt, &self.fill)
+    }
 }
 
 /// Maintains the state of the incremental rendering a canvas at client side
@@ -111,6 +125,7 @@ impl IncrCanvasDocClient {
     }
 
     /// Render a specific page of the document in the given window.
+    #[cfg(feature = "web")]
     pub async fn render_page_in_window(
         &mut self,
         kern: &mut IncrDocClient,
@@ -130,4 +145,18 @@ impl IncrCanvasDocClient {
 
         Ok(())
     }
+
+    /// Rasterizes a specific page of the document without a browser.
+    #[cfg(feature = "raster")]
+    pub fn rasterize_page(&mut self, kern: &mut IncrDocClient, idx: usize) -> Result<RasterCanvas> {
+        self.patch_delta(kern);
+
+        if idx >= self.vec2canvas.pages.len() {
+            Err(error_once!("Renderer.OutofPageRange", idx: idx))?;
+        }
+
+        self.vec2canvas
+            .rasterize_page(idx)
+            .ok_or_else(|| error_once!("Renderer.InvalidPageSize", idx: idx))
+    }
 }
@@ -2,40 +2,49 @@
 #![allow(clippy::arc_with_non_send_sync)]
 
 mod bounds;
+#[cfg(feature = "web")]
 mod device;
 #[cfg(feature = "incremental")]
 mod incr;
 mod ops;
 #[cfg(feature = "rasterize_glyph")]
 mod pixglyph_canvas;
+#[cfg(feature = "raster")]
+mod raster;
 mod utils;
 
 pub use bounds::BBoxAt;
+#[cfg(feature = "web")]
 pub use device::CanvasDevice;
 #[cfg(feature = "incremental")]
 pub use incr::*;
+#[cfg(feature = "web")]
 use js_sys::Promise;
 pub use ops::*;
+#[cfg(feature = "raster")]
+pub use raster::*;
+#[cfg(feature = "web")]
 use web_sys::{Blob, HtmlImageElement, OffscreenCanvas, OffscreenCanvasRenderingContext2d};
 
-use std::{
-    cell::OnceCell,
-    fmt::Debug,
-    sync::{Arc, Mutex},
-};
+#[cfg(feature = "web")]
+use std::sync::Mutex;
+use std::{cell::OnceCell, fmt::Debug, sync::Arc};
 
 use ecow::EcoVec;
+#[cfg(feature = "web")]
+use reflexo::vector::ir::Image;
 use reflexo::{
     hash::Fingerprint,
     vector::{
         ir::{
-            self, Abs, Axes, FontIndice, FontItem, FontRef, Image, ImmutStr, Module, Point, Ratio,
-            Rect, Scalar, Size,
+            self, Abs, Axes, FontIndice, FontItem, FontRef, ImmutStr, Module, Point, Ratio, Rect,
+            Scalar, Size,
         },
         vm::{GroupContext, RenderVm, TransformContext},
     },
 };
 use tiny_skia as sk;
+#[cfg(feature = "web")]
 use wasm_bindgen::{prelude::Closure, JsCast, JsValue};
 
 use bounds::*;
@@ -64,6 +73,7 @@ pub struct BrowserFontMetric {
 }
 
 impl BrowserFontMetric {
+    #[cfg(feature = "web")]
     pub fn from_env() -> Self {
         let v = OffscreenCanvas::new(0, 0).expect("offscreen canvas is not supported");
         let ctx = v
@@ -345,6 +355,7 @@ impl<'m, C: RenderVm<'m, Resultant = CanvasNode> + GlyphFactory> GroupContext<C>
     }
 }
 
+#[cfg(feature = "web")]
 #[inline]
 #[must_use]
 fn set_transform(canvas: &dyn CanvasDevice, transform: sk::Transform) -> bool {
@@ -368,8 +379,10 @@ fn set_transform(canvas: &dyn CanvasDevice, transform: sk::Transform) -> bool {
 ///
 /// When the guard is created, a cheap checkpoint of the canvas state is saved.
 /// When the guard is dropped, the canvas state is restored.
+#[cfg(feature = "web")]
 pub struct CanvasStateGuard<'a>(&'a dyn CanvasDevice);
 
+#[cfg(feature = "web")]
 impl<'a> CanvasStateGuard<'a> {
     pub fn new(context: &'a dyn CanvasDevice) -> Self {
         context.save();
@@ -377,26 +390,32 @@ impl<'a> CanvasStateGuard<'a> {
     }
 }
 
+#[cfg(feature = "web")]
 impl Drop for CanvasStateGuard<'_> {
     fn drop(&mut self) {
         self.0.restore();
     }
 }
 
+#[cfg(feature = "web")]
 #[derive(Debug, Clone)]
 struct UnsafeMemorize<T>(T);
 
 // Safety: `UnsafeMemorize` is only used in wasm targets
+#[cfg(feature = "web")]
 unsafe impl<T> Send for UnsafeMemorize<T> {}
 // Safety: `UnsafeMemorize` is only used in wasm targets
+#[cfg(feature = "web")]
 unsafe impl<T> Sync for UnsafeMemorize<T> {}
 
+#[cfg(feature = "web")]
 #[derive(Debug, Clone)]
 struct LazyImage {
     elem: Promise,
     loaded: Arc<Mutex<Option<JsValue>>>,
 }
 
+#[cfg(feature = "web")]
 fn create_image(image: Arc<Image>) -> Option<LazyImage> {
     let is_svg = image.format.contains("svg");
 
@@ -471,6 +490,7 @@ fn create_image(image: Arc<Image>) -> Option<LazyImage> {
     elem.map(|elem| LazyImage { elem, loaded })
 }
 
+#[cfg(feature = "web")]
 pub fn html_image_to_bitmap(img: &HtmlImageElement) -> web_sys::ImageBitmap {
     let canvas = web_sys::OffscreenCanvas::new(img.width(), img.height()).unwrap();
 
@@ -489,6 +509,7 @@ pub fn html_image_to_bitmap(img: &HtmlImageElement) -> web_sys::ImageBitmap {
         .expect("transfer_to_image_bitmap")
 }
 
+#[cfg(feature = "web")]
 pub async fn exception_create_image_blob(blob: &Blob, image_elem: &HtmlImageElement) {
     let data_url = web_sys::Url::create_object_url_with_blob(blob).unwrap();
 
@@ -536,6 +557,7 @@ pub async fn exception_create_image_blob(blob: &Blob, image_elem: &HtmlImageElem
         .unwrap();
 }
 
+#[cfg(feature = "web")]
 #[comemo::memoize]
 fn rasterize_image(e: Arc<Image>) -> Option<UnsafeMemorize<LazyImage>> {
     create_image(e).map(UnsafeMemorize)
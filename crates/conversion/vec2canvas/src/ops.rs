@@ -3,7 +3,9 @@
 use async_trait::async_trait;
 use reflexo_vec2bbox::Vec2BBoxPass;
 
-use crate::{utils::EmptyFuture, CanvasDevice};
+use crate::utils::EmptyFuture;
+#[cfg(feature = "web")]
+use crate::CanvasDevice;
 use ecow::EcoVec;
 
 use std::{
@@ -15,23 +17,30 @@ use std::{
     },
 };
 
+#[cfg(feature = "web")]
 use js_sys::Promise;
 use tiny_skia as sk;
 
+#[cfg(feature = "web")]
 use wasm_bindgen::{prelude::Closure, JsCast, JsValue};
+#[cfg(feature = "web")]
 use web_sys::{CanvasWindingRule, ImageBitmap, OffscreenCanvas, Path2d};
 
 use reflexo::vector::ir::{
     self, FlatGlyphItem, Image, ImageItem, ImmutStr, PathStyle, Rect, Scalar,
 };
 
-use super::{rasterize_image, set_transform, BBoxAt, CanvasBBox, CanvasStateGuard};
+#[cfg(feature = "web")]
+use super::{rasterize_image, set_transform, CanvasStateGuard};
+use super::{BBoxAt, CanvasBBox};
 
 /// A reference to a canvas element.
 pub type CanvasNode = Arc<CanvasElem>;
+#[cfg(feature = "web")]
 /// 2d Context
 type Context2d = web_sys::CanvasRenderingContext2d;
 
+#[cfg(feature = "web")]
 /// The trait for all the operations that can be performed on some canvas
 /// element.
 #[async_trait(?Send)]
@@ -60,6 +69,7 @@ pub enum CanvasElem {
     Glyph(CanvasGlyphElem),
 }
 
+#[cfg(feature = "web")]
 #[async_trait(?Send)]
 impl CanvasOp for CanvasElem {
     fn prepare(
@@ -118,6 +128,7 @@ pub struct CanvasGroupElem {
     pub rect: CanvasBBox,
 }
 
+#[cfg(feature = "web")]
 #[async_trait(?Send)]
 impl CanvasOp for CanvasGroupElem {
     fn prepare(
@@ -190,6 +201,7 @@ impl CanvasClipElem {
             .bbox_at(ts, || Vec2BBoxPass::simple_path_bbox(&self.d, ts))
     }
 
+    #[cfg(feature = "web")]
     pub fn realize_with<'a>(
         &self,
         ts: sk::Transform,
@@ -206,6 +218,7 @@ impl CanvasClipElem {
     }
 }
 
+#[cfg(feature = "web")]
 #[async_trait(?Send)]
 impl CanvasOp for CanvasClipElem {
     fn prepare(
@@ -229,6 +242,7 @@ pub struct CanvasPathElem {
     pub rect: CanvasBBox,
 }
 
+#[cfg(feature = "web")]
 #[async_trait(?Send)]
 impl CanvasOp for CanvasPathElem {
     fn prepare(
@@ -342,6 +356,7 @@ pub struct CanvasImageElem {
     pub image_data: ImageItem,
 }
 
+#[cfg(feature = "web")]
 impl CanvasImageElem {
     fn prepare_image(image: Arc<Image>) -> Option<impl core::future::Future<Output = ()>> {
         let image_elem = rasterize_image(image.clone()).unwrap().0;
@@ -402,6 +417,7 @@ impl CanvasImageElem {
     }
 }
 
+#[cfg(feature = "web")]
 #[async_trait(?Send)]
 impl CanvasOp for CanvasImageElem {
     fn prepare(
@@ -424,6 +440,7 @@ pub struct CanvasGlyphElem {
     pub glyph_data: Arc<FlatGlyphItem>,
 }
 
+#[cfg(feature = "web")]
 #[async_trait(?Send)]
 impl CanvasOp for CanvasGlyphElem {
     fn prepare(
@@ -0,0 +1,469 @@
+//! Native rasterization of canvas elements.
+//!
+//! The browser realizes [`CanvasElem`]s on a `CanvasDevice`, which names
+//! `web_sys` types and is only available with the `web` feature. This module
+//! realizes the same element tree on a [`tiny_skia::Pixmap`], so that previews
+//! can be produced on the server side from the same vector IR and compared
+//! pixel by pixel with the browser.
+//!
+//! Both devices share the [`CanvasPage`]s built by [`CanvasTask`] and
+//! `IncrVec2CanvasPass`, so only the final realization differs.
+
+use std::sync::Arc;
+
+use reflexo::vector::{
+    ir::{self, FlatGlyphItem, Image, ImageAttr, ImageItem, Module, Page, PathStyle},
+    vm::RenderVm,
+};
+use reflexo_vec2bbox::convert_path;
+use tiny_skia as sk;
+
+use crate::{
+    CanvasClipElem, CanvasElem, CanvasGlyphElem, CanvasGroupElem, CanvasImageElem, CanvasPage,
+    CanvasPathElem, CanvasTask, ExportFeature,
+};
+
+/// A raster target backed by a [`sk::Pixmap`].
+pub struct RasterCanvas {
+    /// The pixels that are drawn so far.
+    pub pixmap: sk::Pixmap,
+    /// The clip mask that is currently applied.
+    clip: Option<sk::Mask>,
+}
+
+impl RasterCanvas {
+    /// Creates a transparent canvas with the given size in pixels.
+    pub fn new(width: u32, height: u32) -> Option<Self> {
+        Some(Self {
+            pixmap: sk::Pixmap::new(width, height)?,
+            clip: None,
+        })
+    }
+
+    /// Consumes the canvas and returns the underlying pixmap.
+    pub fn into_pixmap(self) -> sk::Pixmap {
+        self.pixmap
+    }
+
+    /// Returns the pixels as a non-premultiplied RGBA8 buffer.
+    pub fn to_rgba8(&self) -> Vec<u8> {
+        pixmap_to_rgba8(&self.pixmap)
+    }
+}
+
+/// Converts the premultiplied pixels of a pixmap into a non-premultiplied
+/// RGBA8 buffer, which is the layout of `ImageData` in the browser.
+pub fn pixmap_to_rgba8(pixmap: &sk::Pixmap) -> Vec<u8> {
+    let mut data = Vec::with_capacity(pixmap.data().len());
+    for pixel in pixmap.pixels() {
+        let c = pixel.demultiply();
+        data.extend_from_slice(&[c.red(), c.green(), c.blue(), c.alpha()]);
+    }
+    data
+}
+
+/// The trait for all the operations that can be performed on some canvas
+/// element without a browser. See [`crate::CanvasOp`] for the browser
+/// counterpart.
+pub trait RasterOp {
+    /// Realizes the action on the raster canvas.
+    fn rasterize(&self, ts: sk::Transform, canvas: &mut RasterCanvas);
+}
+
+impl RasterOp for CanvasElem {
+    fn rasterize(&self, ts: sk::Transform, canvas: &mut RasterCanvas) {
+        match self {
+            CanvasElem::Group(g) => g.rasterize(ts, canvas),
+            CanvasElem::Clip(g) => g.rasterize(ts, canvas),
+            CanvasElem::Path(g) => g.rasterize(ts, canvas),
+            CanvasElem::Image(g) => g.rasterize(ts, canvas),
+            CanvasElem::Glyph(g) => g.rasterize(ts, canvas),
+        }
+    }
+}
+
+impl RasterOp for CanvasGroupElem {
+    fn rasterize(&self, rts: sk::Transform, canvas: &mut RasterCanvas) {
+        let ts = rts.pre_concat(*self.ts.as_ref());
+
+        for (pos, sub_elem) in &self.inner {
+            let ts = ts.pre_translate(pos.x.0, pos.y.0);
+            sub_elem.rasterize(ts, canvas);
+        }
+    }
+}
+
+impl RasterOp for CanvasClipElem {
+    fn rasterize(&self, ts: sk::Transform, canvas: &mut RasterCanvas) {
+        if ts.sx == 0. || ts.sy == 0. {
+            return;
+        }
+        let Some(path) = convert_path(&self.d) else {
+            return;
+        };
+
+        let (w, h) = (canvas.pixmap.width(), canvas.pixmap.height());
+        let clip = match &canvas.clip {
+            Some(prev) => {
+                let mut mask = prev.clone();
+                mask.intersect_path(&path, sk::FillRule::Winding, true, ts);
+                mask
+            }
+            None => {
+                let Some(mut mask) = sk::Mask::new(w, h) else {
+                    return;
+                };
+                mask.fill_path(&path, sk::FillRule::Winding, true, ts);
+                mask
+            }
+        };
+
+        let prev = canvas.clip.replace(clip);
+        self.inner.rasterize(ts, canvas);
+        canvas.clip = prev;
+    }
+}
+
+impl RasterOp for CanvasPathElem {
+    fn rasterize(&self, ts: sk::Transform, canvas: &mut RasterCanvas) {
+        if ts.sx == 0. || ts.sy == 0. {
+            return;
+        }
+        let Some(path) = convert_path(&self.path_data.d) else {
+            return;
+        };
+
+        let mut fill = None;
+        let mut fill_rule = sk::FillRule::Winding;
+        let mut stroke_color = None;
+        let mut stroke = sk::Stroke::default();
+        let mut dash_array = None;
+        let mut dash_offset = 0.;
+
+        for style in &self.path_data.styles {
+            match style {
+                PathStyle::Fill(color) => fill = Some(color.clone()),
+                PathStyle::Stroke(color) => stroke_color = Some(color.clone()),
+                PathStyle::StrokeWidth(width) => stroke.width = width.0,
+                PathStyle::StrokeLineCap(cap) => {
+                    stroke.line_cap = match cap.as_ref() {
+                        "round" => sk::LineCap::Round,
+                        "square" => sk::LineCap::Square,
+                        _ => sk::LineCap::Butt,
+                    };
+                }
+                PathStyle::StrokeLineJoin(join) => {
+                    stroke.line_join = match join.as_ref() {
+                        "round" => sk::LineJoin::Round,
+                        "bevel" => sk::LineJoin::Bevel,
+                        _ => sk::LineJoin::Miter,
+                    };
+                }
+                PathStyle::StrokeMitterLimit(limit) => stroke.miter_limit = limit.0,
+                PathStyle::StrokeDashArray(array) => {
+                    dash_array = Some(array.iter().map(|d| d.0).collect::<Vec<_>>());
+                }
+                PathStyle::StrokeDashOffset(offset) => dash_offset = offset.0,
+                PathStyle::FillRule(rule) => {
+                    fill_rule = match rule.as_ref() {
+                        "evenodd" => sk::FillRule::EvenOdd,
+                        _ => sk::FillRule::Winding,
+                    };
+                }
+            }
+        }
+
+        if let Some(paint) = fill.as_deref().and_then(css_paint) {
+            canvas
+                .pixmap
+                .fill_path(&path, &paint, fill_rule, ts, canvas.clip.as_ref());
+        }
+
+        if let Some(paint) = stroke_color.as_deref().and_then(css_paint) {
+            if stroke.width.abs() <= 1e-5 {
+                return;
+            }
+
+            stroke.dash = dash_array.and_then(|array| sk::StrokeDash::new(array, dash_offset));
+            canvas
+                .pixmap
+                .stroke_path(&path, &paint, &stroke, ts, canvas.clip.as_ref());
+        }
+    }
+}
+
+impl RasterOp for CanvasImageElem {
+    fn rasterize(&self, ts: sk::Transform, canvas: &mut RasterCanvas) {
+        draw_image(ts, canvas, &self.image_data);
+    }
+}
+
+impl RasterOp for CanvasGlyphElem {
+    fn rasterize(&self, ts: sk::Transform, canvas: &mut RasterCanvas) {
+        if ts.sx == 0. || ts.sy == 0. {
+            return;
+        }
+
+        match self.glyph_data.as_ref() {
+            FlatGlyphItem::Outline(glyph) => {
+                let Some(path) = convert_path(&glyph.d) else {
+                    return;
+                };
+                let Some(paint) = css_paint(self.fill.as_ref()) else {
+                    return;
+                };
+
+                canvas.pixmap.fill_path(
+                    &path,
+                    &paint,
+                    sk::FillRule::Winding,
+                    ts,
+                    canvas.clip.as_ref(),
+                );
+            }
+            FlatGlyphItem::Image(glyph) => {
+                draw_image(ts.pre_concat(glyph.ts.into()), canvas, &glyph.image)
+            }
+            FlatGlyphItem::None => {}
+        }
+    }
+}
+
+fn draw_image(ts: sk::Transform, canvas: &mut RasterCanvas, image_data: &ImageItem) {
+    if ts.sx == 0. || ts.sy == 0. {
+        return;
+    }
+
+    let image = &image_data.image;
+    let Some(pixmap) = decode_image(image.clone()) else {
+        return;
+    };
+
+    // resize image to fit the view, the same as the browser canvas does
+    let (w, h) = {
+        let size = image_data.size;
+        let view_width = size.x.0;
+        let view_height = size.y.0;
+
+        let aspect = (image.width() as f32) / (image.height() as f32);
+
+        let w: f32 = view_width.max(aspect * view_height);
+        let h: f32 = w / aspect;
+        (w, h)
+    };
+
+    let pixelated = image_data.image.attrs.iter().any(|attr| {
+        matches!(attr, ImageAttr::ImageRendering(rendering) if rendering.as_ref() == "pixelated")
+    });
+    let paint = sk::PixmapPaint {
+        quality: if pixelated {
+            sk::FilterQuality::Nearest
+        } else {
+            sk::FilterQuality::Bicubic
+        },
+        ..Default::default()
+    };
+
+    let ts = ts.pre_scale(w / pixmap.width() as f32, h / pixmap.height() as f32);
+    canvas
+        .pixmap
+        .draw_pixmap(0, 0, pixmap.as_ref(), &paint, ts, canvas.clip.as_ref());
+}
+
+/// Decodes an image into a premultiplied pixmap.
+#[comemo::memoize]
+fn decode_image(image: Arc<Image>) -> Option<Arc<sk::Pixmap>> {
+    // todo: svg images
+    if image.format.contains("svg") {
+        return None;
+    }
+
+    let decoded = image::load_from_memory(&image.data).ok()?.into_rgba8();
+    let mut pixmap = sk::Pixmap::new(decoded.width(), decoded.height())?;
+    for (dst, src) in pixmap.pixels_mut().iter_mut().zip(decoded.pixels()) {
+        let [r, g, b, a] = src.0;
+        *dst = sk::ColorU8::from_rgba(r, g, b, a).premultiply();
+    }
+
+    Some(Arc::new(pixmap))
+}
+
+/// Creates a solid paint from a css color string.
+fn css_paint(color: &str) -> Option<sk::Paint<'static>> {
+    // todo: canvas gradient and pattern
+    let color = if color.starts_with('@') {
+        svgtypes::Color::black()
+    } else {
+        color.parse::<svgtypes::Color>().ok()?
+    };
+
+    let mut paint = sk::Paint::default();
+    paint.set_color_rgba8(color.red, color.green, color.blue, color.alpha);
+    paint.anti_alias = true;
+    Some(paint)
+}
+
+impl CanvasPage {
+    /// Rasterizes the page into a [`RasterCanvas`], which is the counterpart
+    /// of `IncrVec2CanvasPass::flush_page` in the browser.
+    ///
+    /// The `fill` is a css color string for the background. If it is empty,
+    /// the background is transparent.
+    pub fn rasterize(&self, pixel_per_pt: f32, fill: &str) -> Option<RasterCanvas> {
+        let width = (self.size.x.0 * pixel_per_pt).ceil().max(1.) as u32;
+        let height = (self.size.y.0 * pixel_per_pt).ceil().max(1.) as u32;
+        let mut canvas = RasterCanvas::new(width, height)?;
+
+        if let Some(paint) = (!fill.is_empty()).then(|| css_paint(fill)).flatten() {
+            let rect = sk::Rect::from_xywh(0., 0., width as f32, height as f32)?;
+            canvas
+                .pixmap
+                .fill_rect(rect, &paint, sk::Transform::identity(), None);
+        }
+
+        let ts = sk::Transform::from_scale(pixel_per_pt, pixel_per_pt);
+        self.elem.rasterize(ts, &mut canvas);
+
+        Some(canvas)
+    }
+}
+
+impl<Feat: ExportFeature> CanvasTask<Feat> {
+    /// Renders a page of the module into a [`RasterCanvas`].
+    ///
+    /// See [`CanvasPage::rasterize`] for the `fill`.
+    pub fn render_page_raster(
+        &mut self,
+        module: &Module,
+        page: &Page,
+        pixel_per_pt: f32,
+        fill: &str,
+    ) -> Option<RasterCanvas> {
+        let mut ct = self.fork_canvas_render_task(module);
+        let page = CanvasPage {
+            elem: ct.render_item(&page.content),
+            content: page.content,
+            size: page.size,
+        };
+
+        page.rasterize(pixel_per_pt, fill)
+    }
+
+    /// Renders each page of the module into a [`RasterCanvas`].
+    pub fn render_pages_raster(
+        &mut self,
+        module: &Module,
+        pages: &[Page],
+        pixel_per_pt: f32,
+        fill: &str,
+    ) -> Vec<Option<RasterCanvas>> {
+        pages
+            .iter()
+            .map(|page| self.render_page_raster(module, page, pixel_per_pt, fill))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reflexo::hash::Fingerprint;
+
+    use super::*;
+
+    #[test]
+    fn test_css_paint() {
+        let paint = css_paint("#ff000080").unwrap();
+        let c = paint.shader;
+        let sk::Shader::SolidColor(c) = c else {
+            panic!("expected solid color");
+        };
+        assert_eq!(c.to_color_u8(), sk::ColorU8::from_rgba(255, 0, 0, 128));
+
+        assert!(css_paint("@g0").is_some());
+        assert!(css_paint("none").is_none());
+    }
+
+    /// Creates a page of the size in pt, whose content is a group of the
+    /// items at the positions.
+    fn page(width: f32, height: f32, items: Vec<(ir::Point, ir::VecItem)>) -> (Module, Page) {
+        let page = Page {
+            content: Default::default(),
+            size: ir::Size::new(ir::Scalar(width), ir::Scalar(height)),
+            label: None,
+        };
+
+        let mut module = Module::default();
+        let mut group = vec![];
+        for (idx, (pos, item)) in items.into_iter().enumerate() {
+            let fingerprint = Fingerprint::from_u128(idx as u128 + 1);
+            module.items.insert(fingerprint, item);
+            group.push((pos, fingerprint));
+        }
+        module
+            .items
+            .insert(page.content, ir::VecItem::Group(ir::GroupRef(group.into())));
+
+        (module, page)
+    }
+
+    #[test]
+    fn test_empty_page_fill() {
+        let (module, page) = page(2., 3., vec![]);
+
+        let canvas = crate::DefaultCanvasTask::default()
+            .render_page_raster(&module, &page, 2., "#fff")
+            .unwrap();
+        assert_eq!(canvas.pixmap.width(), 4);
+        assert_eq!(canvas.pixmap.height(), 6);
+        assert!(canvas.to_rgba8().iter().all(|&c| c == 255));
+    }
+
+    #[test]
+    fn test_rasterize_rect() {
+        let rect = ir::VecItem::Path(ir::PathItem {
+            d: "M 0 0 L 4 0 L 4 2 L 0 2 Z".into(),
+            size: Some(ir::Size::new(ir::Scalar(4.), ir::Scalar(2.))),
+            styles: vec![PathStyle::Fill("#f00".into())],
+        });
+        let pos = ir::Point::new(ir::Scalar(2.), ir::Scalar(3.));
+        let (module, page) = page(10., 10., vec![(pos, rect)]);
+
+        // The rect covers the pixels from (4, 6) to (12, 10) at 2 pixels per pt.
+        let canvas = crate::DefaultCanvasTask::default()
+            .render_page_raster(&module, &page, 2., "#fff")
+            .unwrap();
+        let pixel = |x: u32, y: u32| {
+            let c = canvas.pixmap.pixel(x, y).unwrap().demultiply();
+            [c.red(), c.green(), c.blue(), c.alpha()]
+        };
+        let (red, white) = ([255, 0, 0, 255], [255, 255, 255, 255]);
+
+        assert_eq!(pixel(4, 6), red);
+        assert_eq!(pixel(8, 8), red);
+        assert_eq!(pixel(11, 9), red);
+        assert_eq!(pixel(3, 6), white);
+        assert_eq!(pixel(4, 5), white);
+        assert_eq!(pixel(12, 9), white);
+        assert_eq!(pixel(11, 10), white);
+        assert_eq!(pixel(0, 0), white);
+    }
+
+    #[cfg(feature = "incremental")]
+    #[test]
+    fn test_incr_pass_rasterize_page() {
+        let (module, page) = page(2., 3., vec![]);
+
+        let mut pass = crate::IncrVec2CanvasPass {
+            pixel_per_pt: 1.,
+            fill: "".into(),
+            ..Default::default()
+        };
+        pass.interpret_changes(&module, std::slice::from_ref(&page));
+
+        let canvas = pass.rasterize_page(0).unwrap();
+        assert_eq!(canvas.pixmap.width(), 2);
+        assert_eq!(canvas.pixmap.height(), 3);
+        assert!(canvas.to_rgba8().iter().all(|&c| c == 0));
+        assert!(pass.rasterize_page(1).is_none());
+    }
+}
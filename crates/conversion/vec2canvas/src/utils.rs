@@ -21,7 +21,9 @@ macro_rules! console_log {
 #[allow(unused_imports)]
 pub(crate) use console_log;
 
+#[cfg(feature = "web")]
 pub(crate) struct EmptyFuture;
+#[cfg(feature = "web")]
 impl core::future::Future for EmptyFuture {
     type Output = ();
 
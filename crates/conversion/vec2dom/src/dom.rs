@@ -245,6 +245,14 @@ impl DomPage {
                 .unwrap();
         }
 
+        let prev_label = self.layout_data.as_ref().map(|d| &d.label);
+        if prev_label.map(|l| *l != data.label).unwrap_or(true) {
+            match &data.label {
+                Some(label) => self.elem.set_attribute("data-page-label", label).unwrap(),
+                None => self.elem.remove_attribute("data-page-label").unwrap(),
+            }
+        }
+
         // todo: cache
         let prev_data = self.layout_data.clone();
         if prev_data.map(|d| d != data).unwrap_or(true) {
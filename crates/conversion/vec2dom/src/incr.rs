@@ -244,7 +244,7 @@ impl IncrDomDocClient {
 
     // todo: move to js world
     fn checkout_layout(&mut self, kern: &mut IncrDocClient, viewport: Option<tiny_skia::Rect>) {
-        let layouts = kern.doc.layouts[0].by_scalar();
+        let layouts = kern.width_layouts();
         let Some(layouts) = layouts else {
             return;
         };
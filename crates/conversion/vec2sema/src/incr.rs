@@ -30,7 +30,7 @@ impl IncrVec2SemaPass {
         let pages = pages
             .iter()
             .enumerate()
-            .map(|(idx, Page { content, size })| {
+            .map(|(idx, Page { content, size, .. })| {
                 if idx < self.pages.len() && self.pages[idx].content == *content {
                     return self.pages[idx].clone();
                 }
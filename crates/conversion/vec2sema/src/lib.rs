@@ -1,4 +1,5 @@
 mod incr;
+mod search;
 
 use std::{
     borrow::Cow,
@@ -14,6 +15,7 @@ use reflexo_vec2canvas::BrowserFontMetric;
 use unicode_width::UnicodeWidthChar;
 
 pub use incr::*;
+pub use search::*;
 
 pub struct SemaTask {
     heavy: bool,
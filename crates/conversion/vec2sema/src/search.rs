@@ -0,0 +1,334 @@
+//! Text search over the vector document.
+//!
+//! The search walks the text items of pages in the order they are laid out,
+//! so it doesn't need typst to be run again.
+
+use reflexo::vector::ir::{FontItem, Module, Page, Rect, Scalar};
+use reflexo_typst2vec::text::{page_text_runs, TextRun};
+
+/// The options of a text search.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SearchOptions {
+    /// Whether to match the text case-insensitively.
+    pub ignore_case: bool,
+    /// Whether to match whole words only.
+    pub whole_word: bool,
+}
+
+/// A match of a text search.
+#[derive(Debug, Clone)]
+pub struct SearchMatch<S> {
+    /// The index of the page containing the match.
+    pub page: usize,
+    /// The rectangles covering the matched text in page coordinates, one per
+    /// text item.
+    pub rects: Vec<Rect>,
+    /// The span of the first text item of the match, if it can be resolved.
+    pub span: Option<S>,
+}
+
+/// Searches the pattern in the pages.
+///
+/// The span of a match is resolved from the element path of its first text
+/// item by `resolve_span`, e.g. by
+/// [`reflexo::vector::incr::IncrDocClientKern::source_span`].
+pub fn search<S>(
+    module: &Module,
+    pages: &[Page],
+    pattern: &str,
+    options: SearchOptions,
+    mut resolve_span: impl FnMut(&[u32]) -> Option<S>,
+) -> Vec<SearchMatch<S>> {
+    if pattern.is_empty() {
+        return vec![];
+    }
+
+    let mut matches = vec![];
+    for (idx, page) in pages.iter().enumerate() {
+        let runs = page_text_runs(module, idx, &page.content)
+            .into_iter()
+            .filter_map(|run| {
+                let font = module.get_font(&run.text.shape.font)?;
+                let rect = run.bbox(font, Scalar(0.), run.text.width());
+                Some(SearchRun { run, font, rect })
+            })
+            .collect();
+        let task = SearchTask { runs };
+
+        let found = task.search(pattern, options).into_iter();
+        matches.extend(found.map(|(first, last)| SearchMatch {
+            page: idx,
+            rects: task.rects(first, last),
+            span: resolve_span(&task.runs[first.0].run.path),
+        }));
+    }
+
+    matches
+}
+
+/// A text item laid out on a page, with its bounding box.
+struct SearchRun<'a> {
+    run: TextRun<'a>,
+    font: &'a FontItem,
+    rect: Rect,
+}
+
+impl SearchRun<'_> {
+    /// Gets the offset of the character along the baseline of the run.
+    fn char_offset(&self, char_idx: usize) -> Scalar {
+        let text = self.run.text;
+        let content = text.content.as_ref();
+        let char_count = content.content.chars().count();
+        if char_idx >= char_count {
+            return text.width();
+        }
+
+        // Glyphs map to characters one by one unless there are ligatures or
+        // clusters, in which case the offset is estimated.
+        if content.glyphs.len() == char_count {
+            let advance = content.glyphs[..char_idx].iter().map(|g| g.1.x.0).sum();
+            Scalar(advance)
+        } else {
+            Scalar(text.width().0 * char_idx as f32 / char_count as f32)
+        }
+    }
+}
+
+struct SearchTask<'a> {
+    runs: Vec<SearchRun<'a>>,
+}
+
+impl SearchTask<'_> {
+    /// Finds the matches, each from the first char to the last char in the
+    /// form of `(run_idx, char_idx)`.
+    fn search(
+        &self,
+        pattern: &str,
+        options: SearchOptions,
+    ) -> Vec<((usize, usize), (usize, usize))> {
+        // The haystack with the run index and char index of each char.
+        let mut haystack = String::new();
+        let mut origins = vec![];
+        for (run_idx, run) in self.runs.iter().enumerate() {
+            if let Some(prev) = run_idx.checked_sub(1).map(|idx| &self.runs[idx]) {
+                // Breaks words between runs which don't touch each other.
+                let same_line = (prev.rect.hi.y - run.rect.hi.y).0.abs() < 1e-3;
+                let gap = (run.rect.lo.x - prev.rect.hi.x).0;
+                if !same_line || gap > 0.1 * (run.rect.hi.y - run.rect.lo.y).0 {
+                    haystack.push(' ');
+                    origins.push(None);
+                }
+            }
+
+            for (char_idx, c) in run.run.text.content.content.chars().enumerate() {
+                let origin = Some((run_idx, char_idx));
+                if options.ignore_case {
+                    for c in c.to_lowercase() {
+                        haystack.push(c);
+                        origins.push(origin);
+                    }
+                } else {
+                    haystack.push(c);
+                    origins.push(origin);
+                }
+            }
+        }
+
+        let pattern = if options.ignore_case {
+            pattern.to_lowercase()
+        } else {
+            pattern.to_owned()
+        };
+
+        find_matches(&haystack, &pattern, options.whole_word)
+            .into_iter()
+            .filter_map(|(start, end)| {
+                let chars = origins[start..end].iter().flatten();
+                let (first, last) = (chars.clone().next()?, chars.last()?);
+                Some((*first, *last))
+            })
+            .collect()
+    }
+
+    /// Gets the rects covering the chars from the first char to the last char.
+    fn rects(&self, first: (usize, usize), last: (usize, usize)) -> Vec<Rect> {
+        (first.0..=last.0)
+            .map(|run_idx| {
+                let run = &self.runs[run_idx];
+                let lo = if run_idx == first.0 {
+                    run.char_offset(first.1)
+                } else {
+                    Scalar(0.)
+                };
+                let hi = if run_idx == last.0 {
+                    run.char_offset(last.1 + 1)
+                } else {
+                    run.run.text.width()
+                };
+                run.run.bbox(run.font, lo, hi)
+            })
+            .collect()
+    }
+}
+
+/// Finds the char ranges of the non-overlapping occurrences of the pattern.
+fn find_matches(haystack: &str, pattern: &str, whole_word: bool) -> Vec<(usize, usize)> {
+    let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+
+    let chars = haystack.chars().collect::<Vec<_>>();
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    if pattern.is_empty() {
+        return vec![];
+    }
+
+    let mut matches = vec![];
+    let mut start = 0;
+    while start + pattern.len() <= chars.len() {
+        let end = start + pattern.len();
+        let is_match = chars[start..end] == pattern[..]
+            && !(whole_word
+                && (is_word(start.checked_sub(1).map(|idx| chars[idx]))
+                    || is_word(chars.get(end).copied())));
+        if is_match {
+            matches.push((start, end));
+            start = end;
+        } else {
+            start += 1;
+        }
+    }
+
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use reflexo::hash::Fingerprint;
+    use reflexo::vector::ir::{self, Axes, Point, Transform, TransformItem, VecItem};
+
+    use super::*;
+
+    #[test]
+    fn test_find_matches() {
+        assert_eq!(
+            find_matches("typst types", "typ", false),
+            vec![(0, 3), (6, 9)]
+        );
+        assert_eq!(find_matches("typst typ", "typ", true), vec![(6, 9)]);
+        assert_eq!(find_matches("aaaa", "aa", false), vec![(0, 2), (2, 4)]);
+        assert_eq!(find_matches("a", "", false), vec![]);
+    }
+
+    /// Creates a page with a text item of 5pt wide chars at `(10, 20)`, whose
+    /// cap height is 8pt, transformed by the transform.
+    fn transformed_page(content: &str, ts: Transform) -> (Module, Page) {
+        let font = ir::FontItem {
+            fingerprint: Fingerprint::from_u128(1),
+            family: "test".into(),
+            hash: 0,
+            cap_height: Scalar(0.8),
+            ascender: Scalar(0.9),
+            descender: Scalar(-0.2),
+            units_per_em: Scalar(1000.),
+            vertical: false,
+            glyphs: vec![],
+            glyph_cov: Default::default(),
+        };
+        let glyphs = content.chars().enumerate().map(|(idx, _)| {
+            let advance = Axes {
+                x: Scalar(5.),
+                y: Scalar(0.),
+            };
+            (Axes::default(), advance, idx as u32)
+        });
+        let text = VecItem::Text(ir::TextItem {
+            shape: Arc::new(ir::TextShape {
+                font: ir::FontRef { hash: 0, idx: 0 },
+                dir: "ltr".into(),
+                size: Scalar(10.),
+                styles: vec![],
+            }),
+            content: Arc::new(ir::TextItemContent {
+                content: content.into(),
+                glyphs: glyphs.collect(),
+            }),
+        });
+
+        let (text_fg, item_fg, page_fg) = (
+            Fingerprint::from_u128(2),
+            Fingerprint::from_u128(3),
+            Fingerprint::from_u128(4),
+        );
+        let item = VecItem::Item(ir::TransformedRef(
+            TransformItem::Matrix(Arc::new(ts)),
+            text_fg,
+        ));
+        let pos = Point::new(Scalar(10.), Scalar(20.));
+        let group = VecItem::Group(ir::GroupRef(Arc::new([(pos, item_fg)])));
+
+        let mut module = Module::default();
+        module.fonts.push(font);
+        module
+            .items
+            .extend([(text_fg, text), (item_fg, item), (page_fg, group)]);
+        let page = Page {
+            content: page_fg,
+            size: Axes {
+                x: Scalar(100.),
+                y: Scalar(100.),
+            },
+            label: None,
+        };
+        (module, page)
+    }
+
+    fn search_rects(content: &str, ts: Transform, pattern: &str) -> Vec<[f32; 4]> {
+        let (module, page) = transformed_page(content, ts);
+        let matches = search(&module, &[page], pattern, SearchOptions::default(), |_| {
+            Some(())
+        });
+        assert_eq!(matches.len(), 1);
+
+        let rects = matches[0].rects.iter();
+        let rounded = |v: Scalar| (v.0 * 1e3).round() / 1e3;
+        rects
+            .map(|r| [r.lo.x, r.lo.y, r.hi.x, r.hi.y].map(rounded))
+            .collect()
+    }
+
+    #[test]
+    fn test_search_transformed_text() {
+        // The text spans from `-8` to `2` vertically around the baseline.
+        let identity = search_rects("typst", Transform::identity(), "ps");
+        assert_eq!(identity, vec![[20., 12., 30., 22.]]);
+
+        let scaled = Transform::from_scale(Scalar(2.), Scalar(3.));
+        let scaled = search_rects("typst", scaled, "ps");
+        assert_eq!(scaled, vec![[30., -4., 50., 26.]]);
+
+        // Rotates by 90 degrees clockwise around the origin of the text.
+        let rotated = Transform {
+            sx: Scalar(0.),
+            ky: Scalar(1.),
+            kx: Scalar(-1.),
+            sy: Scalar(0.),
+            tx: Scalar(0.),
+            ty: Scalar(0.),
+        };
+        let rotated = search_rects("typst", rotated, "ps");
+        assert_eq!(rotated, vec![[8., 30., 18., 40.]]);
+    }
+
+    #[test]
+    fn test_search_resolves_span() {
+        let (module, page) = transformed_page("typst", Transform::identity());
+        let matches = search(&module, &[page], "typ", SearchOptions::default(), |path| {
+            Some(path.to_vec())
+        });
+
+        // The page, the root group and the text item in the group.
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].span, Some(vec![4, 0, 1, 0, 0, 0]));
+    }
+}
@@ -435,9 +435,18 @@ impl<
             )
         };
 
+        let mut target_attrs = String::new();
+        if let Some(target) = &link.target {
+            target_attrs.push_str(&format!(r#" data-target="{}""#, target.anchor));
+            if let Some(label) = &target.label {
+                let label = escape::escape_str::<AttributeEscapes>(label);
+                target_attrs.push_str(&format!(r#" data-target-label="{label}""#));
+            }
+        }
+
         self.content.push(SvgText::Plain(format!(
-            r#"<a {}><rect class="pseudo-link" width="{}" height="{}"></rect></a>"#,
-            href_handler, link.size.x.0, link.size.y.0,
+            r#"<a {}{}><rect class="pseudo-link" width="{}" height="{}"></rect></a>"#,
+            href_handler, target_attrs, link.size.x.0, link.size.y.0,
         )))
     }
 
@@ -54,13 +54,13 @@ pub struct RenderContext<'m, 't, Feat: ExportFeature> {
     /// Stores the patterns used in the document.
     pub(crate) patterns: &'t mut PaintFillMap,
 
-    /// See [`ExportFeature`].
+    /// See [`crate::SvgExportOptions`].
     pub should_render_text_element: bool,
     /// See [`ExportFeature`].
     pub should_attach_debug_info: bool,
-    /// See [`ExportFeature`].
+    /// See [`crate::SvgExportOptions`].
     pub use_stable_glyph_id: bool,
-    /// See [`ExportFeature`].
+    /// See [`crate::SvgExportOptions`].
     pub should_rasterize_text: bool,
 
     pub _feat_phantom: std::marker::PhantomData<Feat>,
@@ -69,17 +69,17 @@ pub struct RenderContext<'m, 't, Feat: ExportFeature> {
 impl<Feat: ExportFeature> DynExportFeature for RenderContext<'_, '_, Feat> {
     #[inline]
     fn should_render_text_element(&self) -> bool {
-        Feat::SHOULD_RENDER_TEXT_ELEMENT && self.should_render_text_element
+        self.should_render_text_element
     }
 
     #[inline]
     fn use_stable_glyph_id(&self) -> bool {
-        Feat::USE_STABLE_GLYPH_ID && self.use_stable_glyph_id
+        self.use_stable_glyph_id
     }
 
     #[inline]
     fn should_rasterize_text(&self) -> bool {
-        Feat::SHOULD_RASTERIZE_TEXT && self.should_rasterize_text
+        self.should_rasterize_text
     }
 
     #[inline]
@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use reflexo::typst::TypstDocument;
 use reflexo_typst2vec::ir::{Abs, LayoutRegion, LayoutRegionNode, MultiVecDocument};
 use reflexo_typst2vec::pass::Typst2VecPass;
@@ -16,11 +18,13 @@ impl DynamicLayoutSvgExporter {
         // let mut t = LowerBuilder::new(output);
 
         let pages = self.typst2vec.doc(output);
+        // The headings are moved across pages by the width of each layout.
+        let outline = Arc::new(self.typst2vec.outline(output.introspector()));
 
         // log::trace!("svg dynamic layout render time: {:?}",
         // instant.elapsed());
 
-        LayoutRegionNode::new_pages(pages)
+        LayoutRegionNode::new_pages_with_outline(pages, outline)
     }
 
     pub fn finalize(self) -> MultiVecDocument {
@@ -28,6 +32,7 @@ impl DynamicLayoutSvgExporter {
         MultiVecDocument {
             module,
             layouts: vec![LayoutRegion::new_by_scalar("width".into(), self.layouts)],
+            outline: None,
         }
     }
 }
@@ -1,5 +1,6 @@
 use std::sync::Arc;
 
+use reflexo::escape::{self, AttributeEscapes};
 use reflexo::hash::Fingerprint;
 use reflexo::typst::TypstPagedDocument;
 use reflexo::vector::{
@@ -10,7 +11,7 @@ use reflexo_typst2vec::pass::Typst2VecPass;
 
 use crate::{
     backend::{generate_text, SvgText, SvgTextNode},
-    ExportFeature, SvgDataSelection, SvgExporter, SvgTask,
+    ExportFeature, SvgDataSelection, SvgExportOptions, SvgExporter, SvgTask,
 };
 
 impl<Feat: ExportFeature> SvgTask<'_, Feat> {
@@ -23,14 +24,20 @@ impl<Feat: ExportFeature> SvgTask<'_, Feat> {
             let entry = &page.content;
             let size = Self::page_size(page.size);
 
+            let mut attributes = vec![
+                ("class", "typst-page".into()),
+                ("transform", format!("translate(0, {acc_height})")),
+                ("data-tid", entry.as_svg_id("p")),
+                ("data-page-width", size.x.to_string()),
+                ("data-page-height", size.y.to_string()),
+            ];
+            if let Some(label) = &page.label {
+                let label = escape::escape_str::<AttributeEscapes>(label);
+                attributes.push(("data-page-label", label.into_owned()));
+            }
+
             svg_body.push(SvgText::Content(Arc::new(SvgTextNode {
-                attributes: vec![
-                    ("class", "typst-page".into()),
-                    ("transform", format!("translate(0, {acc_height})")),
-                    ("data-tid", entry.as_svg_id("p")),
-                    ("data-page-width", size.x.to_string()),
-                    ("data-page-height", size.y.to_string()),
-                ],
+                attributes,
                 content: vec![SvgText::Content(render_task.render_item(entry))],
             })));
             acc_height += size.y;
@@ -61,16 +68,22 @@ impl<Feat: ExportFeature> SvgExporter<Feat> {
     pub fn svg_doc(output: &TypstPagedDocument) -> VecDocument {
         let typst2vec = Typst2VecPass::default();
         let pages = typst2vec.paged(output);
+        let outline = Some(Arc::new(typst2vec.outline(&output.introspector)));
 
         let module = typst2vec.finalize();
-        VecDocument { pages, module }
+        VecDocument {
+            pages,
+            module,
+            outline,
+        }
     }
 
     pub fn render_flat_svg(
         module: &Module,
         pages: &[Page],
         parts: Option<SvgDataSelection>,
+        options: &SvgExportOptions,
     ) -> String {
-        generate_text(Self::render(module, pages, parts))
+        generate_text(Self::render_with(module, pages, parts, options))
     }
 }
@@ -0,0 +1,135 @@
+use std::collections::{HashMap, HashSet};
+
+use reflexo::hash::hash128;
+use reflexo::vector::vm::RenderVm;
+use reflexo_typst2vec::pass::Typst2VecPass;
+use typst::introspection::Introspector;
+use typst::layout::Frame;
+
+use crate::{
+    backend::{generate_text, SvgText},
+    transform, ExportFeature, SvgExportOptions, SvgExporter, SvgTask,
+};
+
+/// The SVG images of standalone frames, e.g. the frames embedded in a HTML
+/// document, deduplicated by the hashes of the frames.
+#[derive(Debug, Clone, Default)]
+pub struct SvgFrames {
+    /// The definitions shared by the frames, e.g. glyphs, as a hidden SVG.
+    ///
+    /// It is empty if the frames are self-contained. Otherwise, it must be
+    /// placed in the same HTML document as the frames.
+    pub defs: String,
+    /// The SVG images, keyed by the hashes of the frames.
+    pub frames: HashMap<u128, String>,
+}
+
+impl SvgFrames {
+    /// Gets the SVG image of a frame.
+    pub fn get(&self, frame: &Frame) -> Option<&str> {
+        self.frames.get(&hash128(frame)).map(String::as_str)
+    }
+
+    /// Gets the relative path to store the SVG image of a frame as an
+    /// external file, which is stable across exports.
+    pub fn path(frame: &Frame) -> String {
+        Self::path_by_hash(hash128(frame))
+    }
+
+    /// Iterates the external files of the frames, as pairs of the relative
+    /// paths and the SVG images.
+    pub fn files(&self) -> impl Iterator<Item = (String, &str)> {
+        self.frames
+            .iter()
+            .map(|(hash, svg)| (Self::path_by_hash(*hash), svg.as_str()))
+    }
+
+    fn path_by_hash(hash: u128) -> String {
+        format!("frames/{hash:032x}.svg")
+    }
+}
+
+impl<Feat: ExportFeature> SvgExporter<Feat> {
+    /// Render standalone frames, e.g. the frames embedded in a HTML document.
+    ///
+    /// If `shared_defs` is set, the definitions of all frames are rendered
+    /// once into [`SvgFrames::defs`], so that a glyph used by many frames is
+    /// only emitted once. Otherwise, each frame is rendered into a
+    /// self-contained SVG.
+    pub fn render_frames<'f>(
+        introspector: &Introspector,
+        frames: impl IntoIterator<Item = &'f Frame>,
+        shared_defs: bool,
+        options: &SvgExportOptions,
+    ) -> SvgFrames {
+        let mut hashes = HashSet::new();
+        let frames = frames
+            .into_iter()
+            .filter(|frame| hashes.insert(hash128(frame)))
+            .collect::<Vec<_>>();
+
+        if !shared_defs {
+            let frames = frames
+                .into_iter()
+                .map(|frame| {
+                    let pass = Typst2VecPass::default();
+                    let pages = pass.frames(introspector, [frame]);
+                    let mut module = pass.finalize();
+                    module.prepare_glyphs();
+
+                    let svg = Self::render_with(&module, &pages, None, options);
+                    (hash128(frame), generate_text(transform::minify(svg)))
+                })
+                .collect();
+
+            return SvgFrames {
+                defs: String::new(),
+                frames,
+            };
+        }
+
+        let pass = Typst2VecPass::default();
+        let pages = pass.frames(introspector, frames.iter().copied());
+        let mut module = pass.finalize();
+        module.prepare_glyphs();
+
+        let mut t = SvgTask::<Feat>::new(*options);
+        let bodies = {
+            let mut render_task = t.get_render_context(&module);
+            pages
+                .iter()
+                .map(|page| render_task.render_item(&page.content))
+                .collect::<Vec<_>>()
+        };
+
+        let frames = frames
+            .iter()
+            .zip(pages.iter().zip(bodies))
+            .map(|(frame, (page, body))| {
+                let svg = vec![
+                    SvgText::Plain(Self::header(std::slice::from_ref(page))),
+                    SvgText::Content(body),
+                    "</svg>".into(),
+                ];
+                (hash128(frame), generate_text(transform::minify(svg)))
+            })
+            .collect();
+
+        let mut defs = vec![SvgText::Plain(
+            r#"<svg style="position: absolute; width: 0; height: 0;" aria-hidden="true" xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink">"#
+                .to_owned(),
+        )];
+        if options.with_builtin_css::<Feat>() {
+            defs.push(r#"<style type="text/css">"#.into());
+            defs.push(include_str!("./typst.svg.css").into());
+            defs.push("</style>".into());
+        }
+        Self::defs(t, &module, &mut defs);
+        defs.push("</svg>".into());
+
+        SvgFrames {
+            defs: generate_text(transform::minify(defs)),
+            frames,
+        }
+    }
+}
@@ -4,6 +4,7 @@ use std::{
     sync::Arc,
 };
 
+use reflexo::escape::{self, AttributeEscapes};
 use reflexo::hash::Fingerprint;
 use reflexo_typst2vec::{
     incr::{IncrDocClient, IncrDocServer},
@@ -13,7 +14,7 @@ use reflexo_typst2vec::{
 
 use crate::{
     backend::{SvgText, SvgTextNode},
-    ExportFeature, SvgExporter, SvgTask,
+    ExportFeature, SvgExportOptions, SvgExporter, SvgTask,
 };
 
 /// The feature set which is used for exporting incremental rendered svg.
@@ -71,6 +72,7 @@ impl<Feat: ExportFeature> SvgTask<'_, Feat> {
         for Page {
             content: entry,
             size: size_f32,
+            label,
         } in ctx.next.iter()
         {
             let size = Self::page_size(*size_f32);
@@ -81,6 +83,12 @@ impl<Feat: ExportFeature> SvgTask<'_, Feat> {
                 ("data-page-width", size.x.to_string()),
                 ("data-page-height", size.y.to_string()),
             ];
+            if let Some(label) = label {
+                attributes.push((
+                    "data-page-label",
+                    escape::escape_str::<AttributeEscapes>(label).into_owned(),
+                ));
+            }
 
             if *entry == NULL_PAGE {
                 attributes.push(("data-dummy", "1".into()));
@@ -140,6 +148,8 @@ pub struct IncrSvgDocClient {
     /// Assmuing glyph_window = N, then `self.doc.module.glyphs[..N]` are
     /// committed.
     pub glyph_window: usize,
+    /// The runtime options of rendering.
+    pub options: SvgExportOptions,
 }
 
 impl IncrSvgDocClient {
@@ -153,6 +163,12 @@ impl IncrSvgDocClient {
         self.glyph_window = 0;
     }
 
+    /// Sets the runtime options, which take effect on the pages rendered
+    /// afterwards.
+    pub fn set_options(&mut self, options: SvgExportOptions) {
+        self.options = options;
+    }
+
     /// Render the document in the given window.
     pub fn render_in_window(&mut self, kern: &mut IncrDocClient, rect: Rect) -> String {
         type IncrExporter = SvgExporter<IncrementalExportFeature>;
@@ -180,6 +196,7 @@ impl IncrSvgDocClient {
                     next_doc_view.push(Page {
                         content: NULL_PAGE,
                         size: page.size,
+                        label: page.label.clone(),
                     });
                     continue;
                 }
@@ -189,7 +206,7 @@ impl IncrSvgDocClient {
         }
         // todo: fix this
 
-        let mut t = SvgTask::<IncrementalExportFeature>::default();
+        let mut t = SvgTask::<IncrementalExportFeature>::new(self.options);
 
         // start to render document difference
         let mut svg = Vec::<SvgText>::new();
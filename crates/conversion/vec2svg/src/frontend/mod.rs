@@ -1,9 +1,11 @@
 pub(crate) mod context;
 pub(crate) mod dynamic_layout;
 pub(crate) mod flat;
+pub(crate) mod frames;
 pub(crate) mod incremental;
 
 pub use dynamic_layout::DynamicLayoutSvgExporter;
+pub use frames::SvgFrames;
 pub use incremental::{IncrSvgDocClient, IncrSvgDocServer, IncrementalRenderContext};
 
 use std::{collections::HashSet, f32::consts::TAU, fmt::Write, sync::Arc};
@@ -24,7 +26,7 @@ use typst::{
 
 use crate::{
     backend::{SvgGlyphBuilder, SvgText, SvgTextNode},
-    ExportFeature, SvgDataSelection,
+    ExportFeature, SvgDataSelection, SvgExportOptions,
 };
 use context::{PaintFillMap, RenderContext, StyleDefMap};
 
@@ -306,19 +308,10 @@ impl<Feat: ExportFeature> SvgExporter<Feat> {
         }
     }
 
-    /// Render pages into the entire SVG
-    pub fn render(
-        module: &Module,
-        pages: &[Page],
-        parts: Option<SvgDataSelection>,
-    ) -> Vec<SvgText> {
-        if !module.glyphs.is_empty() {
-            panic!("Glyphs should be loaded before rendering.");
-        }
-
-        let mut t = SvgTask::<Feat>::default();
-        let mut svg_body = vec![];
-        t.render(module, pages, &mut svg_body);
+    /// Render the definitions used by the items rendered by the task.
+    /// <svg> <defs/> <style/> .. </svg>
+    ///       ^^^^^^^^^^^^^^^^
+    pub(crate) fn defs(mut t: SvgTask<Feat>, module: &Module, svg: &mut Vec<SvgText>) {
         let patterns = t.render_patterns(module);
 
         // note in order!: pattern may use glyphs
@@ -337,6 +330,41 @@ impl<Feat: ExportFeature> SvgExporter<Feat> {
                 }
             });
 
+        // attach the glyph defs, clip paths, and style defs
+        svg.push(r#"<defs class="glyph">"#.into());
+        svg.extend(glyphs);
+        svg.push("</defs>".into());
+        svg.push(r#"<defs class="clip-path">"#.into());
+        Self::gradients(gradients, svg);
+        Self::patterns(patterns.into_iter(), svg);
+        svg.push("</defs>".into());
+        Self::style_defs(t.style_defs, svg);
+    }
+
+    /// Render pages into the entire SVG
+    pub fn render(
+        module: &Module,
+        pages: &[Page],
+        parts: Option<SvgDataSelection>,
+    ) -> Vec<SvgText> {
+        Self::render_with(module, pages, parts, &SvgExportOptions::default())
+    }
+
+    /// Render pages into the entire SVG with the runtime options.
+    pub fn render_with(
+        module: &Module,
+        pages: &[Page],
+        parts: Option<SvgDataSelection>,
+        options: &SvgExportOptions,
+    ) -> Vec<SvgText> {
+        if !module.glyphs.is_empty() {
+            panic!("Glyphs should be loaded before rendering.");
+        }
+
+        let mut t = SvgTask::<Feat>::new(*options);
+        let mut svg_body = vec![];
+        t.render(module, pages, &mut svg_body);
+
         let parts = parts.as_ref();
         let with_css = parts.is_none_or(|parts| parts.css);
         let with_defs = parts.is_none_or(|parts| parts.defs);
@@ -348,22 +376,14 @@ impl<Feat: ExportFeature> SvgExporter<Feat> {
             // base style
         ];
 
-        if Feat::WITH_BUILTIN_CSS && with_css {
+        if options.with_builtin_css::<Feat>() && with_css {
             svg.push(r#"<style type="text/css">"#.into());
             svg.push(include_str!("./typst.svg.css").into());
             svg.push("</style>".into());
         }
 
         if with_defs {
-            // attach the glyph defs, clip paths, and style defs
-            svg.push(r#"<defs class="glyph">"#.into());
-            svg.extend(glyphs);
-            svg.push("</defs>".into());
-            svg.push(r#"<defs class="clip-path">"#.into());
-            Self::gradients(gradients, &mut svg);
-            Self::patterns(patterns.into_iter(), &mut svg);
-            svg.push("</defs>".into());
-            Self::style_defs(t.style_defs, &mut svg);
+            Self::defs(t, module, &mut svg);
         }
 
         // body
@@ -371,7 +391,7 @@ impl<Feat: ExportFeature> SvgExporter<Feat> {
             svg.append(&mut svg_body);
         }
 
-        if Feat::WITH_RESPONSIVE_JS && with_js {
+        if options.with_responsive_js::<Feat>() && with_js {
             // attach the javascript for animations
             svg.push(r#"<script type="text/javascript">"#.into());
             svg.push(include_str!("./typst.svg.js").into());
@@ -398,6 +418,9 @@ pub struct SvgTask<'a, Feat: ExportFeature> {
     /// Stores the patterns used in the document.
     pub patterns: PaintFillMap,
 
+    /// The runtime options overriding the feature set.
+    pub options: SvgExportOptions,
+
     _feat_phantom: std::marker::PhantomData<&'a Feat>,
 }
 
@@ -411,12 +434,22 @@ impl<Feat: ExportFeature> Default for SvgTask<'_, Feat> {
             gradients: PaintFillMap::default(),
             patterns: PaintFillMap::default(),
 
+            options: SvgExportOptions::default(),
+
             _feat_phantom: std::marker::PhantomData,
         }
     }
 }
 
 impl<Feat: ExportFeature> SvgTask<'_, Feat> {
+    /// Creates a task with the runtime options.
+    pub fn new(options: SvgExportOptions) -> Self {
+        Self {
+            options,
+            ..Self::default()
+        }
+    }
+
     /// Return integral page size for showing document.
     pub(crate) fn page_size(sz: Size) -> Axes<u32> {
         let (width_px, height_px) = {
@@ -444,9 +477,9 @@ impl<Feat: ExportFeature> SvgTask<'_, Feat> {
             patterns: &mut self.patterns,
 
             should_attach_debug_info: Feat::SHOULD_ATTACH_DEBUG_INFO,
-            should_render_text_element: true,
-            use_stable_glyph_id: true,
-            should_rasterize_text: true,
+            should_render_text_element: self.options.should_render_text_element::<Feat>(),
+            use_stable_glyph_id: self.options.use_stable_glyph_id::<Feat>(),
+            should_rasterize_text: self.options.should_rasterize_text::<Feat>(),
 
             _feat_phantom: Default::default(),
         }
@@ -4,6 +4,7 @@
 // color export
 
 use reflexo::typst::TypstPagedDocument;
+use serde::{Deserialize, Serialize};
 
 /// re-export the core types.
 pub use reflexo_typst2vec::font::{FontGlyphProvider, GlyphProvider, IGlyphProvider};
@@ -23,7 +24,7 @@ pub(crate) mod frontend;
 pub use frontend::{
     DynamicLayoutSvgExporter, IncrSvgDocClient, IncrSvgDocServer, IncrementalRenderContext,
 };
-pub use frontend::{SvgExporter, SvgTask};
+pub use frontend::{SvgExporter, SvgFrames, SvgTask};
 
 /// Useful transform for SVG Items.
 pub(crate) mod transform;
@@ -72,6 +73,53 @@ pub trait ExportFeature: Send + Sync + 'static {
     const AWARE_HTML_ENTITY: bool;
 }
 
+/// The options of SVG export that can be set at runtime.
+///
+/// An unset option falls back to the corresponding const of the
+/// [`ExportFeature`] the exporter is instantiated with.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SvgExportOptions {
+    /// See [`ExportFeature::SHOULD_RENDER_TEXT_ELEMENT`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub render_text_element: Option<bool>,
+    /// See [`ExportFeature::USE_STABLE_GLYPH_ID`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stable_glyph_id: Option<bool>,
+    /// See [`ExportFeature::SHOULD_RASTERIZE_TEXT`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rasterize_text: Option<bool>,
+    /// See [`ExportFeature::WITH_BUILTIN_CSS`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub builtin_css: Option<bool>,
+    /// See [`ExportFeature::WITH_RESPONSIVE_JS`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub responsive_js: Option<bool>,
+}
+
+impl SvgExportOptions {
+    pub fn should_render_text_element<Feat: ExportFeature>(&self) -> bool {
+        self.render_text_element
+            .unwrap_or(Feat::SHOULD_RENDER_TEXT_ELEMENT)
+    }
+
+    pub fn use_stable_glyph_id<Feat: ExportFeature>(&self) -> bool {
+        self.stable_glyph_id.unwrap_or(Feat::USE_STABLE_GLYPH_ID)
+    }
+
+    pub fn should_rasterize_text<Feat: ExportFeature>(&self) -> bool {
+        self.rasterize_text.unwrap_or(Feat::SHOULD_RASTERIZE_TEXT)
+    }
+
+    pub fn with_builtin_css<Feat: ExportFeature>(&self) -> bool {
+        self.builtin_css.unwrap_or(Feat::WITH_BUILTIN_CSS)
+    }
+
+    pub fn with_responsive_js<Feat: ExportFeature>(&self) -> bool {
+        self.responsive_js.unwrap_or(Feat::WITH_RESPONSIVE_JS)
+    }
+}
+
 /// The default feature set which is used for exporting full-fledged svg.
 pub struct DefaultExportFeature;
 pub type DefaultSvgTask = SvgTask<'static, DefaultExportFeature>;
@@ -106,9 +154,18 @@ impl ExportFeature for SvgExportFeature {
 
 /// Render SVG wrapped with html for [`TypstPagedDocument`].
 pub fn render_svg_html<Feat: ExportFeature>(output: &TypstPagedDocument) -> String {
+    render_svg_html_with_opts::<Feat>(output, &SvgExportOptions::default())
+}
+
+/// Render SVG wrapped with html for [`TypstPagedDocument`] with the runtime
+/// options.
+pub fn render_svg_html_with_opts<Feat: ExportFeature>(
+    output: &TypstPagedDocument,
+    options: &SvgExportOptions,
+) -> String {
     let mut doc = SvgExporter::<Feat>::svg_doc(output);
     doc.module.prepare_glyphs();
-    let mut svg = SvgExporter::<Feat>::render(&doc.module, &doc.pages, None);
+    let mut svg = SvgExporter::<Feat>::render_with(&doc.module, &doc.pages, None, options);
 
     // wrap SVG with html
     let mut html: Vec<SvgText> = Vec::with_capacity(svg.len() + 3);
@@ -129,9 +186,14 @@ pub fn render_svg_html<Feat: ExportFeature>(output: &TypstPagedDocument) -> Stri
 
 /// Render SVG for [`TypstPagedDocument`].
 pub fn render_svg(output: &TypstPagedDocument) -> String {
+    render_svg_with_opts(output, &SvgExportOptions::default())
+}
+
+/// Render SVG for [`TypstPagedDocument`] with the runtime options.
+pub fn render_svg_with_opts(output: &TypstPagedDocument, options: &SvgExportOptions) -> String {
     type UsingExporter = SvgExporter<SvgExportFeature>;
     let mut doc = UsingExporter::svg_doc(output);
     doc.module.prepare_glyphs();
-    let svg_text = UsingExporter::render(&doc.module, &doc.pages, None);
+    let svg_text = UsingExporter::render_with(&doc.module, &doc.pages, None, options);
     generate_text(transform::minify(svg_text))
 }
@@ -6,19 +6,22 @@ use std::{
     collections::HashSet,
     path::Path,
     sync::{Arc, OnceLock},
+    time::{Duration, Instant},
 };
 
 use reflexo::error::prelude::*;
 use reflexo::typst::{TypstHtmlDocument, TypstPagedDocument};
+use serde::Serialize;
 use tinymist_world::{ConfigTask, OptionDocumentTask, ProjectInsId, WorldComputeGraph};
 use tokio::sync::{mpsc, oneshot};
 
-use crate::task::CacheTask;
+use crate::metrics::{millis, CompileMetrics, EvictMetrics, MetricsRecorder, MetricsSink};
+use crate::task::{CacheTask, CancelToken};
 use crate::vfs::notify::{FilesystemEvent, MemoryEvent, NotifyMessage, UpstreamUpdateEvent};
 use crate::vfs::FsProvider;
 use crate::world::{CompilerFeat, CompilerUniverse, EntryReader, RevisingUniverse, TaskInputs};
 use crate::{watch::watch_deps, CompileSignal, TypstDocument};
-use crate::{CompileReport, CompileSnapshot, WorldDeps};
+use crate::{CompileReport, CompileSnapshot, ImmutPath, WorldDeps};
 
 pub trait CompilationHandle<F: CompilerFeat>: Send + Sync + 'static {
     fn status(&self, revision: usize, rep: CompileReport);
@@ -38,8 +41,16 @@ pub enum Interrupt<F: CompilerFeat> {
     Compile,
     /// Compiled from computing thread.
     Compiled(Arc<WorldComputeGraph<F>>),
-    /// Change the watching entry.
+    /// Change the watching entry of the primary project.
     ChangeTask(TaskInputs),
+    /// Add a project instance, which shares the universe with the other
+    /// projects but compiles its own entry with its own inputs. The fields of
+    /// the task that are `None` follow the ones of the universe.
+    AddProject(ProjectInsId, TaskInputs, Arc<dyn CompilationHandle<F>>),
+    /// Change the entry or the inputs of a project instance.
+    ChangeProjectTask(ProjectInsId, TaskInputs),
+    /// Remove a project instance.
+    RemoveProject(ProjectInsId),
     /// Request compiler to respond a snapshot without needing to wait latest
     /// compilation.
     SnapshotRead(oneshot::Sender<CompileSnapshot<F>>),
@@ -60,14 +71,15 @@ enum CompilerResponse {
     Notify(NotifyMessage),
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
-struct CompileReasons {
+/// The reasons to compile a project.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+pub struct CompileReasons {
     /// The snapshot is taken by the memory editing events.
-    by_memory_events: bool,
+    pub by_memory_events: bool,
     /// The snapshot is taken by the file system events.
-    by_fs_events: bool,
+    pub by_fs_events: bool,
     /// The snapshot is taken by the entry change.
-    by_entry_update: bool,
+    pub by_entry_update: bool,
 }
 
 impl CompileReasons {
@@ -77,7 +89,7 @@ impl CompileReasons {
         self.by_entry_update |= reason.by_entry_update;
     }
 
-    fn any(&self) -> bool {
+    pub fn any(&self) -> bool {
         self.by_memory_events || self.by_fs_events || self.by_entry_update
     }
 }
@@ -107,6 +119,39 @@ fn reason_by_entry_change() -> CompileReasons {
     }
 }
 
+/// The state of an in-flight compilation.
+struct CompilingState {
+    /// The revision being compiled.
+    revision: usize,
+    /// The time when the compilation started.
+    start: crate::Time,
+    /// The token to cancel the compilation once it is superseded.
+    cancel: CancelToken,
+    /// The reasons that triggered the compilation.
+    reason: CompileReasons,
+    /// The time from the first event causing the compilation to its start.
+    queue_wait: Duration,
+    /// The recorder of the time of the stages run by the handle.
+    recorder: MetricsRecorder,
+}
+
+impl CompilingState {
+    fn metrics(&self, id: &ProjectInsId, revision: usize, dependencies: usize) -> CompileMetrics {
+        let (compile, exports) = self.recorder.take();
+        CompileMetrics {
+            project: id.to_string(),
+            revision,
+            reason: self.reason,
+            cancelled: self.cancel.is_cancelled(),
+            queue_wait_ms: millis(self.queue_wait),
+            compile_ms: millis(compile),
+            exports,
+            total_ms: self.start.elapsed().map(millis).unwrap_or_default(),
+            dependencies,
+        }
+    }
+}
+
 /// A tagged memory event with logical tick.
 struct TaggedMemoryEvent {
     /// The logical tick when the event is received.
@@ -118,6 +163,8 @@ struct TaggedMemoryEvent {
 pub struct CompileServerOpts<F: CompilerFeat> {
     pub compile_handle: Arc<dyn CompilationHandle<F>>,
     pub cache: CacheTask,
+    /// The sink to report the metrics of the compilations to.
+    pub metrics: Option<Arc<dyn MetricsSink>>,
 }
 
 impl<F: CompilerFeat + Send + Sync + 'static> Default for CompileServerOpts<F> {
@@ -125,6 +172,111 @@ impl<F: CompilerFeat + Send + Sync + 'static> Default for CompileServerOpts<F> {
         Self {
             compile_handle: Arc::new(std::marker::PhantomData),
             cache: Default::default(),
+            metrics: None,
+        }
+    }
+}
+
+/// A project instance managed by the compiler actor. The projects share the
+/// universe, i.e. the VFS, the fonts and the packages, but each of them
+/// compiles its own entry with its own inputs.
+struct ProjectInstance<F: CompilerFeat> {
+    /// The id of the project.
+    id: ProjectInsId,
+    /// The entry and the inputs overriding the ones of the universe, which is
+    /// `None` for the primary project.
+    task: Option<TaskInputs>,
+    /// The compilation handle, which is `None` for the primary project to use
+    /// [`CompileActor::compile_handle`].
+    handle: Option<Arc<dyn CompilationHandle<F>>>,
+
+    /// The latest compiled document.
+    latest_doc: Option<Arc<WorldComputeGraph<F>>>,
+    /// The latest successful document.
+    latest_success_doc: Option<TypstDocument>,
+    /// The file dependencies of the latest compilation.
+    deps: Vec<ImmutPath>,
+
+    watch_snap: OnceLock<CompileSnapshot<F>>,
+    suspended: bool,
+    /// The in-flight compilations, in the order of their revisions. All but
+    /// the last one are superseded and cancelled.
+    compiling: Vec<CompilingState>,
+    suspended_reason: CompileReasons,
+    /// The time when the first pending reason is seen.
+    pending_since: Option<Instant>,
+    committed_revision: usize,
+}
+
+impl<F: CompilerFeat> ProjectInstance<F> {
+    fn new(
+        id: ProjectInsId,
+        task: Option<TaskInputs>,
+        handle: Option<Arc<dyn CompilationHandle<F>>>,
+        verse: &CompilerUniverse<F>,
+    ) -> Self {
+        Self {
+            suspended: Self::is_inactive(verse, &task),
+            id,
+            task,
+            handle,
+
+            latest_doc: None,
+            latest_success_doc: None,
+            deps: vec![],
+
+            watch_snap: OnceLock::new(),
+            compiling: vec![],
+            suspended_reason: no_reason(),
+            pending_since: None,
+            committed_revision: 0,
+        }
+    }
+
+    /// Records a reason to compile.
+    fn see(&mut self, reason: CompileReasons) {
+        if reason.any() && self.pending_since.is_none() {
+            self.pending_since = Some(Instant::now());
+        }
+        self.suspended_reason.see(reason);
+    }
+
+    /// Whether the entry of the project is inactive.
+    fn is_inactive(verse: &CompilerUniverse<F>, task: &Option<TaskInputs>) -> bool {
+        match task.as_ref().and_then(|task| task.entry.as_ref()) {
+            Some(entry) => entry.is_inactive(),
+            None => verse.entry_state().is_inactive(),
+        }
+    }
+
+    /// Whether the project follows the entry of the universe.
+    fn inherits_entry(&self) -> bool {
+        self.task.as_ref().is_none_or(|task| task.entry.is_none())
+    }
+
+    /// Resets the documents and cancels the in-flight compilations, e.g. after
+    /// the entry is changed.
+    fn reset(&mut self) {
+        for state in &self.compiling {
+            state.cancel.cancel();
+        }
+
+        self.latest_doc = None;
+        self.latest_success_doc = None;
+        self.committed_revision = 0;
+    }
+
+    fn snapshot(&self, verse: &CompilerUniverse<F>, reason: CompileReasons) -> CompileSnapshot<F> {
+        let world = verse.snapshot_with(self.task.clone());
+        CompileSnapshot {
+            id: self.id.clone(),
+            world,
+            signal: CompileSignal {
+                by_entry_update: reason.by_entry_update,
+                by_mem_events: reason.by_memory_events,
+                by_fs_events: reason.by_fs_events,
+            },
+            success_doc: self.latest_success_doc.clone(),
         }
     }
 }
@@ -133,7 +285,7 @@ impl<F: CompilerFeat + Send + Sync + 'static> Default for CompileServerOpts<F> {
 pub struct CompileActor<F: CompilerFeat> {
     /// The underlying universe.
     pub verse: CompilerUniverse<F>,
-    /// The compilation handle.
+    /// The compilation handle of the primary project.
     pub compile_handle: Arc<dyn CompilationHandle<F>>,
     /// Whether to enable file system watching.
     pub enable_watch: bool,
@@ -145,10 +297,8 @@ pub struct CompileActor<F: CompilerFeat> {
 
     /// Estimated latest set of shadow files.
     estimated_shadow_files: HashSet<Arc<Path>>,
-    /// The latest compiled document.
-    pub(crate) latest_doc: Option<Arc<WorldComputeGraph<F>>>,
-    /// The latest successful document.
-    pub(crate) latest_success_doc: Option<TypstDocument>,
+    /// The project instances, the first of which is the primary project.
+    projects: Vec<ProjectInstance<F>>,
 
     /// Channel for sending interrupts to the compiler actor.
     intr_tx: mpsc::UnboundedSender<Interrupt<F>>,
@@ -156,12 +306,8 @@ pub struct CompileActor<F: CompilerFeat> {
     intr_rx: mpsc::UnboundedReceiver<Interrupt<F>>,
     /// Shared cache evict task.
     cache: CacheTask,
-
-    watch_snap: OnceLock<CompileSnapshot<F>>,
-    suspended: bool,
-    compiling: bool,
-    suspended_reason: CompileReasons,
-    committed_revision: usize,
+    /// The sink to report the metrics of the compilations to.
+    metrics: Option<Arc<dyn MetricsSink>>,
 }
 
 impl<F: CompilerFeat + Send + Sync + 'static> CompileActor<F> {
@@ -173,9 +319,10 @@ impl<F: CompilerFeat + Send + Sync + 'static> CompileActor<F> {
         CompileServerOpts {
             compile_handle,
             cache: cache_evict,
+            metrics,
         }: CompileServerOpts<F>,
     ) -> Self {
-        let entry = verse.entry_state();
+        let primary = ProjectInstance::new(ProjectInsId::PRIMARY, None, None, &verse);
 
         Self {
             verse,
@@ -186,18 +333,12 @@ impl<F: CompilerFeat + Send + Sync + 'static> CompileActor<F> {
             dirty_shadow_logical_tick: 0,
 
             estimated_shadow_files: Default::default(),
-            latest_doc: None,
-            latest_success_doc: None,
+            projects: vec![primary],
 
             intr_tx,
             intr_rx,
             cache: cache_evict,
-
-            watch_snap: OnceLock::new(),
-            suspended: entry.is_inactive(),
-            compiling: false,
-            suspended_reason: no_reason(),
-            committed_revision: 0,
+            metrics,
         }
     }
 
@@ -215,6 +356,24 @@ impl<F: CompilerFeat + Send + Sync + 'static> CompileActor<F> {
         self
     }
 
+    /// Gets the ids of the project instances, the first of which is the
+    /// primary project.
+    pub fn project_ids(&self) -> impl Iterator<Item = &ProjectInsId> {
+        self.projects.iter().map(|proj| &proj.id)
+    }
+
+    /// Gets the latest compiled document of a project instance.
+    pub fn latest_doc(&self, id: &ProjectInsId) -> Option<Arc<WorldComputeGraph<F>>> {
+        let proj = self.projects.iter().find(|proj| &proj.id == id)?;
+        proj.latest_doc.clone()
+    }
+
+    /// Gets the latest successful document of a project instance.
+    pub fn latest_success_doc(&self, id: &ProjectInsId) -> Option<TypstDocument> {
+        let proj = self.projects.iter().find(|proj| &proj.id == id)?;
+        proj.latest_success_doc.clone()
+    }
+
     /// Launches the compiler actor.
     pub async fn run(mut self) -> Result<bool> {
         if !self.enable_watch {
@@ -234,7 +393,7 @@ impl<F: CompilerFeat + Send + Sync + 'static> CompileActor<F> {
         log::debug!("CompileActor: initialized");
 
         // Trigger the first compilation (if active)
-        self.run_compile(reason_by_entry_change(), &mut curr_reads, false);
+        self.run_compile(0, reason_by_entry_change(), &mut curr_reads, false);
 
         // Spawn file system watcher.
         let fs_tx = self.intr_tx.clone();
@@ -243,8 +402,6 @@ impl<F: CompilerFeat + Send + Sync + 'static> CompileActor<F> {
         }));
 
         'event_loop: while let Some(mut event) = self.intr_rx.recv().await {
-            let mut comp_reason = no_reason();
-
             'accumulate: loop {
                 // Warp the logical clock by one.
                 self.logical_tick += 1;
@@ -259,11 +416,11 @@ impl<F: CompilerFeat + Send + Sync + 'static> CompileActor<F> {
                 if let Interrupt::CurrentRead(event) = event {
                     curr_reads.push(event);
                 } else {
-                    comp_reason.see(self.process(event, |res: CompilerResponse| match res {
+                    self.process(event, |res: CompilerResponse| match res {
                         CompilerResponse::Notify(msg) => {
                             log_send_error("compile_deps", dep_tx.send(msg));
                         }
-                    }));
+                    });
                 }
 
                 // Try to accumulate more events.
@@ -273,10 +430,19 @@ impl<F: CompilerFeat + Send + Sync + 'static> CompileActor<F> {
                 }
             }
 
-            // Either we have a reason to compile or we have events that want to have any
-            // compilation.
-            if comp_reason.any() || !curr_reads.is_empty() {
-                self.run_compile(comp_reason, &mut curr_reads, false);
+            // Either a project has a reason to compile or we have events that want to have
+            // any compilation of the primary project.
+            for idx in 0..self.projects.len() {
+                // The current reads are only answered by the primary project.
+                let mut no_reads = vec![];
+                let reads = if idx == 0 {
+                    &mut curr_reads
+                } else {
+                    &mut no_reads
+                };
+                if self.projects[idx].suspended_reason.any() || !reads.is_empty() {
+                    self.run_compile(idx, no_reason(), reads, false);
+                }
             }
         }
 
@@ -285,43 +451,31 @@ impl<F: CompilerFeat + Send + Sync + 'static> CompileActor<F> {
         Ok(true)
     }
 
-    fn snapshot(&self, reason: CompileReasons) -> CompileSnapshot<F> {
-        let world = self.verse.snapshot();
-        CompileSnapshot {
-            id: ProjectInsId::PRIMARY,
-            world,
-            signal: CompileSignal {
-                by_entry_update: reason.by_entry_update,
-                by_mem_events: reason.by_memory_events,
-                by_fs_events: reason.by_fs_events,
-            },
-            success_doc: self.latest_success_doc.clone(),
-        }
-    }
-
     /// Compile the document once.
     pub async fn compile_once(&mut self) -> Arc<WorldComputeGraph<F>> {
-        self.run_compile(reason_by_entry_change(), &mut vec![], true)
+        self.run_compile(0, reason_by_entry_change(), &mut vec![], true)
             .unwrap()
     }
 
-    /// Compile the document once.
+    /// Compile the document of the project at the index once.
     fn run_compile(
         &mut self,
+        idx: usize,
         reason: CompileReasons,
         curr_reads: &mut Vec<oneshot::Sender<Arc<WorldComputeGraph<F>>>>,
         is_once: bool,
     ) -> Option<Arc<WorldComputeGraph<F>>> {
-        self.suspended_reason.see(reason);
-        let reason = std::mem::take(&mut self.suspended_reason);
+        let proj = &mut self.projects[idx];
+        proj.see(reason);
+        let reason = std::mem::take(&mut proj.suspended_reason);
         let start = reflexo::time::now();
 
-        let compiling = self.snapshot(reason);
-        self.watch_snap = OnceLock::new();
-        self.watch_snap.get_or_init(|| compiling.clone());
+        let compiling = proj.snapshot(&self.verse, reason);
+        proj.watch_snap = OnceLock::new();
+        proj.watch_snap.get_or_init(|| compiling.clone());
 
-        if self.suspended {
-            self.suspended_reason.see(reason);
+        if proj.suspended {
+            proj.suspended_reason.see(reason);
 
             for reader in curr_reads.drain(..) {
                 let _ = reader.send(WorldComputeGraph::new(compiling.clone()));
@@ -329,23 +483,44 @@ impl<F: CompilerFeat + Send + Sync + 'static> CompileActor<F> {
             return None;
         }
 
-        if self.compiling {
-            self.suspended_reason.see(reason);
-            return None;
+        let revision = compiling.world.revision().get();
+        if let Some(state) = proj.compiling.last() {
+            // The revision is already compiling, so the lagged reason triggers a
+            // compilation once it returns.
+            if state.revision >= revision && !state.cancel.is_cancelled() {
+                proj.suspended_reason.see(reason);
+                return None;
+            }
+
+            // The newer revision starts compiling right away, while the superseded
+            // compilation is signaled to abort early and its result is discarded.
+            state.cancel.cancel();
         }
 
-        self.compiling = true;
+        let cancel = CancelToken::default();
+        let recorder = MetricsRecorder::default();
+        let queued_at = proj.pending_since.take();
+        proj.compiling.push(CompilingState {
+            revision,
+            start,
+            cancel: cancel.clone(),
+            reason,
+            queue_wait: queued_at.map(|at| at.elapsed()).unwrap_or_default(),
+            recorder: recorder.clone(),
+        });
 
-        let h = self.compile_handle.clone();
+        let h = proj.handle.clone();
+        let h = h.unwrap_or_else(|| self.compile_handle.clone());
 
         // todo unwrap main id
         let id = compiling.world.main_id().unwrap();
-        let revision = compiling.world.revision().get();
 
         h.status(revision, CompileReport::Stage(id, "compiling", start));
 
         let compile = move || {
             let compiling = WorldComputeGraph::new(compiling);
+            cancel.provide(&compiling);
+            recorder.provide(&compiling);
 
             h.notify_compile(&compiling);
 
@@ -369,12 +544,34 @@ impl<F: CompilerFeat + Send + Sync + 'static> CompileActor<F> {
         artifact: Arc<WorldComputeGraph<F>>,
         send: impl Fn(CompilerResponse),
     ) {
-        self.compiling = false;
-
+        // The project may be removed during the compilation.
+        let Some(proj) = self.projects.iter_mut().find(|p| p.id == artifact.snap.id) else {
+            return;
+        };
         let w = &artifact.snap.world;
 
         let compiled_revision = w.revision().get();
-        if self.committed_revision >= compiled_revision {
+        let cancelled = CancelToken::of(&artifact).is_cancelled();
+        let idx = proj.compiling.iter().position(|state| {
+            state.revision == compiled_revision && state.cancel.is_cancelled() == cancelled
+        });
+        let state = idx.map(|idx| proj.compiling.remove(idx));
+
+        // A cancelled compilation is incomplete, so its result is discarded. It is
+        // reported even if the newer compilation is already committed.
+        if cancelled {
+            let elapsed = state.as_ref().and_then(|state| state.start.elapsed().ok());
+            let report = CompileReport::Cancelled(w.main(), elapsed.unwrap_or_default());
+            let h = proj.handle.as_ref().unwrap_or(&self.compile_handle);
+            h.status(compiled_revision, report);
+
+            if let (Some(sink), Some(state)) = (&self.metrics, &state) {
+                sink.compile(&state.metrics(&proj.id, compiled_revision, 0));
+            }
+            return;
+        }
+
+        if proj.committed_revision >= compiled_revision {
             return;
         }
 
@@ -400,10 +597,10 @@ impl<F: CompilerFeat + Send + Sync + 'static> CompileActor<F> {
         };
 
         // Update state.
-        self.committed_revision = compiled_revision;
-        self.latest_doc = Some(artifact.clone());
+        proj.committed_revision = compiled_revision;
+        proj.latest_doc = Some(artifact.clone());
         if doc.is_some() {
-            self.latest_success_doc = doc;
+            proj.latest_success_doc = doc;
         }
 
         // Notify the new file dependencies.
@@ -413,16 +610,48 @@ impl<F: CompilerFeat + Send + Sync + 'static> CompileActor<F> {
                 deps.push(x.into())
             }
         });
+        if let (Some(sink), Some(state)) = (&self.metrics, &state) {
+            sink.compile(&state.metrics(&proj.id, compiled_revision, deps.len()));
+        }
+        proj.deps = deps;
+        self.sync_dependencies(&send);
+
+        // Trigger an evict task.
+        let sink = self.metrics.clone();
+        self.cache.evict_with(move |elapsed| {
+            if let Some(sink) = sink {
+                let duration_ms = millis(elapsed);
+                sink.evict(&EvictMetrics {
+                    revision: compiled_revision,
+                    duration_ms,
+                });
+            }
+        });
+    }
+
+    /// Notifies the file dependencies of all projects, since the watcher
+    /// replaces the watched files by the notified ones.
+    fn sync_dependencies(&self, send: impl Fn(CompilerResponse)) {
+        let deps = self
+            .projects
+            .iter()
+            .flat_map(|proj| proj.deps.iter().cloned());
+        let deps = deps.collect::<HashSet<ImmutPath>>();
         send(CompilerResponse::Notify(NotifyMessage::SyncDependency(
-            Box::new(deps),
+            Box::new(deps.into_iter().collect::<Vec<_>>()),
         )));
+    }
 
-        // Trigger an evict task.
-        self.cache.evict();
+    /// Records a reason to compile for all projects.
+    fn see_all(&mut self, reason: CompileReasons) {
+        for proj in &mut self.projects {
+            proj.see(reason);
+        }
     }
 
-    /// Process some interrupt. Return whether it needs compilation.
-    fn process(&mut self, event: Interrupt<F>, send: impl Fn(CompilerResponse)) -> CompileReasons {
+    /// Process some interrupt, which records the reasons to compile in the
+    /// affected projects.
+    fn process(&mut self, event: Interrupt<F>, send: impl Fn(CompilerResponse)) {
         use CompilerResponse::*;
 
         match event {
@@ -432,24 +661,25 @@ impl<F: CompilerFeat + Send + Sync + 'static> CompileActor<F> {
                     verse.flush();
                 });
 
-                reason_by_entry_change()
+                self.see_all(reason_by_entry_change());
             }
             Interrupt::SnapshotRead(task) => {
                 log::debug!("CompileActor: take snapshot");
-                if self
+                let primary = &mut self.projects[0];
+                if primary
                     .watch_snap
                     .get()
                     .is_some_and(|e| e.world.revision() < self.verse.revision)
                 {
-                    self.watch_snap = OnceLock::new();
+                    primary.watch_snap = OnceLock::new();
                 }
 
                 let _ = task.send(
-                    self.watch_snap
-                        .get_or_init(|| self.snapshot(no_reason()))
+                    primary
+                        .watch_snap
+                        .get_or_init(|| primary.snapshot(&self.verse, no_reason()))
                         .clone(),
                 );
-                no_reason()
             }
             Interrupt::CurrentRead(..) => {
                 unreachable!()
@@ -469,25 +699,92 @@ impl<F: CompilerFeat + Send + Sync + 'static> CompileActor<F> {
                 });
 
                 // After incrementing the revision
-                if let Some(entry) = change.entry {
-                    self.suspended = entry.is_inactive();
-                    if self.suspended {
-                        log::info!("CompileActor: removing diag");
-                        self.compile_handle
-                            .status(self.verse.revision.get(), CompileReport::Suspend);
+                if change.entry.is_some() {
+                    let revision = self.verse.revision.get();
+                    for proj in self.projects.iter_mut().filter(|p| p.inherits_entry()) {
+                        proj.suspended = ProjectInstance::is_inactive(&self.verse, &proj.task);
+                        if proj.suspended {
+                            log::info!("CompileActor: removing diag");
+                            let h = proj.handle.as_ref().unwrap_or(&self.compile_handle);
+                            h.status(revision, CompileReport::Suspend);
+                        }
+
+                        // Reset the watch state and document state.
+                        proj.latest_doc = None;
+                        proj.latest_success_doc = None;
+                        proj.suspended_reason = no_reason();
+                    }
+                }
+
+                // The projects overriding the inputs are not affected.
+                let inherits_inputs = |p: &ProjectInstance<F>| {
+                    p.task.as_ref().is_none_or(|task| task.inputs.is_none())
+                };
+                for proj in self.projects.iter_mut() {
+                    if proj.inherits_entry() || inherits_inputs(proj) {
+                        proj.see(reason_by_entry_change());
                     }
+                }
+            }
+            Interrupt::AddProject(id, task, handle) => {
+                if id == ProjectInsId::PRIMARY {
+                    log::error!("CompileActor: cannot add the primary project");
+                    return;
+                }
 
-                    // Reset the watch state and document state.
-                    self.latest_doc = None;
-                    self.latest_success_doc = None;
-                    self.suspended_reason = no_reason();
+                log::info!("CompileActor: add project {id:?}");
+                let mut proj = ProjectInstance::new(id, Some(task), Some(handle), &self.verse);
+                proj.see(reason_by_entry_change());
+                match self.projects.iter_mut().find(|p| p.id == proj.id) {
+                    Some(prev) => {
+                        // Keeps the in-flight compilations to drop their results.
+                        prev.reset();
+                        proj.compiling = std::mem::take(&mut prev.compiling);
+                        *prev = proj;
+                    }
+                    None => self.projects.push(proj),
+                }
+            }
+            Interrupt::ChangeProjectTask(id, change) => {
+                let Some(proj) = self.projects.iter_mut().find(|p| p.id == id) else {
+                    log::warn!("CompileActor: change task of unknown project {id:?}");
+                    return;
+                };
+                let Some(task) = &mut proj.task else {
+                    log::error!("CompileActor: change task of the primary project by project id");
+                    return;
+                };
+
+                if let Some(entry) = change.entry {
+                    task.entry = Some(entry);
+                }
+                if let Some(inputs) = change.inputs {
+                    task.inputs = Some(inputs);
                 }
 
-                reason_by_entry_change()
+                proj.reset();
+                proj.suspended = ProjectInstance::is_inactive(&self.verse, &proj.task);
+                if proj.suspended {
+                    let h = proj.handle.as_ref().unwrap_or(&self.compile_handle);
+                    h.status(self.verse.revision.get(), CompileReport::Suspend);
+                }
+                proj.see(reason_by_entry_change());
+            }
+            Interrupt::RemoveProject(id) => {
+                if id == ProjectInsId::PRIMARY {
+                    log::error!("CompileActor: cannot remove the primary project");
+                    return;
+                }
+
+                log::info!("CompileActor: remove project {id:?}");
+                if let Some(idx) = self.projects.iter().position(|p| p.id == id) {
+                    let mut proj = self.projects.remove(idx);
+                    proj.reset();
+                    self.sync_dependencies(&send);
+                }
             }
             Interrupt::Compiled(artifact) => {
                 self.process_compile(artifact, send);
-                self.process_lagged_compile()
             }
             Interrupt::Memory(event) => {
                 log::debug!("CompileActor: memory event incoming");
@@ -512,7 +809,8 @@ impl<F: CompilerFeat + Send + Sync + 'static> CompileActor<F> {
                 if files.is_empty() && self.dirty_shadow_logical_tick == 0 {
                     self.verse
                         .increment_revision(|verse| Self::apply_memory_changes(verse, event));
-                    return reason_by_mem();
+                    self.see_all(reason_by_mem());
+                    return;
                 }
 
                 // Otherwise, send upstream update event.
@@ -525,8 +823,6 @@ impl<F: CompilerFeat + Send + Sync + 'static> CompileActor<F> {
                         event,
                     }),
                 })));
-
-                no_reason()
             }
             Interrupt::Fs(mut event) => {
                 log::debug!("CompileActor: fs event incoming {event:?}");
@@ -546,18 +842,12 @@ impl<F: CompilerFeat + Send + Sync + 'static> CompileActor<F> {
                     verse.vfs().notify_fs_event(event)
                 });
 
-                reason
+                self.see_all(reason);
             }
             Interrupt::Settle(_) => unreachable!(),
         }
     }
 
-    /// Process reason after each compilation.
-    fn process_lagged_compile(&mut self) -> CompileReasons {
-        // The reason which is kept but not used.
-        std::mem::take(&mut self.suspended_reason)
-    }
-
     /// Apply delayed memory changes to underlying compiler.
     fn apply_delayed_memory_changes(
         verse: &mut RevisingUniverse<F>,
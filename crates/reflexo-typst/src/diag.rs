@@ -0,0 +1,383 @@
+//! Diagnostics printing.
+
+use std::io::{self, Write};
+use std::path::Path;
+
+use reflexo::debug_loc::LspPosition;
+use serde::Serialize;
+use typst::diag::{Severity, SourceDiagnostic};
+use typst::syntax::Span;
+use url::Url;
+
+// todo: remove cfg feature here
+#[cfg(feature = "system-compile")]
+pub use tinymist_world::system::print_diagnostics;
+pub use tinymist_world::DiagnosticFormat;
+
+use crate::error::{resolve_source_span, PosFmt};
+
+/// The machine-readable formats to print diagnostics in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StructuredDiagnosticFormat {
+    /// One JSON object per diagnostic per line, see [`JsonDiagnostic`].
+    Json,
+    /// A [SARIF 2.1.0](https://docs.oasis-open.org/sarif/sarif/v2.1.0/sarif-v2.1.0.html)
+    /// log per compilation.
+    Sarif,
+}
+
+/// A diagnostic in the JSON format.
+#[derive(Debug, Clone, Serialize)]
+pub struct JsonDiagnostic {
+    /// The severity, either `error` or `warning`.
+    pub severity: &'static str,
+    pub message: String,
+    pub hints: Vec<String>,
+    /// The location of the diagnostic, if it is attached to a file.
+    #[serde(flatten)]
+    pub location: Option<JsonDiagLocation>,
+    /// The trace of the diagnostic, from the innermost call.
+    pub trace: Vec<JsonDiagTrace>,
+}
+
+/// A step in the trace of a diagnostic.
+#[derive(Debug, Clone, Serialize)]
+pub struct JsonDiagTrace {
+    /// The message of the step, e.g. `while calling foo`.
+    pub message: String,
+    #[serde(flatten)]
+    pub location: Option<JsonDiagLocation>,
+}
+
+/// The resolved location of a diagnostic.
+#[derive(Debug, Clone, Serialize)]
+pub struct JsonDiagLocation {
+    /// The package containing the file, e.g. `@preview/example:0.1.0`.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub package: String,
+    /// The path to the file, which is absolute for a file in the workspace,
+    /// or rooted at the package for a file in a package.
+    pub file: String,
+    /// The range in the file, if the source of the file is available.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub range: Option<JsonDiagRange>,
+}
+
+/// A range in a file.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct JsonDiagRange {
+    pub start: JsonDiagPosition,
+    pub end: JsonDiagPosition,
+}
+
+/// A position in a file. Both the line and the column are 1-based, and the
+/// column is counted in characters.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct JsonDiagPosition {
+    pub line: u32,
+    pub column: u32,
+}
+
+impl JsonDiagnostic {
+    /// Resolves a diagnostic against the world.
+    pub fn new(diag: &SourceDiagnostic, world: Option<&dyn typst::World>) -> Self {
+        let trace = diag.trace.iter().map(|trace| JsonDiagTrace {
+            message: PosFmt(&trace.v).to_string(),
+            location: JsonDiagLocation::resolve(trace.span, world),
+        });
+
+        Self {
+            severity: match diag.severity {
+                Severity::Error => "error",
+                Severity::Warning => "warning",
+            },
+            message: diag.message.to_string(),
+            hints: diag.hints.iter().map(ToString::to_string).collect(),
+            location: JsonDiagLocation::resolve(diag.span, world),
+            trace: trace.collect(),
+        }
+    }
+
+    /// Converts the diagnostic into a SARIF result, see
+    /// [`JsonDiagLocation::artifact_location`] for the `root`.
+    fn to_sarif(&self, root: Option<&Url>) -> serde_json::Value {
+        let mut text = self.message.clone();
+        for hint in &self.hints {
+            text.push_str("\nhint: ");
+            text.push_str(hint);
+        }
+
+        let related = self.trace.iter().filter_map(|trace| {
+            let mut location = trace.location.as_ref()?.to_sarif(root);
+            location["message"] = serde_json::json!({ "text": trace.message });
+            Some(location)
+        });
+
+        serde_json::json!({
+            "ruleId": format!("typst/{}", self.severity),
+            "level": self.severity,
+            "message": { "text": text },
+            "locations": self.location.iter().map(|loc| loc.to_sarif(root)).collect::<Vec<_>>(),
+            "relatedLocations": related.collect::<Vec<_>>(),
+        })
+    }
+}
+
+impl JsonDiagLocation {
+    /// Resolves the location of a span, which is `None` if the span is
+    /// detached.
+    fn resolve(span: Span, world: Option<&dyn typst::World>) -> Option<Self> {
+        let (package, file, range) = resolve_source_span(span, world);
+        if file.is_empty() {
+            return None;
+        }
+
+        let position = |pos: LspPosition| JsonDiagPosition {
+            line: pos.line + 1,
+            column: pos.character + 1,
+        };
+        Some(Self {
+            package,
+            file,
+            range: range.map(|range| JsonDiagRange {
+                start: position(range.start),
+                end: position(range.end),
+            }),
+        })
+    }
+
+    /// Converts the location into a SARIF artifact location.
+    ///
+    /// A file in the workspace is relative to the `%SRCROOT%` base, which is
+    /// the `root` of the workspace. Other files are `file` URIs if their paths
+    /// are absolute.
+    fn artifact_location(&self, root: Option<&Url>) -> serde_json::Value {
+        if !self.package.is_empty() {
+            return serde_json::json!({ "uri": format!("{}{}", self.package, self.file) });
+        }
+
+        let Ok(file) = Url::from_file_path(&self.file) else {
+            return serde_json::json!({ "uri": self.file });
+        };
+        let relative = root
+            .and_then(|root| root.make_relative(&file))
+            .filter(|uri| !uri.starts_with("../"));
+        match relative {
+            Some(uri) => serde_json::json!({ "uri": uri, "uriBaseId": SRCROOT }),
+            None => serde_json::json!({ "uri": file.as_str() }),
+        }
+    }
+
+    /// Converts the location into a SARIF location.
+    fn to_sarif(&self, root: Option<&Url>) -> serde_json::Value {
+        let mut physical = serde_json::json!({
+            "artifactLocation": self.artifact_location(root),
+        });
+        if let Some(range) = &self.range {
+            physical["region"] = serde_json::json!({
+                "startLine": range.start.line,
+                "startColumn": range.start.column,
+                "endLine": range.end.line,
+                "endColumn": range.end.column,
+            });
+        }
+
+        serde_json::json!({ "physicalLocation": physical })
+    }
+}
+
+/// The base of the URIs of the files in the workspace in a SARIF log.
+const SRCROOT: &str = "%SRCROOT%";
+
+/// Prints the diagnostics in a machine-readable format.
+///
+/// The `root` of the workspace is the `%SRCROOT%` base of the SARIF log.
+pub fn print_structured_diagnostics<'d>(
+    w: &mut impl Write,
+    world: Option<&dyn typst::World>,
+    root: Option<&Path>,
+    diagnostics: impl Iterator<Item = &'d SourceDiagnostic>,
+    format: StructuredDiagnosticFormat,
+) -> io::Result<()> {
+    let diagnostics = diagnostics.map(|diag| JsonDiagnostic::new(diag, world));
+
+    match format {
+        StructuredDiagnosticFormat::Json => {
+            for diag in diagnostics {
+                serde_json::to_writer(&mut *w, &diag)?;
+                writeln!(w)?;
+            }
+        }
+        StructuredDiagnosticFormat::Sarif => {
+            let root = root.and_then(|root| Url::from_directory_path(root).ok());
+            let base_ids = root.iter().map(|root| {
+                (
+                    SRCROOT.to_owned(),
+                    serde_json::json!({ "uri": root.as_str() }),
+                )
+            });
+            let log = serde_json::json!({
+                "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+                "version": "2.1.0",
+                "runs": [{
+                    "tool": {
+                        "driver": {
+                            "name": "reflexo-typst",
+                            "version": crate::build_info::VERSION,
+                            "informationUri": "https://github.com/Myriad-Dreamin/typst.ts",
+                            "rules": [
+                                { "id": "typst/error", "name": "TypstError" },
+                                { "id": "typst/warning", "name": "TypstWarning" },
+                            ],
+                        },
+                    },
+                    "originalUriBaseIds": base_ids.collect::<serde_json::Map<_, _>>(),
+                    "results": diagnostics
+                        .map(|diag| diag.to_sarif(root.as_ref()))
+                        .collect::<Vec<_>>(),
+                }],
+            });
+            serde_json::to_writer(&mut *w, &log)?;
+            writeln!(w)?;
+        }
+    }
+
+    w.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sarif(root: Option<&Path>, diagnostics: &[SourceDiagnostic]) -> serde_json::Value {
+        let mut buf = vec![];
+        let format = StructuredDiagnosticFormat::Sarif;
+        print_structured_diagnostics(&mut buf, None, root, diagnostics.iter(), format).unwrap();
+        serde_json::from_slice(&buf).unwrap()
+    }
+
+    #[test]
+    fn test_sarif_log() {
+        let diag = SourceDiagnostic::error(Span::detached(), "unknown variable: x")
+            .with_hint("declare it with `let`");
+        let log = sarif(None, &[diag]);
+
+        assert_eq!(
+            log["$schema"],
+            "https://json.schemastore.org/sarif-2.1.0.json"
+        );
+        assert_eq!(log["version"], "2.1.0");
+
+        let runs = log["runs"].as_array().unwrap();
+        assert_eq!(runs.len(), 1);
+        let driver = &runs[0]["tool"]["driver"];
+        assert_eq!(driver["name"], "reflexo-typst");
+        assert!(driver["version"].is_string());
+        let rules = driver["rules"].as_array().unwrap();
+        assert!(rules.iter().any(|rule| rule["id"] == "typst/error"));
+        assert!(rules.iter().any(|rule| rule["id"] == "typst/warning"));
+
+        let results = runs[0]["results"].as_array().unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0]["ruleId"], "typst/error");
+        assert_eq!(results[0]["level"], "error");
+        assert_eq!(
+            results[0]["message"]["text"],
+            "unknown variable: x\nhint: declare it with `let`"
+        );
+        // A detached span has no location.
+        assert_eq!(results[0]["locations"], serde_json::json!([]));
+    }
+
+    #[test]
+    fn test_sarif_log_without_diagnostics() {
+        let log = sarif(None, &[]);
+        assert_eq!(log["runs"][0]["results"], serde_json::json!([]));
+        assert_eq!(log["runs"][0]["originalUriBaseIds"], serde_json::json!({}));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_sarif_root() {
+        let log = sarif(Some(Path::new("/work space")), &[]);
+        assert_eq!(
+            log["runs"][0]["originalUriBaseIds"],
+            serde_json::json!({ "%SRCROOT%": { "uri": "file:///work%20space/" } })
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_sarif_uri() {
+        let location = |file: &str| JsonDiagLocation {
+            package: String::new(),
+            file: file.to_owned(),
+            range: None,
+        };
+        let root = Url::from_directory_path("/work space").unwrap();
+        let uri = |file: &str| location(file).artifact_location(Some(&root));
+
+        assert_eq!(
+            uri("/work space/sub dir/main#1.typ"),
+            serde_json::json!({ "uri": "sub%20dir/main%231.typ", "uriBaseId": "%SRCROOT%" })
+        );
+        // A file out of the workspace has an absolute URI.
+        assert_eq!(
+            uri("/work spaces/main.typ"),
+            serde_json::json!({ "uri": "file:///work%20spaces/main.typ" })
+        );
+        assert_eq!(
+            location("/work space/main.typ").artifact_location(None),
+            serde_json::json!({ "uri": "file:///work%20space/main.typ" })
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_sarif_location() {
+        let range = JsonDiagRange {
+            start: JsonDiagPosition { line: 3, column: 1 },
+            end: JsonDiagPosition { line: 3, column: 5 },
+        };
+        let diag = JsonDiagnostic {
+            severity: "warning",
+            message: "unused".to_owned(),
+            hints: vec![],
+            location: Some(JsonDiagLocation {
+                package: String::new(),
+                file: "/work/main.typ".to_owned(),
+                range: Some(range),
+            }),
+            trace: vec![JsonDiagTrace {
+                message: "while calling f".to_owned(),
+                location: Some(JsonDiagLocation {
+                    package: "@preview/example:0.1.0".to_owned(),
+                    file: "/lib.typ".to_owned(),
+                    range: None,
+                }),
+            }],
+        };
+
+        let result = diag.to_sarif(None);
+        assert_eq!(result["ruleId"], "typst/warning");
+        assert_eq!(result["level"], "warning");
+        assert_eq!(
+            result["locations"],
+            serde_json::json!([{
+                "physicalLocation": {
+                    "artifactLocation": { "uri": "file:///work/main.typ" },
+                    "region": { "startLine": 3, "startColumn": 1, "endLine": 3, "endColumn": 5 },
+                },
+            }])
+        );
+        assert_eq!(
+            result["relatedLocations"],
+            serde_json::json!([{
+                "physicalLocation": {
+                    "artifactLocation": { "uri": "@preview/example:0.1.0/lib.typ" },
+                },
+                "message": { "text": "while calling f" },
+            }])
+        );
+    }
+}
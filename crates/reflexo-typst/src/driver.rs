@@ -1,8 +1,10 @@
+use std::path::PathBuf;
+
 use typst::diag::{SourceDiagnostic, SourceResult, Warned};
 
 use crate::{
-    diag::print_diagnostics,
-    world::{CompilerFeat, CompilerWorld},
+    diag::{print_diagnostics, print_structured_diagnostics, StructuredDiagnosticFormat},
+    world::{CompilerFeat, CompilerWorld, EntryReader},
     CompileReport, DiagnosticFormat,
 };
 
@@ -10,6 +12,13 @@ use crate::{
 pub struct DiagnosticHandler {
     /// The diagnostic format to use.
     pub diagnostic_format: DiagnosticFormat,
+    /// The machine-readable format to print the diagnostics in, which
+    /// overrides [`Self::diagnostic_format`] if set.
+    pub structured_format: Option<StructuredDiagnosticFormat>,
+    /// The file to write the machine-readable diagnostics to, which is
+    /// rewritten on each report. They are printed to stderr if it is `None`,
+    /// leaving stdout to the outputs.
+    pub diagnostic_output: Option<PathBuf>,
     /// Whether to print the compile status.
     pub print_compile_status: bool,
 }
@@ -30,7 +39,9 @@ impl DiagnosticHandler {
             Ok(doc) => (Some(doc), res.warnings),
             Err(diag) => (None, diag),
         };
-        if !diag.is_empty() {
+        // A SARIF log is printed even if there is no diagnostic.
+        let sarif = self.structured_format == Some(StructuredDiagnosticFormat::Sarif);
+        if !diag.is_empty() || sarif {
             self.report(world, diag.iter());
         }
 
@@ -42,6 +53,26 @@ impl DiagnosticHandler {
         world: &CompilerWorld<F>,
         diagnostics: impl Iterator<Item = &'d SourceDiagnostic>,
     ) {
+        if let Some(format) = self.structured_format {
+            let root = world.entry_state().root();
+            let root = root.as_deref();
+            let world: &dyn typst::World = world;
+            let err = match &self.diagnostic_output {
+                Some(path) => std::fs::File::create(path).and_then(|file| {
+                    let mut w = std::io::BufWriter::new(file);
+                    print_structured_diagnostics(&mut w, Some(world), root, diagnostics, format)
+                }),
+                None => {
+                    let mut w = std::io::stderr().lock();
+                    print_structured_diagnostics(&mut w, Some(world), root, diagnostics, format)
+                }
+            };
+            if let Err(err) = err {
+                log::error!("failed to print diagnostics: {err:?}");
+            }
+            return;
+        }
+
         let _err = print_diagnostics(world, diagnostics, self.diagnostic_format);
         // todo: log in browser compiler
         #[cfg(feature = "system-compile")]
@@ -18,6 +18,8 @@ pub enum CompileReport {
     CompileError(FileId, usize, reflexo::time::Duration),
     ExportError(FileId, usize, reflexo::time::Duration),
     CompileSuccess(FileId, usize, reflexo::time::Duration),
+    /// The compilation is cancelled since a newer revision supersedes it.
+    Cancelled(FileId, reflexo::time::Duration),
 }
 
 impl CompileReport {
@@ -27,7 +29,8 @@ impl CompileReport {
             Self::Stage(id, ..)
             | Self::CompileError(id, ..)
             | Self::ExportError(id, ..)
-            | Self::CompileSuccess(id, ..) => *id,
+            | Self::CompileSuccess(id, ..)
+            | Self::Cancelled(id, ..) => *id,
         })
     }
 
@@ -36,13 +39,14 @@ impl CompileReport {
             Self::Suspend | Self::Stage(..) => None,
             Self::CompileError(_, _, dur)
             | Self::ExportError(_, _, dur)
-            | Self::CompileSuccess(_, _, dur) => Some(*dur),
+            | Self::CompileSuccess(_, _, dur)
+            | Self::Cancelled(_, dur) => Some(*dur),
         }
     }
 
     pub fn diagnostics_size(self) -> Option<usize> {
         match self {
-            Self::Suspend | Self::Stage(..) => None,
+            Self::Suspend | Self::Stage(..) | Self::Cancelled(..) => None,
             Self::CompileError(_, diagnostics, ..)
             | Self::ExportError(_, diagnostics, ..)
             | Self::CompileSuccess(_, diagnostics, ..) => Some(diagnostics),
@@ -78,6 +82,9 @@ impl fmt::Display for CompileReportMsg<'_> {
             CompileError(_, _, duration) | ExportError(_, _, duration) => {
                 write!(f, "{input:?}: compilation failed after {duration:?}")
             }
+            Cancelled(_, duration) => {
+                write!(f, "{input:?}: compilation cancelled after {duration:?}")
+            }
         }
     }
 }
@@ -96,7 +103,7 @@ impl fmt::Display for DiagMsgFmt<'_> {
     }
 }
 
-struct PosFmt<'a>(&'a typst::diag::Tracepoint);
+pub(crate) struct PosFmt<'a>(pub(crate) &'a typst::diag::Tracepoint);
 
 impl fmt::Display for PosFmt<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -109,7 +116,7 @@ impl fmt::Display for PosFmt<'_> {
     }
 }
 
-fn resolve_source_span(
+pub(crate) fn resolve_source_span(
     s: Span,
     world: Option<&dyn typst::World>,
 ) -> (String, String, Option<LspRange>) {
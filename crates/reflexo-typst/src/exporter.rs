@@ -11,6 +11,10 @@ pub mod ast;
 pub mod dyn_svg;
 #[cfg(feature = "html")]
 pub mod html;
+#[cfg(feature = "pdf")]
+pub mod pdf;
+#[cfg(feature = "png")]
+pub mod png;
 #[cfg(feature = "svg")]
 pub mod svg;
 
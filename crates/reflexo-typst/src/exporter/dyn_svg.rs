@@ -1,22 +1,134 @@
 use std::sync::Arc;
+use std::time::Duration;
 
+use ecow::{eco_format, EcoString};
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
 use reflexo::error::prelude::*;
 use reflexo::typst::{TypstDocument, TypstHtmlDocument, TypstPagedDocument};
 use reflexo_typst2vec::pass::{CommandExecutor, Typst2VecPass};
 use reflexo_typst2vec::IntoTypst;
 use reflexo_vec2svg::{DynamicLayoutSvgExporter, MultiVecDocument};
 use tinymist_task::ExportTask;
-use typst::diag::SourceResult;
+use typst::diag::{SourceDiagnostic, SourceResult};
 use typst::foundations::IntoValue;
 use typst::utils::LazyHash;
 
 use crate::typst::prelude::*;
-use crate::vector::ir::{LayoutRegion, LayoutRegionNode};
+use crate::vector::ir::{LayoutRegion, LayoutRegionNode, Page};
 use crate::world::{CompilerFeat, CompilerWorld, TaskInputs, WorldComputeGraph};
 use crate::TypstDict;
 
 pub type LayoutWidths = EcoVec<typst::layout::Abs>;
 
+/// A dimension of the layouts besides the width, along which the document is
+/// compiled once per value of `sys.inputs.<key>`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LayoutAxis {
+    /// The key in `sys.inputs`, e.g. `x-target`.
+    pub key: EcoString,
+    pub values: EcoVec<EcoString>,
+}
+
+/// Discovers the layout widths by bisecting between the minimum and maximum
+/// widths, keeping only the widths at which the layout changes.
+///
+/// An interval is bisected only if the layouts at its ends differ, so a
+/// change reverted within the interval is missed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AdaptiveLayoutWidths {
+    pub min: typst::layout::Abs,
+    pub max: typst::layout::Abs,
+    /// The intervals narrower than this are not bisected.
+    pub precision: typst::layout::Abs,
+    /// The maximum number of compilations per combination of the layout axes,
+    /// which is at least two for the minimum and maximum widths.
+    pub budget: usize,
+}
+
+impl Default for AdaptiveLayoutWidths {
+    fn default() -> Self {
+        Self {
+            min: typst::layout::Abs::pt(360.0),
+            max: typst::layout::Abs::pt(750.0),
+            precision: typst::layout::Abs::pt(5.0),
+            budget: 40,
+        }
+    }
+}
+
+impl AdaptiveLayoutWidths {
+    /// Discovers the widths, returning the layouts from the widest one.
+    ///
+    /// The widths of each round are compiled concurrently, but lowered in
+    /// order, so that the items are interned deterministically.
+    fn discover<D: Send>(
+        &self,
+        compile: impl Fn(typst::layout::Abs) -> (Option<D>, DynSvgLayoutReport) + Sync,
+        mut lower: impl FnMut(&D, &mut DynSvgLayoutReport) -> Vec<Page>,
+    ) -> (Vec<LayoutSample<D>>, Vec<DynSvgLayoutReport>) {
+        let mut samples: Vec<LayoutSample<D>> = vec![];
+        let mut reports = vec![];
+
+        let mut pending = vec![self.max];
+        if self.min < self.max {
+            pending.push(self.min);
+        }
+        let mut budget = self.budget.max(pending.len());
+        while !pending.is_empty() {
+            let compiled = pending.par_iter().map(|width| compile(*width));
+            let compiled = compiled.collect::<Vec<_>>();
+            budget -= pending.len();
+
+            for (output, mut report) in compiled {
+                // The errors are reported without the document anyway.
+                if !report.errors.is_empty() {
+                    reports.push(report);
+                    return (vec![], reports);
+                }
+                if let Some(output) = output {
+                    let pages = lower(&output, &mut report);
+                    samples.push(LayoutSample {
+                        width: report.width,
+                        output,
+                        pages,
+                    });
+                }
+                reports.push(report);
+            }
+            samples.sort_by(|x, y| y.width.cmp(&x.width));
+
+            pending = (samples.windows(2))
+                .filter(|w| w[0].pages != w[1].pages && w[0].width - w[1].width > self.precision)
+                .map(|w| (w[0].width + w[1].width) / 2.)
+                .take(budget)
+                .collect();
+        }
+
+        // The renderer selects the widest layout narrower than the viewport, so
+        // the narrowest width of each run of identical layouts is kept.
+        let mut layouts: Vec<LayoutSample<D>> = vec![];
+        for sample in samples.into_iter().rev() {
+            if layouts
+                .last()
+                .is_some_and(|last| last.pages == sample.pages)
+            {
+                continue;
+            }
+            layouts.push(sample);
+        }
+        layouts.reverse();
+
+        (layouts, reports)
+    }
+}
+
+/// A document compiled and lowered at a layout width.
+struct LayoutSample<D = TypstDocument> {
+    width: typst::layout::Abs,
+    output: D,
+    pages: Vec<Page>,
+}
+
 pub type PostProcessLayoutFn = Arc<
     dyn Fn(&mut Typst2VecPass, TypstDocument, LayoutRegionNode) -> LayoutRegionNode + Send + Sync,
 >;
@@ -38,6 +150,11 @@ pub struct ExportDynSvgModuleTask {
     // output: PathBuf,
     // pub extension: String,
     pub layout_widths: LayoutWidths,
+    /// Discovers the layout widths instead of using [`Self::layout_widths`].
+    pub adaptive_widths: Option<AdaptiveLayoutWidths>,
+    /// The extra dimensions of the layouts, from the outermost one. Each of
+    /// them nests the layouts by a string region named by the key.
+    pub layout_axes: Vec<LayoutAxis>,
 
     pub command_executor: Arc<dyn CommandExecutor + Send + Sync>,
 
@@ -76,6 +193,8 @@ impl ExportDynSvgModuleTask {
                     typst::layout::Abs::pt(750.0) - typst::layout::Abs::pt(i as f64 * 10.0)
                 }),
             ),
+            adaptive_widths: None,
+            layout_axes: vec![],
             command_executor: Arc::new(()),
             post_process_layout: None,
             post_process_layouts: None,
@@ -100,6 +219,18 @@ impl ExportDynSvgModuleTask {
         self.layout_widths = layout_widths;
     }
 
+    pub fn set_adaptive_widths(&mut self, adaptive_widths: Option<AdaptiveLayoutWidths>) {
+        self.adaptive_widths = adaptive_widths;
+    }
+
+    pub fn set_layout_axes(&mut self, layout_axes: Vec<LayoutAxis>) {
+        self.layout_axes = layout_axes;
+    }
+
+    pub fn add_layout_axis(&mut self, key: EcoString, values: EcoVec<EcoString>) {
+        self.layout_axes.push(LayoutAxis { key, values });
+    }
+
     pub fn set_target(&mut self, target: String) {
         self.target = target;
     }
@@ -141,6 +272,59 @@ impl Default for ExportDynSvgModuleTask {
     }
 }
 
+/// The result of compiling the document at one of the layout widths.
+#[derive(Debug, Clone)]
+pub struct DynSvgLayoutReport {
+    /// The layout width passed to the document as `x-page-width`.
+    pub width: typst::layout::Abs,
+    /// The values of the layout axes passed to the document.
+    pub axes: EcoVec<(EcoString, EcoString)>,
+    /// The time spent on compiling and lowering the document.
+    pub elapsed: Duration,
+    pub warnings: EcoVec<SourceDiagnostic>,
+    pub errors: EcoVec<SourceDiagnostic>,
+}
+
+/// The result of compiling the document at all of the layout widths.
+pub struct DynSvgModuleReport {
+    /// The document containing all the layouts, which is absent if any of the
+    /// widths failed to compile.
+    pub doc: Option<MultiVecDocument>,
+    /// The reports of each compilation, grouped by the combinations of the
+    /// layout axes.
+    pub layouts: Vec<DynSvgLayoutReport>,
+}
+
+impl DynSvgModuleReport {
+    /// Iterates over the warnings along with the width producing them.
+    pub fn warnings(&self) -> impl Iterator<Item = (typst::layout::Abs, &SourceDiagnostic)> {
+        (self.layouts.iter()).flat_map(|l| l.warnings.iter().map(move |diag| (l.width, diag)))
+    }
+
+    /// Iterates over the errors along with the width producing them.
+    pub fn errors(&self) -> impl Iterator<Item = (typst::layout::Abs, &SourceDiagnostic)> {
+        (self.layouts.iter()).flat_map(|l| l.errors.iter().map(move |diag| (l.width, diag)))
+    }
+
+    /// Converts the report into the document, or all of the errors hinted with
+    /// the width producing them.
+    pub fn into_result(self) -> SourceResult<MultiVecDocument> {
+        if let Some(doc) = self.doc {
+            return Ok(doc);
+        }
+
+        let errors = self.layouts.into_iter().flat_map(|layout| {
+            let mut inputs = eco_format!("x-page-width={:?}", layout.width);
+            for (key, value) in layout.axes.iter() {
+                inputs.push_str(&eco_format!(", {key}={value}"));
+            }
+            (layout.errors.into_iter())
+                .map(move |diag| diag.with_hint(eco_format!("while compiling with {inputs}")))
+        });
+        Err(errors.collect())
+    }
+}
+
 // F: CompilerFeat, CompilerWorld<F>
 impl ExportDynSvgModuleTask {
     /// Export a typst document using `reflexo_typst::DocumentExporter`.
@@ -148,83 +332,417 @@ impl ExportDynSvgModuleTask {
         &self,
         world: &CompilerWorld<F>,
     ) -> SourceResult<MultiVecDocument> {
+        self.do_export_report(world).into_result()
+    }
+
+    /// Export a typst document, reporting the diagnostics and timings of each
+    /// layout width.
+    pub fn do_export_report<F: CompilerFeat>(
+        &self,
+        world: &CompilerWorld<F>,
+    ) -> DynSvgModuleReport {
         let mut svg_exporter = DynamicLayoutSvgExporter::default();
         svg_exporter.typst2vec.command_executor = self.command_executor.clone();
         self.do_export_with(world, svg_exporter)
     }
 
     /// Export a typst document using `reflexo_typst::DocumentExporter`.
+    ///
+    /// The document is compiled at each combination of the layout axes and
+    /// widths concurrently, each on a forked world, while all the layouts are
+    /// lowered in order by the same pass, so that the items shared by them are
+    /// deduplicated and interned deterministically.
     pub fn do_export_with<F: CompilerFeat>(
         &self,
         world: &CompilerWorld<F>,
         mut svg_exporter: reflexo_vec2svg::DynamicLayoutSvgExporter,
-    ) -> SourceResult<MultiVecDocument> {
-        // self.export(doc.clone())?;
-        // checkout the entry file
-
-        // for each 10pt we rerender once
+    ) -> DynSvgModuleReport {
         let instant_begin = reflexo::time::Instant::now();
-        for (i, current_width) in self.layout_widths.clone().into_iter().enumerate() {
-            let instant = reflexo::time::Instant::now(); // replace layout
 
-            let world = world.task(TaskInputs {
-                inputs: Some({
-                    let mut dict = TypstDict::new();
-                    dict.insert("x-page-width".into(), current_width.into_value());
-                    dict.insert("x-target".into(), self.target.clone().into_value());
+        // Axes without values would leave no layout to select.
+        let axes = (self.layout_axes.iter())
+            .filter(|axis| !axis.values.is_empty())
+            .collect::<Vec<_>>();
+        let mut combinations = vec![EcoVec::new()];
+        for axis in axes.iter() {
+            combinations = (combinations.into_iter())
+                .flat_map(|combination| {
+                    axis.values.iter().map(move |value| {
+                        let mut combination = combination.clone();
+                        combination.push((axis.key.clone(), value.clone()));
+                        combination
+                    })
+                })
+                .collect();
+        }
 
-                    Arc::new(LazyHash::new(dict))
-                }),
-                ..Default::default()
-            });
-
-            log::trace!(
-                "rerendering {i} at {:?}, width={current_width:?} target={}",
-                instant - instant_begin,
-                self.target,
-            );
-
-            // compile and export document
-            // todo: collect warnings and errors here.
-            let output = if self.html_format {
-                let world = world.html_task();
-                TypstDocument::Html(Arc::new(
-                    typst::compile::<TypstHtmlDocument>(world.as_ref()).output?,
-                ))
-            } else {
-                let world = world.paged_task();
-                TypstDocument::Paged(Arc::new(
-                    typst::compile::<TypstPagedDocument>(world.as_ref()).output?,
-                ))
+        let typst2vec = &svg_exporter.typst2vec;
+        let compile = |axes: &EcoVec<(EcoString, EcoString)>, width| {
+            self.compile_layout(world, axes, width, instant_begin)
+        };
+        let lower = |output: &TypstDocument, report: &mut DynSvgLayoutReport| {
+            let instant = reflexo::time::Instant::now();
+            let pages = typst2vec.doc(output);
+            report.elapsed += instant.elapsed();
+            pages
+        };
+
+        let mut samples = Vec::with_capacity(combinations.len());
+        let mut reports = vec![];
+        if let Some(adaptive) = &self.adaptive_widths {
+            // The rounds of bisection depend on the lowered layouts, so the
+            // combinations are discovered one by one.
+            for axes in combinations.iter() {
+                let (layouts, sub_reports) = adaptive.discover(|width| compile(axes, width), lower);
+                samples.push(layouts);
+                reports.extend(sub_reports);
+            }
+        } else {
+            let jobs = (combinations.iter())
+                .flat_map(|axes| self.layout_widths.iter().map(move |width| (axes, *width)))
+                .collect::<Vec<_>>();
+            let compiled = (jobs.par_iter())
+                .map(|(axes, width)| compile(*axes, *width))
+                .collect::<Vec<_>>();
+            let mut compiled = compiled.into_iter();
+            for _ in combinations.iter() {
+                let mut layouts = vec![];
+                for (output, mut report) in compiled.by_ref().take(self.layout_widths.len()) {
+                    if let Some(output) = output {
+                        let pages = lower(&output, &mut report);
+                        layouts.push(LayoutSample {
+                            width: report.width,
+                            output,
+                            pages,
+                        });
+                    }
+                    reports.push(report);
+                }
+                samples.push(layouts);
+            }
+        }
+
+        // Any failed width makes the document incomplete.
+        if reports.iter().any(|report| !report.errors.is_empty()) {
+            return DynSvgModuleReport {
+                doc: None,
+                layouts: reports,
             };
-            let mut layout = svg_exporter.render(&output);
+        }
 
-            if let Some(post_process_layout) = &self.post_process_layout {
-                layout = post_process_layout(&mut svg_exporter.typst2vec, output.clone(), layout);
+        // Each combination of the axes owns a width region, which is nested in
+        // the string regions of the axes. The layouts are post processed in
+        // order, since the processors may mutate the pass.
+        let mut width_regions = Vec::with_capacity(combinations.len());
+        for layouts in samples {
+            let mut width_layouts = Vec::with_capacity(layouts.len());
+            for LayoutSample {
+                width,
+                output,
+                pages,
+            } in layouts
+            {
+                // The headings are moved across pages by the width of each layout.
+                let outline = Arc::new(svg_exporter.typst2vec.outline(output.introspector()));
+                let mut layout = LayoutRegionNode::new_pages_with_outline(pages, outline);
+                if let Some(post_process_layout) = &self.post_process_layout {
+                    layout = post_process_layout(&mut svg_exporter.typst2vec, output, layout);
+                }
+                width_layouts.push((width.into_typst(), layout));
             }
-            svg_exporter
-                .layouts
-                .push((current_width.into_typst(), layout));
-
-            log::trace!("rerendered {i} at {:?}", instant - instant_begin);
+            width_regions.push(LayoutRegion::new_by_scalar("width".into(), width_layouts));
         }
 
-        // post process
-        let mut layouts = vec![LayoutRegion::new_by_scalar(
-            "width".into(),
-            svg_exporter.layouts,
-        )];
+        let mut width_regions = width_regions.into_iter();
+        let mut nested = vec![];
+        let root = nest_layouts(&axes, &mut width_regions, &mut nested);
+        let mut layouts = std::iter::once(root).chain(nested).collect::<Vec<_>>();
         if let Some(post_process_layouts) = &self.post_process_layouts {
             layouts = post_process_layouts(&mut svg_exporter.typst2vec, layouts);
         }
 
         // finalize
         let module = svg_exporter.typst2vec.finalize();
-        let doc = MultiVecDocument { module, layouts };
+        let doc = MultiVecDocument {
+            module,
+            layouts,
+            outline: None,
+        };
+
+        log::trace!("multiple layouts finished at {:?}", instant_begin.elapsed());
+
+        DynSvgModuleReport {
+            doc: Some(doc),
+            layouts: reports,
+        }
+    }
 
+    /// Compiles the document at the layout width.
+    fn compile_layout<F: CompilerFeat>(
+        &self,
+        world: &CompilerWorld<F>,
+        axes: &EcoVec<(EcoString, EcoString)>,
+        width: typst::layout::Abs,
+        instant_begin: reflexo::time::Instant,
+    ) -> (Option<TypstDocument>, DynSvgLayoutReport) {
         let instant = reflexo::time::Instant::now();
-        log::trace!("multiple layouts finished at {:?}", instant - instant_begin);
+        log::trace!(
+            "rerendering at {:?}, width={width:?} target={} axes={axes:?}",
+            instant - instant_begin,
+            self.target,
+        );
+
+        let world = world.task(TaskInputs {
+            inputs: Some({
+                let mut dict = TypstDict::new();
+                dict.insert("x-page-width".into(), width.into_value());
+                dict.insert("x-target".into(), self.target.clone().into_value());
+                for (key, value) in axes.iter() {
+                    dict.insert(key.as_str().into(), value.clone().into_value());
+                }
+
+                Arc::new(LazyHash::new(dict))
+            }),
+            ..Default::default()
+        });
+
+        let (output, warnings) = self.compile(&world);
+        let (output, errors) = match output {
+            Ok(output) => (Some(output), EcoVec::new()),
+            Err(errors) => (None, errors),
+        };
+
+        log::trace!("rerendered at {:?}, width={width:?}", instant.elapsed());
+        let report = DynSvgLayoutReport {
+            width,
+            axes: axes.clone(),
+            elapsed: instant.elapsed(),
+            warnings,
+            errors,
+        };
+        (output, report)
+    }
+
+    /// Compiles the document in the format of the task.
+    fn compile<F: CompilerFeat>(
+        &self,
+        world: &CompilerWorld<F>,
+    ) -> (SourceResult<TypstDocument>, EcoVec<SourceDiagnostic>) {
+        if self.html_format {
+            let world = world.html_task();
+            let res = typst::compile::<TypstHtmlDocument>(world.as_ref());
+            let output = res.output.map(|doc| TypstDocument::Html(Arc::new(doc)));
+            (output, res.warnings)
+        } else {
+            let world = world.paged_task();
+            let res = typst::compile::<TypstPagedDocument>(world.as_ref());
+            let output = res.output.map(|doc| TypstDocument::Paged(Arc::new(doc)));
+            (output, res.warnings)
+        }
+    }
+}
+
+/// Nests the width regions by the axes, where the regions referred to by
+/// [`LayoutRegionNode::Indirect`] are pushed to `nested`, which follows the
+/// returned root region.
+fn nest_layouts(
+    axes: &[&LayoutAxis],
+    width_regions: &mut impl Iterator<Item = LayoutRegion>,
+    nested: &mut Vec<LayoutRegion>,
+) -> LayoutRegion {
+    let Some((axis, rest)) = axes.split_first() else {
+        return width_regions.next().unwrap();
+    };
+
+    let mut layouts = Vec::with_capacity(axis.values.len());
+    for value in axis.values.iter() {
+        let region = nest_layouts(rest, width_regions, nested);
+        nested.push(region);
+        // the root region is at index 0
+        layouts.push((
+            value.as_str().into(),
+            LayoutRegionNode::Indirect(nested.len()),
+        ));
+    }
+
+    LayoutRegion::new_by_str(axis.key.as_str().into(), layouts)
+}
+
+#[cfg(test)]
+mod tests {
+    use reflexo::hash::Fingerprint;
+    use typst::layout::Abs;
+
+    use super::*;
+    use crate::vector::ir::{
+        LayoutMappingSelector, LayoutNestSelector, LayoutSelectorExpr, Scalar, Size,
+    };
+
+    /// Discovers the widths of a document whose layout changes at the
+    /// breakpoints, where each layout is identified by the number of the
+    /// breakpoints not wider than the width.
+    fn discover(
+        adaptive: AdaptiveLayoutWidths,
+        breakpoints: &[f64],
+    ) -> (Vec<(f64, u128)>, Vec<DynSvgLayoutReport>) {
+        let compile = |width: Abs| {
+            let layout = breakpoints
+                .iter()
+                .filter(|bp| **bp <= width.to_pt())
+                .count();
+            let report = DynSvgLayoutReport {
+                width,
+                axes: EcoVec::new(),
+                elapsed: Duration::ZERO,
+                warnings: EcoVec::new(),
+                errors: EcoVec::new(),
+            };
+            (Some(layout as u128), report)
+        };
+        let lower = |layout: &u128, _: &mut DynSvgLayoutReport| {
+            vec![Page {
+                content: Fingerprint::from_u128(*layout),
+                size: Size::new(Scalar(0.), Scalar(0.)),
+                label: None,
+            }]
+        };
+
+        let (layouts, reports) = adaptive.discover(compile, lower);
+        let layouts = layouts.into_iter();
+        let layouts = layouts.map(|sample| (sample.width.to_pt(), sample.output));
+        (layouts.collect(), reports)
+    }
+
+    fn adaptive(min: f64, max: f64) -> AdaptiveLayoutWidths {
+        AdaptiveLayoutWidths {
+            min: Abs::pt(min),
+            max: Abs::pt(max),
+            ..AdaptiveLayoutWidths::default()
+        }
+    }
+
+    #[test]
+    fn test_discover_converges_to_breakpoints() {
+        let adaptive = adaptive(360., 750.);
+        let (layouts, reports) = discover(adaptive, &[500., 620.]);
+
+        let outputs = layouts.iter().map(|(_, layout)| *layout);
+        assert_eq!(outputs.collect::<Vec<_>>(), vec![2, 1, 0]);
+        // The narrowest width of each layout is close to its breakpoint.
+        assert!((620.0..620.0 + 5.0).contains(&layouts[0].0));
+        assert!((500.0..500.0 + 5.0).contains(&layouts[1].0));
+        assert_eq!(layouts[2].0, 360.);
+        assert!(reports.len() <= adaptive.budget);
+    }
+
+    #[test]
+    fn test_discover_within_budget() {
+        let adaptive = AdaptiveLayoutWidths {
+            budget: 4,
+            ..adaptive(360., 750.)
+        };
+        let (layouts, reports) = discover(adaptive, &[500.]);
+
+        assert_eq!(reports.len(), 4);
+        let outputs = layouts.iter().map(|(_, layout)| *layout);
+        assert_eq!(outputs.collect::<Vec<_>>(), vec![1, 0]);
+    }
+
+    #[test]
+    fn test_discover_single_width() {
+        let (layouts, reports) = discover(adaptive(500., 500.), &[400., 600.]);
+
+        assert_eq!(reports.len(), 1);
+        assert_eq!(layouts, vec![(500., 1)]);
+    }
+
+    #[test]
+    fn test_discover_dedups_identical_layouts() {
+        // The breakpoints out of the range never change the layout.
+        let (layouts, reports) = discover(adaptive(360., 750.), &[100., 800.]);
+
+        // Only the ends are compiled, since their layouts are identical.
+        assert_eq!(reports.len(), 2);
+        assert_eq!(layouts, vec![(360., 1)]);
+    }
+
+    fn axis(key: &str, values: &[&str]) -> LayoutAxis {
+        LayoutAxis {
+            key: key.into(),
+            values: values.iter().map(|value| EcoString::from(*value)).collect(),
+        }
+    }
+
+    /// Nests the width regions by the axes, where the only width of each
+    /// region is the index of its combination of the axes.
+    fn nested(axes: &[LayoutAxis]) -> Vec<LayoutRegion> {
+        let axes = axes.iter().collect::<Vec<_>>();
+        let mut width_regions = (0..).map(|idx| {
+            let layout = LayoutRegionNode::new_pages(vec![]);
+            LayoutRegion::new_by_scalar("width".into(), vec![(Scalar(idx as f32), layout)])
+        });
+        let mut nested = vec![];
+        let root = nest_layouts(&axes, &mut width_regions, &mut nested);
+        std::iter::once(root).chain(nested).collect()
+    }
+
+    /// Selects the width region, returning the index of its combination.
+    fn select(layouts: &[LayoutRegion], selectors: &[(&str, &str)]) -> Result<f32> {
+        let selectors = selectors.iter().map(|(kind, value)| {
+            let expr = LayoutSelectorExpr::StrEQ(value.to_string());
+            (kind.to_string(), expr)
+        });
+        let selector = LayoutNestSelector {
+            layouts,
+            inner: LayoutMappingSelector {
+                selectors: selectors.collect(),
+            },
+        };
+
+        let region = selector.select_region("width")?;
+        Ok(region.by_scalar().unwrap()[0].0 .0)
+    }
+
+    #[test]
+    fn test_nest_layouts_without_axes() {
+        let layouts = nested(&[]);
+        assert_eq!(layouts.len(), 1);
+        assert_eq!(select(&layouts, &[]).unwrap(), 0.);
+    }
+
+    #[test]
+    fn test_select_region_across_axes() {
+        let layouts = nested(&[
+            axis("x-theme", &["light", "dark"]),
+            axis("x-target", &["web", "mobile"]),
+        ]);
+        // the root region, two target regions and four width regions
+        assert_eq!(layouts.len(), 7);
+        assert_eq!(layouts[0].kind(), "x-theme");
+
+        let select =
+            |theme, target| select(&layouts, &[("x-theme", theme), ("x-target", target)]).unwrap();
+        assert_eq!(select("light", "web"), 0.);
+        assert_eq!(select("light", "mobile"), 1.);
+        assert_eq!(select("dark", "web"), 2.);
+        assert_eq!(select("dark", "mobile"), 3.);
+    }
 
-        Ok(doc)
+    #[test]
+    fn test_select_region_fallback() {
+        let layouts = nested(&[
+            axis("x-theme", &["light", "dark"]),
+            axis("x-target", &["web", "mobile"]),
+        ]);
+
+        // The axes without selectors fall back to their first values.
+        assert_eq!(select(&layouts, &[]).unwrap(), 0.);
+        assert_eq!(select(&layouts, &[("x-theme", "dark")]).unwrap(), 2.);
+        assert_eq!(select(&layouts, &[("x-target", "mobile")]).unwrap(), 1.);
+
+        // The values out of the axes select nothing.
+        assert!(select(&layouts, &[("x-theme", "sepia")]).is_err());
+        let selectors = [("x-theme", "dark"), ("x-target", "print")];
+        assert!(select(&layouts, &selectors).is_err());
     }
 }
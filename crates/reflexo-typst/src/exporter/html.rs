@@ -1,38 +1,69 @@
 use std::fmt::Write;
 use std::sync::{Arc, OnceLock};
 
-use ecow::EcoString;
+use ecow::{eco_format, EcoString};
 use reflexo::error::prelude::*;
 use reflexo::typst::TypstHtmlDocument;
+use reflexo_typst2hast::collect_frames;
+use reflexo_vec2svg::{SvgExportFeature, SvgExportOptions, SvgExporter, SvgFrames};
+use serde::{Deserialize, Serialize};
 use tinymist_world::{CompilerFeat, ExportComputation, WorldComputeGraph};
 use typst::diag::{bail, At, SourceResult, StrResult};
 use typst::foundations::Repr;
 use typst::layout::Frame;
 use typst::syntax::Span;
+use typst::World;
 use typst_html::{charsets, tag, HtmlElement, HtmlNode, HtmlTag};
 
 pub type ExportStaticHtmlTask = tinymist_task::ExportHtmlTask;
 pub type StaticHtmlExport = tinymist_task::HtmlExport;
 pub type ExportHtmlTask = tinymist_task::ExportHtmlTask;
 
+pub use reflexo_typst2hast::HtmlFrameMode;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ExportHtmlOutputTask {
+    #[serde(flatten)]
+    pub export: ExportHtmlTask,
+    /// Whether to attach the source locations of the elements, as the
+    /// `position` of the hast nodes and the `data-typst-span` attributes in
+    /// HTML.
+    #[serde(default)]
+    pub source_positions: bool,
+    /// How to encode the frames embedded in the document, e.g. equations.
+    #[serde(default)]
+    pub frames: HtmlFrameMode,
+}
+
 pub struct HtmlOutputExport;
 
 impl<F: CompilerFeat> ExportComputation<F, TypstHtmlDocument> for HtmlOutputExport {
     type Output = HtmlOutput;
-    type Config = ExportHtmlTask;
+    type Config = ExportHtmlOutputTask;
 
     fn run(
-        _graph: &Arc<WorldComputeGraph<F>>,
+        graph: &Arc<WorldComputeGraph<F>>,
         doc: &Arc<TypstHtmlDocument>,
-        _config: &ExportHtmlTask,
+        config: &ExportHtmlOutputTask,
     ) -> Result<HtmlOutput> {
-        Ok(static_html(doc)?)
+        let mut output = static_html(doc)?.with_frame_mode(config.frames);
+        if config.source_positions {
+            output = output.with_source_positions(Arc::new(graph.snap.world.clone()));
+        }
+
+        Ok(output)
     }
 }
 
 pub struct HtmlOutput {
     pretty: bool,
     document: Arc<TypstHtmlDocument>,
+    /// The world to resolve the source locations, if enabled.
+    world: Option<Arc<dyn World>>,
+    /// How to encode the frames.
+    frames: HtmlFrameMode,
+    svg_frames: OnceLock<SvgFrames>,
     head_idx: Option<usize>,
     body_idx: Option<usize>,
 
@@ -41,6 +72,60 @@ pub struct HtmlOutput {
 }
 
 impl HtmlOutput {
+    /// Attaches the source locations of the elements to the output.
+    ///
+    /// The HTML elements get a `data-typst-span="path:line:column"`
+    /// attribute, and the hast nodes get their `position`.
+    pub fn with_source_positions(self, world: Arc<dyn World>) -> Self {
+        Self {
+            world: Some(world),
+            body: OnceLock::new(),
+            html: OnceLock::new(),
+            ..self
+        }
+    }
+
+    /// Sets how to encode the frames embedded in the document.
+    pub fn with_frame_mode(self, frames: HtmlFrameMode) -> Self {
+        Self {
+            frames,
+            svg_frames: OnceLock::new(),
+            body: OnceLock::new(),
+            html: OnceLock::new(),
+            ..self
+        }
+    }
+
+    /// Gets the frames rendered by `reflexo-vec2svg`.
+    ///
+    /// The glyphs are shared across the frames only in the
+    /// [`HtmlFrameMode::Inline`] mode. Otherwise, each SVG is self-contained.
+    pub fn svg_frames(&self) -> &SvgFrames {
+        self.svg_frames.get_or_init(|| {
+            let mut frames = vec![];
+            collect_frames(&self.document.root, &mut frames);
+
+            SvgExporter::<SvgExportFeature>::render_frames(
+                &self.document.introspector,
+                frames,
+                self.frames == HtmlFrameMode::Inline,
+                &SvgExportOptions::default(),
+            )
+        })
+    }
+
+    /// Gets the external SVG files of the frames, as pairs of the paths
+    /// relative to the HTML file and the contents.
+    ///
+    /// It is empty unless the frames are encoded as [`HtmlFrameMode::Files`],
+    /// where the paths are given by [`SvgFrames::path`].
+    pub fn frame_files(&self) -> Vec<(String, &str)> {
+        match self.frames {
+            HtmlFrameMode::Files => self.svg_frames().files().collect(),
+            HtmlFrameMode::Svg | HtmlFrameMode::Inline => vec![],
+        }
+    }
+
     fn root_child(&self, idx: Option<usize>) -> Option<&HtmlElement> {
         match self.document.root.children.get(idx?)? {
             HtmlNode::Element(e) => Some(e),
@@ -100,6 +185,9 @@ impl HtmlOutput {
             .get_or_init(|| {
                 let mut w = Writer {
                     pretty: self.pretty,
+                    world: self.world.as_deref(),
+                    frames: self.frames,
+                    svg_frames: (self.frames == HtmlFrameMode::Inline).then(|| self.svg_frames()),
                     ..Writer::default()
                 };
                 write_indent(&mut w);
@@ -123,6 +211,9 @@ impl HtmlOutput {
             .get_or_init(|| {
                 let mut w = Writer {
                     pretty: self.pretty,
+                    world: self.world.as_deref(),
+                    frames: self.frames,
+                    svg_frames: (self.frames == HtmlFrameMode::Inline).then(|| self.svg_frames()),
                     ..Writer::default()
                 };
                 w.buf.push_str("<!DOCTYPE html>\n");
@@ -140,7 +231,12 @@ impl HtmlOutput {
 
     #[cfg(feature = "hast")]
     pub fn hast(&self) -> SourceResult<reflexo_typst2hast::hast::HastElementContent> {
-        reflexo_typst2hast::hast(&self.document)
+        let options = reflexo_typst2hast::HastOptions {
+            world: self.world.as_deref(),
+            frames: self.frames,
+            svg_frames: (self.frames == HtmlFrameMode::Inline).then(|| self.svg_frames()),
+        };
+        reflexo_typst2hast::hast_with(&self.document, options)
     }
 }
 
@@ -159,6 +255,9 @@ pub fn static_html(document: &Arc<TypstHtmlDocument>) -> SourceResult<HtmlOutput
     Ok(HtmlOutput {
         pretty: true,
         document: document.clone(),
+        world: None,
+        frames: HtmlFrameMode::default(),
+        svg_frames: OnceLock::new(),
         head_idx,
         body_idx,
         body: OnceLock::new(),
@@ -167,13 +266,37 @@ pub fn static_html(document: &Arc<TypstHtmlDocument>) -> SourceResult<HtmlOutput
 }
 
 #[derive(Default)]
-struct Writer {
+struct Writer<'a> {
     /// The output buffer.
     buf: String,
     /// The current indentation level
     level: usize,
     /// Whether pretty printing is enabled.
     pretty: bool,
+    /// The world to resolve the source locations, if enabled.
+    world: Option<&'a dyn World>,
+    /// How to encode the frames.
+    frames: HtmlFrameMode,
+    /// The frames rendered by `reflexo-vec2svg`, if required by the mode.
+    svg_frames: Option<&'a SvgFrames>,
+}
+
+impl Writer<'_> {
+    /// Formats the source location of a span as `path:line:column`, if
+    /// enabled.
+    ///
+    /// The path is prefixed with the package specifier if the file is in a
+    /// package. The line and column are 1-indexed.
+    fn span_location(&self, span: Span) -> Option<EcoString> {
+        let position = reflexo_typst2hast::position(self.world?, span)?;
+        let start = position.start;
+        Some(eco_format!(
+            "{}:{}:{}",
+            position.file?,
+            start.line,
+            start.column
+        ))
+    }
 }
 
 /// Write a newline and indent, if pretty printing is enabled.
@@ -234,12 +357,31 @@ fn write_element_with_tag(w: &mut Writer, element: &HtmlElement, tag: &str) -> S
         w.buf.push('"');
     }
 
+    if let Some(location) = w.span_location(element.span) {
+        w.buf.push_str(" data-typst-span=\"");
+        for c in location.chars() {
+            if charsets::is_valid_in_attribute_value(c) {
+                w.buf.push(c);
+            } else {
+                write_escape(w, c).at(element.span)?;
+            }
+        }
+        w.buf.push('"');
+    }
+
     w.buf.push('>');
 
     if tag::is_void(element.tag) {
         return Ok(());
     }
 
+    // The frames reference the shared definitions by ids.
+    if element.tag == tag::body && w.frames == HtmlFrameMode::Inline {
+        if let Some(svg_frames) = w.svg_frames {
+            w.buf.push_str(&svg_frames.defs);
+        }
+    }
+
     let pretty = w.pretty;
     if !element.children.is_empty() {
         let pretty_inside = allows_pretty_inside(element.tag)
@@ -320,6 +462,22 @@ fn write_escape(w: &mut Writer, c: char) -> StrResult<()> {
 
 /// Encode a laid out frame into the writer.
 fn write_frame(w: &mut Writer, frame: &Frame) {
+    match w.frames {
+        HtmlFrameMode::Svg => {}
+        HtmlFrameMode::Inline => {
+            if let Some(svg) = w.svg_frames.and_then(|f| f.get(frame)) {
+                w.buf.push_str(svg);
+                return;
+            }
+        }
+        HtmlFrameMode::Files => {
+            w.buf.push_str(r#"<img class="typst-frame" src=""#);
+            w.buf.push_str(&SvgFrames::path(frame));
+            w.buf.push_str(r#"">"#);
+            return;
+        }
+    }
+
     // FIXME: This string replacement is obviously a hack.
     let svg = typst_svg::svg_frame(frame)
         .replace("<svg class", "<svg style=\"overflow: visible;\" class");
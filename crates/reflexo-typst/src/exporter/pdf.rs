@@ -0,0 +1,187 @@
+use std::sync::Arc;
+
+use chrono::{Datelike, Timelike};
+use reflexo::error::prelude::*;
+use reflexo::typst::{Bytes, TypstPagedDocument};
+use serde::{Deserialize, Serialize};
+use tinymist_task::{ExportPdfTask, Pages};
+use typst::foundations::{Datetime, Smart};
+use typst::World;
+use typst_pdf::{PageRanges, PdfOptions, PdfStandard, PdfStandards, Timestamp};
+
+use crate::world::{CompilerFeat, ExportComputation, WorldComputeGraph};
+
+/// Exports a paged document as PDF, with the options of `typst-pdf` not yet
+/// covered by [`ExportPdfTask`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ExportPdfOutputTask {
+    #[serde(flatten)]
+    pub export: ExportPdfTask,
+    /// The pages to export. All pages are exported if it is `None`.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub pages: Option<Vec<Pages>>,
+    /// Whether to omit the tags for accessibility. A tagged PDF is written by
+    /// default.
+    #[serde(default)]
+    pub no_tags: bool,
+    /// The identifier of the document, which should stay the same across the
+    /// revisions of the document. It is derived from the title and the
+    /// authors of the document if it is `None`.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub ident: Option<String>,
+}
+
+impl ExportPdfOutputTask {
+    /// Gets the standards to enforce conformance with.
+    ///
+    /// The standards of `tinymist-task` are converted by their names, e.g.
+    /// `a-2b`, which are shared with `typst-pdf`.
+    fn standards(&self) -> Result<PdfStandards> {
+        let standards = self.export.pdf_standards.iter().map(|standard| {
+            let name = serde_json::to_value(standard).context("PdfExport: invalid standard")?;
+            serde_json::from_value::<PdfStandard>(name.clone()).map_err(
+                |err| error_once!("PdfExport: unsupported standard", standard: name, err: err),
+            )
+        });
+        let standards = standards.collect::<Result<Vec<_>>>()?;
+
+        // The standards requiring a tagged PDF are checked by `typst-pdf`.
+        PdfStandards::new(&standards)
+            .map_err(|err| error_once!("PdfExport: invalid combination of standards", err: err))
+    }
+
+    /// Converts the task into the options of `typst-pdf`, validating them
+    /// against the document.
+    fn options(&self, doc: &TypstPagedDocument, today: Option<Datetime>) -> Result<PdfOptions> {
+        let total = doc.pages.len();
+        for range in self.pages.iter().flatten() {
+            let (start, end) = (range.0.start(), range.0.end());
+            if let Some(start) = start.filter(|start| start.get() > total) {
+                Err(error_once!("PdfExport: page out of range", page: start, total: total))?;
+            }
+            if let (Some(start), Some(end)) = (start, end) {
+                if start > end {
+                    Err(error_once!("PdfExport: empty page range", start: start, end: end))?;
+                }
+            }
+        }
+        let page_ranges = self
+            .pages
+            .as_ref()
+            .map(|pages| PageRanges::new(pages.iter().map(|range| range.0.clone()).collect()));
+
+        let ident = match self.ident.as_deref() {
+            Some(ident) if ident.trim().is_empty() => Err(error_once!(
+                "PdfExport: the document identifier must not be empty"
+            ))?,
+            Some(ident) => Smart::Custom(ident),
+            None => Smart::Auto,
+        };
+
+        let timestamp = match self.export.creation_timestamp {
+            Some(secs) => Some(utc_datetime(secs).ok_or_else(
+                || error_once!("PdfExport: invalid creation timestamp", timestamp: secs),
+            )?),
+            None => today,
+        };
+
+        Ok(PdfOptions {
+            ident,
+            timestamp: timestamp.map(Timestamp::new_utc),
+            page_ranges,
+            standards: self.standards()?,
+            tagged: !self.no_tags,
+        })
+    }
+}
+
+pub struct PdfOutputExport;
+
+impl<F: CompilerFeat> ExportComputation<F, TypstPagedDocument> for PdfOutputExport {
+    type Output = Bytes;
+    type Config = ExportPdfOutputTask;
+
+    fn run(
+        g: &Arc<WorldComputeGraph<F>>,
+        doc: &Arc<TypstPagedDocument>,
+        config: &Self::Config,
+    ) -> Result<Bytes> {
+        let options = config.options(doc, g.snap.world.today(Some(0)))?;
+        Ok(Bytes::new(typst_pdf::pdf(doc, &options)?))
+    }
+}
+
+/// Converts a UNIX timestamp in seconds into a UTC datetime.
+fn utc_datetime(secs: i64) -> Option<Datetime> {
+    let dt = chrono::DateTime::from_timestamp(secs, 0)?;
+    Datetime::from_ymd_hms(
+        dt.year(),
+        dt.month().try_into().ok()?,
+        dt.day().try_into().ok()?,
+        dt.hour().try_into().ok()?,
+        dt.minute().try_into().ok()?,
+        dt.second().try_into().ok()?,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ymd_hms(secs: i64) -> Option<(i32, u8, u8, u8, u8, u8)> {
+        let dt = utc_datetime(secs)?;
+        Some((
+            dt.year()?,
+            dt.month()?,
+            dt.day()?,
+            dt.hour()?,
+            dt.minute()?,
+            dt.second()?,
+        ))
+    }
+
+    #[test]
+    fn test_pdf_standards() {
+        let task = |names: &[&str]| ExportPdfOutputTask {
+            export: ExportPdfTask {
+                pdf_standards: names
+                    .iter()
+                    .map(|name| serde_json::from_value(serde_json::json!(name)).unwrap())
+                    .collect(),
+                ..ExportPdfTask::default()
+            },
+            ..ExportPdfOutputTask::default()
+        };
+
+        assert!(task(&[]).standards().is_ok());
+        assert!(task(&["1.7"]).standards().is_ok());
+        assert!(task(&["a-2b"]).standards().is_ok());
+    }
+
+    #[test]
+    fn test_utc_datetime_epoch() {
+        assert_eq!(ymd_hms(0), Some((1970, 1, 1, 0, 0, 0)));
+        assert_eq!(ymd_hms(86399), Some((1970, 1, 1, 23, 59, 59)));
+        assert_eq!(ymd_hms(1_700_000_000), Some((2023, 11, 14, 22, 13, 20)));
+    }
+
+    #[test]
+    fn test_utc_datetime_leap_days() {
+        // 2000 is a leap year as it is divisible by 400.
+        assert_eq!(ymd_hms(951_782_400), Some((2000, 2, 29, 0, 0, 0)));
+        assert_eq!(ymd_hms(951_868_800), Some((2000, 3, 1, 0, 0, 0)));
+        assert_eq!(ymd_hms(1_709_164_800), Some((2024, 2, 29, 0, 0, 0)));
+        // 2100 is not a leap year as it is divisible by 100.
+        assert_eq!(ymd_hms(4_107_456_000), Some((2100, 2, 28, 0, 0, 0)));
+        assert_eq!(ymd_hms(4_107_542_400), Some((2100, 3, 1, 0, 0, 0)));
+    }
+
+    #[test]
+    fn test_utc_datetime_negative() {
+        assert_eq!(ymd_hms(-1), Some((1969, 12, 31, 23, 59, 59)));
+        assert_eq!(ymd_hms(-86400), Some((1969, 12, 31, 0, 0, 0)));
+        assert_eq!(ymd_hms(-2_208_988_800), Some((1900, 1, 1, 0, 0, 0)));
+        assert_eq!(ymd_hms(-68_428_800), Some((1967, 11, 1, 0, 0, 0)));
+    }
+}
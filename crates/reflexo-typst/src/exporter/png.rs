@@ -0,0 +1,167 @@
+use std::sync::Arc;
+
+use reflexo::error::prelude::*;
+use reflexo::typst::Bytes;
+use reflexo::typst::TypstPagedDocument;
+use reflexo_typst2vec::pass::Typst2VecPass;
+use reflexo_vec2canvas::DefaultCanvasTask;
+use serde::{Deserialize, Serialize};
+use tinymist_task::{ExportTask, Pages};
+
+use crate::task::CancelToken;
+use crate::world::{CompilerFeat, ExportComputation, WorldComputeGraph};
+
+/// The pages rendered by [`WebPngExport`], each of which is paired with its
+/// (zero-based) page index in the document.
+pub type WebPngPages = Vec<(usize, Bytes)>;
+
+/// Renders pages into PNG images from the vector IR, which produces the same
+/// pixels as the canvas renderer in browsers.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ExportWebPngTask {
+    #[serde(flatten)]
+    pub export: ExportTask,
+    /// The pixels per inch of the output images.
+    pub ppi: f32,
+    /// The pages to export. All pages are exported if it is `None`.
+    pub pages: Option<Vec<Pages>>,
+    /// The background color of the pages in css format.
+    /// If the string is empty, the background is transparent.
+    pub fill: String,
+}
+
+impl Default for ExportWebPngTask {
+    fn default() -> Self {
+        Self {
+            export: ExportTask::default(),
+            ppi: 144.,
+            pages: None,
+            fill: "#ffffff".to_owned(),
+        }
+    }
+}
+
+impl ExportWebPngTask {
+    /// Whether the page at the (zero-based) index is selected for export.
+    pub fn is_page_selected(&self, idx: usize) -> bool {
+        let Some(pages) = &self.pages else {
+            return true;
+        };
+
+        let number = idx + 1;
+        pages.iter().any(|range| {
+            let lo = range.0.start().is_none_or(|lo| lo.get() <= number);
+            let hi = range.0.end().is_none_or(|hi| number <= hi.get());
+            lo && hi
+        })
+    }
+}
+
+pub struct WebPngExport;
+
+impl<F: CompilerFeat> ExportComputation<F, TypstPagedDocument> for WebPngExport {
+    type Output = WebPngPages;
+    type Config = ExportWebPngTask;
+
+    fn run(
+        g: &Arc<WorldComputeGraph<F>>,
+        doc: &Arc<TypstPagedDocument>,
+        config: &Self::Config,
+    ) -> Result<WebPngPages> {
+        if !config.ppi.is_finite() || config.ppi <= 0. {
+            Err(error_once!("WebPngExport: invalid ppi", ppi: config.ppi))?;
+        }
+
+        let typst2vec = Typst2VecPass::default();
+        let pages = typst2vec.paged(doc);
+        let mut module = typst2vec.finalize();
+        module.prepare_glyphs();
+
+        let pixel_per_pt = config.ppi / 72.;
+        let mut task = DefaultCanvasTask::default();
+        let cancel = CancelToken::of(g);
+
+        let mut images = Vec::new();
+        for (idx, page) in pages.iter().enumerate() {
+            if !config.is_page_selected(idx) {
+                continue;
+            }
+            cancel.check()?;
+
+            let canvas = task
+                .render_page_raster(&module, page, pixel_per_pt, &config.fill)
+                .ok_or_else(|| {
+                    error_once!("WebPngExport: cannot allocate pixmap", page: idx, ppi: config.ppi)
+                })?;
+            let data = canvas
+                .into_pixmap()
+                .encode_png()
+                .context_ut("WebPngExport: cannot encode png")?;
+            images.push((idx, Bytes::new(data)));
+        }
+
+        Ok(images)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+
+    use super::*;
+
+    fn select(pages: &[(usize, usize)]) -> ExportWebPngTask {
+        ExportWebPngTask {
+            pages: Some(
+                pages
+                    .iter()
+                    .map(|&(lo, hi)| Pages(NonZeroUsize::new(lo)..=NonZeroUsize::new(hi)))
+                    .collect(),
+            ),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_all_pages_selected() {
+        let task = ExportWebPngTask::default();
+        assert!((0..4).all(|idx| task.is_page_selected(idx)));
+    }
+
+    #[test]
+    fn test_single_page_selected() {
+        let task = select(&[(2, 2)]);
+        assert!(!task.is_page_selected(0));
+        assert!(task.is_page_selected(1));
+        assert!(!task.is_page_selected(2));
+    }
+
+    #[test]
+    fn test_open_ended_pages_selected() {
+        // `-2`, where zero stands for an open end
+        let task = select(&[(0, 2)]);
+        assert!(task.is_page_selected(0));
+        assert!(task.is_page_selected(1));
+        assert!(!task.is_page_selected(2));
+
+        // `3-`
+        let task = select(&[(3, 0)]);
+        assert!(!task.is_page_selected(1));
+        assert!(task.is_page_selected(2));
+        assert!(task.is_page_selected(100));
+    }
+
+    #[test]
+    fn test_disjoint_pages_selected() {
+        let task = select(&[(1, 1), (3, 4)]);
+        let selected = (0..6).filter(|&idx| task.is_page_selected(idx));
+        assert_eq!(selected.collect::<Vec<_>>(), vec![0, 2, 3]);
+    }
+
+    #[test]
+    fn test_no_pages_selected() {
+        let task = select(&[]);
+        assert!(!task.is_page_selected(0));
+    }
+}
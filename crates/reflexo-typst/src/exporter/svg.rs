@@ -4,7 +4,9 @@ use reflexo::error::prelude::*;
 
 use reflexo::typst::Bytes;
 use reflexo::typst::TypstPagedDocument;
-use reflexo_vec2svg::{render_svg, render_svg_html, ExportFeature, SvgExporter};
+use reflexo_vec2svg::{
+    render_svg_html_with_opts, render_svg_with_opts, ExportFeature, SvgExportOptions, SvgExporter,
+};
 use serde::{Deserialize, Serialize};
 use tinymist_task::{ExportSvgTask, ExportTask};
 
@@ -39,6 +41,9 @@ impl<EF: ExportFeature, F: CompilerFeat> ExportComputation<F, TypstPagedDocument
 pub struct ExportWebSvgTask {
     #[serde(flatten)]
     pub base: ExportSvgTask,
+    /// The runtime options overriding the feature set of the exporter.
+    #[serde(default)]
+    pub options: SvgExportOptions,
 }
 
 pub struct WebSvgExport<EF>(std::marker::PhantomData<EF>);
@@ -52,9 +57,9 @@ impl<EF: ExportFeature, F: CompilerFeat> ExportComputation<F, TypstPagedDocument
     fn run(
         _g: &Arc<WorldComputeGraph<F>>,
         doc: &Arc<TypstPagedDocument>,
-        _config: &Self::Config,
+        config: &Self::Config,
     ) -> Result<String> {
-        Ok(render_svg(doc))
+        Ok(render_svg_with_opts(doc, &config.options))
     }
 }
 
@@ -63,6 +68,9 @@ impl<EF: ExportFeature, F: CompilerFeat> ExportComputation<F, TypstPagedDocument
 pub struct ExportWebSvgHtmlTask {
     #[serde(flatten)]
     pub base: ExportSvgTask,
+    /// The runtime options overriding the feature set of the exporter.
+    #[serde(default)]
+    pub options: SvgExportOptions,
 }
 
 pub struct WebSvgHtmlExport<EF>(std::marker::PhantomData<EF>);
@@ -76,8 +84,8 @@ impl<EF: ExportFeature, F: CompilerFeat> ExportComputation<F, TypstPagedDocument
     fn run(
         _g: &Arc<WorldComputeGraph<F>>,
         doc: &Arc<TypstPagedDocument>,
-        _config: &Self::Config,
+        config: &Self::Config,
     ) -> Result<String> {
-        Ok(render_svg_html::<EF>(doc))
+        Ok(render_svg_html_with_opts::<EF>(doc, &config.options))
     }
 }
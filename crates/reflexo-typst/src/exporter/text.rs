@@ -1 +1,400 @@
+use std::sync::Arc;
+
+use reflexo::debug_loc::{ElementPoint, SourceSpanOffset};
+use reflexo::error::prelude::*;
+use reflexo::path::unix_slash;
+use reflexo::typst::TypstPagedDocument;
+use reflexo::vector::ir::{FontItem, Module, Scalar};
+use reflexo_typst2vec::pass::{Span2VecPass, Typst2VecPass};
+use reflexo_typst2vec::text::{page_text_runs, TextRun};
+use serde::{Deserialize, Serialize};
+use tinymist_task::ExportTask;
+use typst::{World, WorldExt};
+
+use crate::world::{CompilerFeat, ExportComputation, WorldComputeGraph};
+
 pub use tinymist_task::text::TextExport;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ExportTextJsonTask {
+    #[serde(flatten)]
+    pub export: ExportTask,
+}
+
+/// Exports the text of a paged document as JSON, with the text runs grouped
+/// into lines and blocks in reading order.
+pub struct TextJsonExport;
+
+impl<F: CompilerFeat> ExportComputation<F, TypstPagedDocument> for TextJsonExport {
+    type Output = String;
+    type Config = ExportTextJsonTask;
+
+    fn run(
+        g: &Arc<WorldComputeGraph<F>>,
+        doc: &Arc<TypstPagedDocument>,
+        _config: &Self::Config,
+    ) -> Result<String> {
+        let doc = text_json_doc(&g.snap.world, doc);
+        serde_json::to_string(&doc).context("failed to serialize text json")
+    }
+}
+
+/// The structured text of a document.
+#[derive(Debug, Clone, Serialize)]
+pub struct TextJsonDocument {
+    pub pages: Vec<TextJsonPage>,
+}
+
+/// The structured text of a page.
+#[derive(Debug, Clone, Serialize)]
+pub struct TextJsonPage {
+    /// The index of the page.
+    pub index: usize,
+    /// The label of the page, e.g. `iv`.
+    pub label: Option<String>,
+    pub width: f32,
+    pub height: f32,
+    pub blocks: Vec<TextJsonBlock>,
+}
+
+/// A block of lines, e.g. a paragraph or a heading.
+#[derive(Debug, Clone, Serialize)]
+pub struct TextJsonBlock {
+    /// The bounding box in the form of `[x0, y0, x1, y1]`, in pt.
+    pub bbox: [f32; 4],
+    pub lines: Vec<TextJsonLine>,
+}
+
+/// A line of text runs sharing the same baseline.
+#[derive(Debug, Clone, Serialize)]
+pub struct TextJsonLine {
+    /// The bounding box in the form of `[x0, y0, x1, y1]`, in pt.
+    pub bbox: [f32; 4],
+    pub runs: Vec<TextJsonRun>,
+}
+
+/// A text item laid out on a page.
+#[derive(Debug, Clone, Serialize)]
+pub struct TextJsonRun {
+    pub text: String,
+    /// The bounding box in the form of `[x0, y0, x1, y1]`, in pt.
+    pub bbox: [f32; 4],
+    /// The font size, in pt.
+    pub font_size: f32,
+    pub font_family: String,
+    /// The labels of the elements containing the run, from the outermost one.
+    pub labels: Vec<String>,
+    /// The source location of the run, if any.
+    pub span: Option<TextJsonSpan>,
+    #[serde(skip)]
+    baseline: f32,
+}
+
+/// A byte range in a source file.
+#[derive(Debug, Clone, Serialize)]
+pub struct TextJsonSpan {
+    /// The package containing the file, if any.
+    pub package: Option<String>,
+    /// The path of the file, relative to the root of the project or package.
+    pub path: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Converts a paged document into its structured text.
+pub fn text_json_doc(world: &dyn World, doc: &TypstPagedDocument) -> TextJsonDocument {
+    let mut pass = Typst2VecPass::default();
+    let pages = pass.paged(doc);
+    let mut spans = std::mem::take(&mut pass.spans);
+    let module = pass.finalize();
+
+    let pages = pages
+        .iter()
+        .enumerate()
+        .map(|(idx, page)| {
+            let mut task = TextJsonTask {
+                world,
+                module: &module,
+                spans: &mut spans,
+            };
+            let runs = page_text_runs(&module, idx, &page.content);
+            let runs = runs.iter().filter_map(|run| task.run(run)).collect();
+
+            TextJsonPage {
+                index: idx,
+                label: page.label.as_deref().map(ToOwned::to_owned),
+                width: page.size.x.0,
+                height: page.size.y.0,
+                blocks: group_blocks(group_lines(runs)),
+            }
+        })
+        .collect();
+
+    TextJsonDocument { pages }
+}
+
+struct TextJsonTask<'a> {
+    world: &'a dyn World,
+    module: &'a Module,
+    spans: &'a mut Span2VecPass,
+}
+
+impl TextJsonTask<'_> {
+    /// Converts a text run into its structured text.
+    fn run(&mut self, run: &TextRun) -> Option<TextJsonRun> {
+        let font = self.module.get_font(&run.text.shape.font)?;
+        Some(TextJsonRun {
+            span: self.resolve_span(&run.path),
+            ..text_json_run(run, font)
+        })
+    }
+
+    /// Resolves the source location of the element at the path.
+    fn resolve_span(&mut self, path: &[u32]) -> Option<TextJsonSpan> {
+        let path = path
+            .chunks_exact(2)
+            .map(|point| ElementPoint {
+                kind: point[0],
+                index: point[1],
+                // An empty fingerprint skips the check of the element.
+                fingerprint: String::new(),
+            })
+            .collect::<Vec<_>>();
+        let (start, end) = self.spans.query(&path).ok()??;
+
+        let id = start.span.id()?;
+        let offset = |span: SourceSpanOffset| {
+            let range = self.world.range(span.span)?;
+            Some((range.start + span.offset).min(range.end)..range.end)
+        };
+        let (start, end) = (offset(start)?, offset(end)?);
+
+        Some(TextJsonSpan {
+            package: id.package().map(ToString::to_string),
+            path: unix_slash(id.vpath().as_rooted_path()),
+            start: start.start,
+            end: end.end.max(start.start),
+        })
+    }
+}
+
+/// Converts a text run into its structured text without the source location.
+fn text_json_run(run: &TextRun, font: &FontItem) -> TextJsonRun {
+    let (t, ts) = (run.text, run.ts);
+    // The length of the vertical axis on the page keeps the size of the
+    // rotated or skewed runs.
+    let font_size = t.shape.size.0 * ts.kx.0.hypot(ts.sy.0);
+    let bbox = run.bbox(font, Scalar(0.), t.width());
+
+    TextJsonRun {
+        text: t.content.content.to_string(),
+        bbox: [bbox.lo.x.0, bbox.lo.y.0, bbox.hi.x.0, bbox.hi.y.0],
+        font_size,
+        font_family: font.family.to_string(),
+        labels: run.labels.iter().map(ToString::to_string).collect(),
+        span: None,
+        baseline: ts.ty.0,
+    }
+}
+
+/// Groups the runs into lines, keeping the order in which they are laid out.
+fn group_lines(runs: Vec<TextJsonRun>) -> Vec<TextJsonLine> {
+    let mut lines: Vec<TextJsonLine> = vec![];
+    for run in runs {
+        if let Some(line) = lines.last_mut() {
+            let last = line.runs.last().unwrap();
+            let tolerance = 0.5 * last.font_size.max(run.font_size);
+            if (last.baseline - run.baseline).abs() <= tolerance && run.bbox[0] >= line.bbox[0] {
+                line.bbox = union(line.bbox, run.bbox);
+                line.runs.push(run);
+                continue;
+            }
+        }
+
+        lines.push(TextJsonLine {
+            bbox: run.bbox,
+            runs: vec![run],
+        });
+    }
+
+    for line in lines.iter_mut() {
+        line.runs.sort_by(|x, y| x.bbox[0].total_cmp(&y.bbox[0]));
+    }
+
+    lines
+}
+
+/// Groups the lines into blocks, starting a new block when the font size
+/// changes, the lines don't overlap horizontally, or the gap between them is
+/// larger than half of the font size.
+fn group_blocks(lines: Vec<TextJsonLine>) -> Vec<TextJsonBlock> {
+    let font_size = |line: &TextJsonLine| line.runs.iter().map(|r| r.font_size).fold(0., f32::max);
+
+    let mut blocks: Vec<TextJsonBlock> = vec![];
+    for line in lines {
+        if let Some(block) = blocks.last_mut() {
+            let last = block.lines.last().unwrap();
+            let gap = line.bbox[1] - last.bbox[3];
+            let overlaps = line.bbox[0] < last.bbox[2] && last.bbox[0] < line.bbox[2];
+            let same_size = (font_size(last) - font_size(&line)).abs() < 1e-3;
+            let size = font_size(&line);
+            if overlaps && same_size && (-0.25 * size..=0.5 * size).contains(&gap) {
+                block.bbox = union(block.bbox, line.bbox);
+                block.lines.push(line);
+                continue;
+            }
+        }
+
+        blocks.push(TextJsonBlock {
+            bbox: line.bbox,
+            lines: vec![line],
+        });
+    }
+
+    blocks
+}
+
+fn union(x: [f32; 4], y: [f32; 4]) -> [f32; 4] {
+    [
+        x[0].min(y[0]),
+        x[1].min(y[1]),
+        x[2].max(y[2]),
+        x[3].max(y[3]),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use reflexo::hash::Fingerprint;
+    use reflexo::vector::ir;
+
+    use super::*;
+
+    fn run(text: &str, x: f32, baseline: f32, size: f32) -> TextJsonRun {
+        let width = text.len() as f32 * 0.5 * size;
+        TextJsonRun {
+            text: text.to_owned(),
+            bbox: [x, baseline - 0.8 * size, x + width, baseline + 0.2 * size],
+            font_size: size,
+            font_family: "Libertinus Serif".to_owned(),
+            labels: vec![],
+            span: None,
+            baseline,
+        }
+    }
+
+    fn texts(line: &TextJsonLine) -> Vec<&str> {
+        line.runs.iter().map(|r| r.text.as_str()).collect()
+    }
+
+    #[test]
+    fn test_group_lines_same_baseline() {
+        let lines = group_lines(vec![
+            run("a", 0., 10., 10.),
+            run("c", 40., 10., 10.),
+            // A shifted run on the same line, laid out after its neighbours.
+            run("b", 20., 11., 10.),
+            run("d", 0., 30., 10.),
+        ]);
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(texts(&lines[0]), vec!["a", "b", "c"]);
+        assert_eq!(lines[0].bbox, [0., 2., 45., 13.]);
+        assert_eq!(texts(&lines[1]), vec!["d"]);
+    }
+
+    #[test]
+    fn test_group_lines_wrapped() {
+        // A run starting left of the line is wrapped even on a close baseline.
+        let lines = group_lines(vec![run("ab", 20., 10., 10.), run("c", 0., 12., 10.)]);
+        assert_eq!(lines.len(), 2);
+    }
+
+    #[test]
+    fn test_group_blocks_paragraph_gap() {
+        let lines = group_lines(vec![
+            run("first", 0., 10., 10.),
+            // The gap to the previous line is 2pt.
+            run("second", 0., 22., 10.),
+            // The gap to the previous line is 8pt, starting a new paragraph.
+            run("third", 0., 40., 10.),
+        ]);
+        let blocks = group_blocks(lines);
+
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].lines.len(), 2);
+        assert_eq!(blocks[0].bbox, [0., 2., 30., 24.]);
+        assert_eq!(blocks[1].lines.len(), 1);
+        assert_eq!(texts(&blocks[1].lines[0]), vec!["third"]);
+    }
+
+    fn text_run(text: &ir::TextItem, ts: ir::Transform) -> TextRun<'_> {
+        TextRun {
+            fg: Fingerprint::from_u128(1),
+            text,
+            ts,
+            labels: vec![],
+            path: vec![],
+        }
+    }
+
+    #[test]
+    fn test_text_json_run_rotated() {
+        let font = FontItem {
+            fingerprint: Fingerprint::from_u128(1),
+            family: "Libertinus Serif".into(),
+            hash: 0,
+            cap_height: Scalar(0.7),
+            ascender: Scalar(0.9),
+            descender: Scalar(-0.2),
+            units_per_em: Scalar(1000.),
+            vertical: false,
+            glyphs: vec![],
+            glyph_cov: Default::default(),
+        };
+        let advance = ir::Axes::new(Scalar(30.), Scalar(0.));
+        let text = ir::TextItem {
+            shape: Arc::new(ir::TextShape {
+                font: ir::FontRef { hash: 0, idx: 0 },
+                dir: "ltr".into(),
+                size: Scalar(10.),
+                styles: vec![],
+            }),
+            content: Arc::new(ir::TextItemContent {
+                content: "up".into(),
+                glyphs: Arc::new([(ir::Axes::default(), advance, 0)]),
+            }),
+        };
+
+        let upright = ir::Transform {
+            sx: Scalar(1.),
+            ky: Scalar(0.),
+            kx: Scalar(0.),
+            sy: Scalar(1.),
+            tx: Scalar(100.),
+            ty: Scalar(50.),
+        };
+        let run = text_json_run(&text_run(&text, upright), &font);
+        assert_eq!(run.bbox, [100., 43., 130., 53.]);
+        assert_eq!(run.font_size, 10.);
+
+        // Rotated by 90 degrees clockwise, the run reads from top to bottom.
+        let rotated = ir::Transform {
+            sx: Scalar(0.),
+            ky: Scalar(1.),
+            kx: Scalar(-1.),
+            sy: Scalar(0.),
+            ..upright
+        };
+        let run = text_json_run(&text_run(&text, rotated), &font);
+        assert_eq!(run.bbox, [97., 50., 107., 80.]);
+        assert_eq!(run.font_size, 10.);
+    }
+
+    #[test]
+    fn test_group_blocks_font_size() {
+        let lines = group_lines(vec![run("Title", 0., 20., 20.), run("body", 0., 35., 10.)]);
+        assert_eq!(group_blocks(lines).len(), 2);
+    }
+}
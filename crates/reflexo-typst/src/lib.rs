@@ -31,7 +31,10 @@
 // #![warn(missing_copy_implementations)]
 
 pub mod config;
+pub mod diag;
 pub mod error;
+#[cfg(feature = "system-watch")]
+pub mod metrics;
 pub mod query;
 pub mod task;
 
@@ -69,9 +72,13 @@ pub use exporter::ast::{dump_ast, AstExport, ExportAstTask};
 pub use exporter::dyn_svg::*;
 #[cfg(feature = "html")]
 pub use exporter::html::*;
+#[cfg(feature = "pdf")]
+pub use exporter::pdf::{ExportPdfOutputTask, PdfOutputExport};
+#[cfg(feature = "png")]
+pub use exporter::png::*;
 #[cfg(feature = "svg")]
 pub use exporter::svg::*;
-pub use exporter::text::TextExport;
+pub use exporter::text::{text_json_doc, ExportTextJsonTask, TextExport, TextJsonExport};
 #[cfg(feature = "svg")]
 pub use reflexo_vec2svg as svg;
 pub use tinymist_task::compute::*;
@@ -117,13 +124,6 @@ pub mod program_meta {
         "This is a bug, please report to https://github.com/Myriad-Dreamin/typst.ts/issues/new";
 }
 
-pub mod diag {
-    // todo: remove cfg feature here
-    #[cfg(feature = "system-compile")]
-    pub use tinymist_world::system::print_diagnostics;
-    pub use tinymist_world::DiagnosticFormat;
-}
-
 pub trait CompilerExt<F: CompilerFeat> {
     fn world(&self) -> &CompilerWorld<F>;
 
@@ -0,0 +1,455 @@
+//! Metrics of the compilations run by the [`crate::CompileActor`], e.g. to
+//! see where the latency of previews goes.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::Write;
+use std::sync::Arc;
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use reflexo::error::prelude::*;
+use serde::Serialize;
+use tinymist_world::{
+    CompilationTask, CompilerFeat, ConfigTask, FlagTask, OptionDocumentTask, WorldComputeGraph,
+};
+
+use crate::CompileReasons;
+
+/// The metrics of a compilation.
+#[derive(Debug, Clone, Serialize)]
+pub struct CompileMetrics {
+    /// The id of the project instance.
+    pub project: String,
+    /// The revision of the universe being compiled.
+    pub revision: usize,
+    /// The reasons that triggered the compilation.
+    pub reason: CompileReasons,
+    /// Whether the compilation is cancelled since a newer revision supersedes
+    /// it.
+    pub cancelled: bool,
+    /// The time from the first event causing the compilation to its start in
+    /// milliseconds.
+    pub queue_wait_ms: f64,
+    /// The time spent by typst on compiling the documents in milliseconds,
+    /// which is recorded by [`MetricsRecorder::compile`].
+    pub compile_ms: f64,
+    /// The time spent on each export task in milliseconds, which is recorded
+    /// by the [`crate::CompilationHandle`].
+    pub exports: Vec<ExportMetrics>,
+    /// The time from the start of the compilation to its end in milliseconds.
+    pub total_ms: f64,
+    /// The number of the file dependencies of the document.
+    pub dependencies: usize,
+}
+
+/// The metrics of an export task.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportMetrics {
+    /// The name of the task, e.g. `pdf`.
+    pub task: String,
+    pub duration_ms: f64,
+}
+
+/// The metrics of a cache eviction.
+#[derive(Debug, Clone, Serialize)]
+pub struct EvictMetrics {
+    /// The revision whose compilation triggered the eviction.
+    pub revision: usize,
+    pub duration_ms: f64,
+}
+
+/// A pluggable sink of the metrics.
+pub trait MetricsSink: Send + Sync + 'static {
+    /// Receives the metrics of a compilation.
+    fn compile(&self, metrics: &CompileMetrics);
+    /// Receives the metrics of a comemo cache eviction.
+    fn evict(&self, metrics: &EvictMetrics);
+}
+
+/// Records the time of the stages run by a [`crate::CompilationHandle`],
+/// which is provided to the compute graph of each compilation.
+#[derive(Debug, Clone, Default)]
+pub struct MetricsRecorder(Arc<Mutex<RecordedMetrics>>);
+
+#[derive(Debug, Default)]
+struct RecordedMetrics {
+    compile: Duration,
+    exports: Vec<ExportMetrics>,
+}
+
+impl MetricsRecorder {
+    /// Gets the recorder of the compute graph. The time is recorded to
+    /// nowhere if the graph is not compiled by the compile actor.
+    pub fn of<F: CompilerFeat>(graph: &WorldComputeGraph<F>) -> Self {
+        let recorder = graph
+            .get::<ConfigTask<MetricsRecorder>>()
+            .and_then(|r| r.ok());
+        recorder.map(|r| r.as_ref().clone()).unwrap_or_default()
+    }
+
+    /// Provides the recorder to the compute graph.
+    pub fn provide<F: CompilerFeat>(&self, graph: &WorldComputeGraph<F>) {
+        let _ = graph.provide::<ConfigTask<MetricsRecorder>>(Ok(Arc::new(self.clone())));
+    }
+
+    /// Compiles the document of the compute graph, recording the time spent
+    /// by typst to the recorder of the graph.
+    ///
+    /// The document is compiled once per graph, so the time is only recorded
+    /// by the first call.
+    pub fn compile<F: CompilerFeat, D: typst::Document + Send + Sync + 'static>(
+        graph: &Arc<WorldComputeGraph<F>>,
+    ) -> Result<Option<Arc<D>>> {
+        if let Some(doc) = graph.get::<OptionDocumentTask<D>>() {
+            return doc.map(|doc| doc.as_ref().clone());
+        }
+
+        let _ = graph.provide::<FlagTask<CompilationTask<D>>>(Ok(FlagTask::flag(true)));
+        let start = std::time::Instant::now();
+        let doc = graph.compute::<OptionDocumentTask<D>>();
+        Self::of(graph).0.lock().compile += start.elapsed();
+        doc.map(|doc| doc.as_ref().clone())
+    }
+
+    /// Records the time spent on an export task.
+    pub fn record_export(&self, task: &str, elapsed: Duration) {
+        self.0.lock().exports.push(ExportMetrics {
+            task: task.to_owned(),
+            duration_ms: millis(elapsed),
+        });
+    }
+
+    /// Gets the recorded compile time and export metrics.
+    pub fn take(&self) -> (Duration, Vec<ExportMetrics>) {
+        let recorded = std::mem::take(&mut *self.0.lock());
+        (recorded.compile, recorded.exports)
+    }
+}
+
+/// Converts a duration into milliseconds.
+pub(crate) fn millis(elapsed: Duration) -> f64 {
+    elapsed.as_secs_f64() * 1000.
+}
+
+/// An event emitted by [`JsonMetricsSink`].
+#[derive(Serialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+enum MetricsEvent<'a> {
+    Compile(&'a CompileMetrics),
+    Evict(&'a EvictMetrics),
+}
+
+/// Writes the metrics as JSON lines, one object per event tagged by `kind`,
+/// either `compile` or `evict`.
+pub struct JsonMetricsSink<W> {
+    writer: Mutex<W>,
+}
+
+impl<W: Write + Send + 'static> JsonMetricsSink<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer: Mutex::new(writer),
+        }
+    }
+
+    fn write(&self, event: MetricsEvent) {
+        let mut writer = self.writer.lock();
+        let res = serde_json::to_writer(&mut *writer, &event)
+            .map_err(std::io::Error::from)
+            .and_then(|_| writeln!(writer))
+            .and_then(|_| writer.flush());
+        if let Err(err) = res {
+            log::warn!("JsonMetricsSink: cannot write metrics: {err}");
+        }
+    }
+}
+
+impl<W: Write + Send + 'static> MetricsSink for JsonMetricsSink<W> {
+    fn compile(&self, metrics: &CompileMetrics) {
+        self.write(MetricsEvent::Compile(metrics));
+    }
+
+    fn evict(&self, metrics: &EvictMetrics) {
+        self.write(MetricsEvent::Evict(metrics));
+    }
+}
+
+/// Aggregates the metrics, which are rendered in the Prometheus text format
+/// by [`PrometheusMetricsSink::render`].
+#[derive(Default)]
+pub struct PrometheusMetricsSink {
+    state: Mutex<PrometheusState>,
+}
+
+/// The sum and the count of the observations of a summary.
+#[derive(Default, Clone, Copy)]
+struct Summary {
+    sum: f64,
+    count: u64,
+}
+
+impl Summary {
+    fn observe(&mut self, ms: f64) {
+        self.sum += ms / 1000.;
+        self.count += 1;
+    }
+}
+
+#[derive(Default)]
+struct PrometheusState {
+    /// Keyed by the project and whether the compilation is cancelled.
+    compiles: BTreeMap<(String, bool), u64>,
+    /// Keyed by the project and the reason.
+    reasons: BTreeMap<(String, &'static str), u64>,
+    queue_wait: BTreeMap<String, Summary>,
+    compile: BTreeMap<String, Summary>,
+    total: BTreeMap<String, Summary>,
+    /// Keyed by the project and the task.
+    exports: BTreeMap<(String, String), Summary>,
+    revision: BTreeMap<String, usize>,
+    dependencies: BTreeMap<String, usize>,
+    evict: Summary,
+}
+
+impl PrometheusMetricsSink {
+    /// Renders the aggregated metrics in the Prometheus text format.
+    pub fn render(&self) -> String {
+        let state = self.state.lock();
+        let mut out = String::new();
+
+        header(
+            &mut out,
+            "compile_total",
+            "counter",
+            "The number of compilations.",
+        );
+        for ((project, cancelled), count) in &state.compiles {
+            let status = if *cancelled { "cancelled" } else { "done" };
+            let labels = labels(&[("project", project.as_str()), ("status", status)]);
+            let _ = writeln!(out, "reflexo_typst_compile_total{labels} {count}");
+        }
+
+        let help = "The number of compilations by the reasons.";
+        header(&mut out, "compile_reason_total", "counter", help);
+        for ((project, reason), count) in &state.reasons {
+            let labels = labels(&[("project", project.as_str()), ("reason", *reason)]);
+            let _ = writeln!(out, "reflexo_typst_compile_reason_total{labels} {count}");
+        }
+
+        let summaries = [
+            (
+                "compile_queue_wait_seconds",
+                "The time waiting to compile.",
+                &state.queue_wait,
+            ),
+            (
+                "compile_seconds",
+                "The time spent by typst on compiling.",
+                &state.compile,
+            ),
+            (
+                "compile_total_seconds",
+                "The total time of compilations.",
+                &state.total,
+            ),
+        ];
+        for (name, help, values) in summaries {
+            header(&mut out, name, "summary", help);
+            for (project, value) in values {
+                summary(
+                    &mut out,
+                    name,
+                    &labels(&[("project", project.as_str())]),
+                    value,
+                );
+            }
+        }
+
+        let help = "The time spent on export tasks.";
+        header(&mut out, "export_seconds", "summary", help);
+        for ((project, task), value) in &state.exports {
+            let labels = labels(&[("project", project.as_str()), ("task", task.as_str())]);
+            summary(&mut out, "export_seconds", &labels, value);
+        }
+
+        let gauges = [
+            (
+                "compile_revision",
+                "The latest compiled revision.",
+                &state.revision,
+            ),
+            (
+                "compile_dependencies",
+                "The number of file dependencies.",
+                &state.dependencies,
+            ),
+        ];
+        for (name, help, values) in gauges {
+            header(&mut out, name, "gauge", help);
+            for (project, value) in values {
+                let labels = labels(&[("project", project.as_str())]);
+                let _ = writeln!(out, "reflexo_typst_{name}{labels} {value}");
+            }
+        }
+
+        let help = "The time spent on evicting the comemo cache.";
+        header(&mut out, "cache_evict_seconds", "summary", help);
+        summary(&mut out, "cache_evict_seconds", "", &state.evict);
+
+        out
+    }
+}
+
+impl MetricsSink for PrometheusMetricsSink {
+    fn compile(&self, metrics: &CompileMetrics) {
+        let mut state = self.state.lock();
+        let project = &metrics.project;
+
+        *state
+            .compiles
+            .entry((project.clone(), metrics.cancelled))
+            .or_default() += 1;
+        let reason = &metrics.reason;
+        let reasons = [
+            ("memory", reason.by_memory_events),
+            ("fs", reason.by_fs_events),
+            ("entry", reason.by_entry_update),
+        ];
+        for (name, _) in reasons.into_iter().filter(|(_, seen)| *seen) {
+            *state.reasons.entry((project.clone(), name)).or_default() += 1;
+        }
+
+        let queue_wait = state.queue_wait.entry(project.clone()).or_default();
+        queue_wait.observe(metrics.queue_wait_ms);
+        state
+            .total
+            .entry(project.clone())
+            .or_default()
+            .observe(metrics.total_ms);
+        if metrics.cancelled {
+            return;
+        }
+
+        state
+            .compile
+            .entry(project.clone())
+            .or_default()
+            .observe(metrics.compile_ms);
+        for export in &metrics.exports {
+            let key = (project.clone(), export.task.clone());
+            state
+                .exports
+                .entry(key)
+                .or_default()
+                .observe(export.duration_ms);
+        }
+        state.revision.insert(project.clone(), metrics.revision);
+        state
+            .dependencies
+            .insert(project.clone(), metrics.dependencies);
+    }
+
+    fn evict(&self, metrics: &EvictMetrics) {
+        self.state.lock().evict.observe(metrics.duration_ms);
+    }
+}
+
+/// Writes the metadata of a metric.
+fn header(out: &mut String, name: &str, ty: &str, help: &str) {
+    let _ = writeln!(out, "# HELP reflexo_typst_{name} {help}");
+    let _ = writeln!(out, "# TYPE reflexo_typst_{name} {ty}");
+}
+
+/// Writes the samples of a summary.
+fn summary(out: &mut String, name: &str, labels: &str, summary: &Summary) {
+    let _ = writeln!(out, "reflexo_typst_{name}_sum{labels} {}", summary.sum);
+    let _ = writeln!(out, "reflexo_typst_{name}_count{labels} {}", summary.count);
+}
+
+/// Formats the labels of a sample, escaping the values.
+fn labels(pairs: &[(&str, &str)]) -> String {
+    let pairs = pairs.iter().map(|(name, value)| {
+        let value = value
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n");
+        format!("{name}=\"{value}\"")
+    });
+    format!("{{{}}}", pairs.collect::<Vec<_>>().join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compile_metrics(project: &str, cancelled: bool) -> CompileMetrics {
+        CompileMetrics {
+            project: project.to_owned(),
+            revision: 3,
+            reason: CompileReasons {
+                by_fs_events: true,
+                ..CompileReasons::default()
+            },
+            cancelled,
+            queue_wait_ms: 500.,
+            compile_ms: 250.,
+            exports: vec![ExportMetrics {
+                task: "pdf".to_owned(),
+                duration_ms: 125.,
+            }],
+            total_ms: 1000.,
+            dependencies: 7,
+        }
+    }
+
+    #[test]
+    fn test_prometheus_render() {
+        let sink = PrometheusMetricsSink::default();
+        sink.compile(&compile_metrics("p", false));
+        sink.compile(&compile_metrics("p", true));
+        sink.evict(&EvictMetrics {
+            revision: 3,
+            duration_ms: 500.,
+        });
+        let out = sink.render();
+        let lines = out.lines().collect::<Vec<_>>();
+
+        let expected = [
+            "# HELP reflexo_typst_compile_total The number of compilations.",
+            "# TYPE reflexo_typst_compile_total counter",
+            r#"reflexo_typst_compile_total{project="p",status="done"} 1"#,
+            r#"reflexo_typst_compile_total{project="p",status="cancelled"} 1"#,
+            r#"reflexo_typst_compile_reason_total{project="p",reason="fs"} 2"#,
+            "# TYPE reflexo_typst_compile_queue_wait_seconds summary",
+            r#"reflexo_typst_compile_queue_wait_seconds_sum{project="p"} 1"#,
+            r#"reflexo_typst_compile_queue_wait_seconds_count{project="p"} 2"#,
+            // The cancelled compilation is only counted by the total time.
+            r#"reflexo_typst_compile_seconds_sum{project="p"} 0.25"#,
+            r#"reflexo_typst_compile_seconds_count{project="p"} 1"#,
+            r#"reflexo_typst_compile_total_seconds_sum{project="p"} 2"#,
+            r#"reflexo_typst_compile_total_seconds_count{project="p"} 2"#,
+            r#"reflexo_typst_export_seconds_sum{project="p",task="pdf"} 0.125"#,
+            r#"reflexo_typst_export_seconds_count{project="p",task="pdf"} 1"#,
+            "# TYPE reflexo_typst_compile_revision gauge",
+            r#"reflexo_typst_compile_revision{project="p"} 3"#,
+            r#"reflexo_typst_compile_dependencies{project="p"} 7"#,
+            "reflexo_typst_cache_evict_seconds_sum 0.5",
+            "reflexo_typst_cache_evict_seconds_count 1",
+        ];
+        for line in expected {
+            assert!(lines.contains(&line), "missing {line:?} in:\n{out}");
+        }
+        assert!(!out.contains("reason=\"memory\""), "{out}");
+    }
+
+    #[test]
+    fn test_prometheus_labels_escaped() {
+        let sink = PrometheusMetricsSink::default();
+        sink.compile(&compile_metrics("a\"b\\c\nd", false));
+        let out = sink.render();
+
+        let expected = r#"reflexo_typst_compile_total{project="a\"b\\c\nd",status="done"} 1"#;
+        assert!(out.lines().any(|line| line == expected), "{out}");
+    }
+}
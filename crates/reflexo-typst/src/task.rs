@@ -4,6 +4,9 @@
 
 pub use tinymist_task::*;
 
+mod cancel;
+pub use cancel::*;
+
 #[cfg(feature = "system-watch")]
 mod cache;
 #[cfg(feature = "system-watch")]
@@ -32,6 +32,11 @@ impl CacheTask {
     }
 
     pub fn evict(&self) {
+        self.evict_with(|_| {});
+    }
+
+    /// Evicts the cache and reports the time spent on the eviction.
+    pub fn evict_with(&self, report: impl FnOnce(std::time::Duration) + Send + Sync + 'static) {
         let revision = self
             .revision
             .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
@@ -44,6 +49,7 @@ impl CacheTask {
                     comemo::evict(task.max_age);
                     let elapsed = evict_start.elapsed();
                     log::debug!("CacheEvictTask: evict cache in {elapsed:?}");
+                    report(elapsed);
                 })
                 .await;
 
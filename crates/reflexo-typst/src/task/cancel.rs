@@ -0,0 +1,54 @@
+//! The token that cancels superseded compilations.
+
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use reflexo::error::prelude::*;
+use tinymist_world::{CompilerFeat, ConfigTask, WorldComputeGraph};
+
+/// A token to cooperatively cancel a compilation, which is signaled when a
+/// newer revision supersedes the compiling one.
+///
+/// The token is provided to the compute graph as a [`ConfigTask`], so that
+/// the computations and exporters can check it between expensive steps.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    /// Signals the compilation to abort.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    /// Whether the compilation is cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    /// Returns an error if the compilation is cancelled.
+    pub fn check(&self) -> Result<()> {
+        if self.is_cancelled() {
+            return Err(error_once!("compilation cancelled"));
+        }
+
+        Ok(())
+    }
+
+    /// Gets the token of the compute graph. A graph without a token, e.g. one
+    /// not created by the compile actor, is never cancelled.
+    pub fn of<F: CompilerFeat>(graph: &WorldComputeGraph<F>) -> Self {
+        let token = graph
+            .get::<ConfigTask<CancelToken>>()
+            .and_then(|token| token.ok());
+        token
+            .map(|token| token.as_ref().clone())
+            .unwrap_or_default()
+    }
+
+    /// Provides the token to the compute graph.
+    pub fn provide<F: CompilerFeat>(&self, graph: &WorldComputeGraph<F>) {
+        let _ = graph.provide::<ConfigTask<CancelToken>>(Ok(Arc::new(self.clone())));
+    }
+}
@@ -10,6 +10,8 @@ pub mod vector {
     #[cfg(feature = "rkyv")]
     pub mod stream;
     pub mod vm;
+    #[cfg(feature = "rkyv")]
+    pub mod wire;
 
     pub use ir::geom;
 
@@ -62,15 +64,23 @@ pub mod vector {
         const _: () = assert!(core::mem::align_of::<ArchivedDefId>() == 8);
         const _: () = assert!(core::mem::size_of::<ArchivedAbsoluteRef>() == 24);
         const _: () = assert!(core::mem::align_of::<ArchivedAbsoluteRef>() == 8);
-        const _: () = assert!(core::mem::size_of::<ArchivedLinkItem>() == 16);
+        const _: () = assert!(core::mem::size_of::<ArchivedLinkItem>() == 24);
         const _: () = assert!(core::mem::align_of::<ArchivedLinkItem>() == 4);
+        const _: () = assert!(core::mem::size_of::<ArchivedLinkTarget>() == 32);
+        const _: () = assert!(core::mem::align_of::<ArchivedLinkTarget>() == 4);
+        const _: () = assert!(core::mem::size_of::<ArchivedOutline>() == 8);
+        const _: () = assert!(core::mem::align_of::<ArchivedOutline>() == 4);
+        const _: () = assert!(core::mem::size_of::<ArchivedOutlineItem>() == 32);
+        const _: () = assert!(core::mem::align_of::<ArchivedOutlineItem>() == 8);
         const _: () = assert!(core::mem::size_of::<ArchivedPathItem>() == 28);
         const _: () = assert!(core::mem::align_of::<ArchivedPathItem>() == 4);
         const _: () = assert!(core::mem::size_of::<ArchivedTransformItem>() == 8);
         const _: () = assert!(core::mem::align_of::<ArchivedTransformItem>() == 4);
         const _: () = assert!(core::mem::size_of::<ArchivedIncrGlyphPack>() == 12);
         const _: () = assert!(core::mem::align_of::<ArchivedIncrGlyphPack>() == 4);
-        const _: () = assert!(core::mem::size_of::<ArchivedPage>() == 24);
+        const _: () = assert!(core::mem::size_of::<ArchivedIncrLifetimePack>() == 16);
+        const _: () = assert!(core::mem::align_of::<ArchivedIncrLifetimePack>() == 8);
+        const _: () = assert!(core::mem::size_of::<ArchivedPage>() == 40);
         const _: () = assert!(core::mem::align_of::<ArchivedPage>() == 8);
         const _: () = assert!(core::mem::size_of::<ArchivedBuildInfo>() == 16);
         const _: () = assert!(core::mem::align_of::<ArchivedBuildInfo>() == 4);
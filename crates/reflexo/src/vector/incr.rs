@@ -1,7 +1,13 @@
 use super::ir::{
-    FlatGlyphItem, FlatModule, GlyphRef, LayoutRegionNode, LayoutSourceMapping, Module,
-    ModuleMetadata, MultiVecDocument, Page, SourceMappingNode,
+    FlatGlyphItem, FlatModule, GlyphRef, LayoutMappingSelector, LayoutNestSelector, LayoutRegion,
+    LayoutRegionNode, LayoutSourceMapping, Module, ModuleMetadata, MultiVecDocument, Page, Scalar,
+    SourceMappingNode,
 };
+#[cfg(feature = "rkyv-validation")]
+use super::stream::BytesModuleStream;
+#[cfg(feature = "rkyv-validation")]
+use super::wire::{self, ResyncReason, WireError};
+use super::wire::{FrameHeader, FrameKind};
 use crate::{error::prelude::*, TakeAs};
 
 /// maintains the data of the incremental rendering at client side
@@ -18,9 +24,73 @@ pub struct IncrDocClient {
     pub source_mapping_data: Vec<SourceMappingNode>,
     /// Optional page source mapping references.
     pub page_source_mapping: LayoutSourceMapping,
+    /// The revision of the document merged from frames.
+    pub revision: u64,
+    /// Selects among the nested layouts of the document, e.g. by `x-target`.
+    /// It is kept when the document is reset.
+    pub layout_selector: LayoutMappingSelector,
 }
 
 impl IncrDocClient {
+    /// Merge a frame from server, see [`crate::vector::wire`] for the format.
+    ///
+    /// On [`WireError::NeedsFullSnapshot`], the client is left unchanged and
+    /// should send [`Self::resync_request`] to the server.
+    ///
+    /// The frame is validated, so it requires the `rkyv-validation` feature.
+    #[cfg(feature = "rkyv-validation")]
+    pub fn merge_frame(&mut self, data: &[u8]) -> Result<FrameKind, WireError> {
+        let (header, payload) = FrameHeader::decode(data)?;
+        match header.kind {
+            FrameKind::Full => {}
+            FrameKind::Delta if header.base == self.revision => {}
+            FrameKind::Delta => {
+                return Err(WireError::NeedsFullSnapshot(ResyncReason::Revision {
+                    expected: self.revision,
+                    actual: header.base,
+                }))
+            }
+            FrameKind::Resync => return Err(WireError::Malformed("unexpected resync request")),
+        }
+
+        let delta = BytesModuleStream::from_slice(payload)
+            .try_checkout_owned()
+            .map_err(|_| WireError::Malformed("invalid module data"))?;
+        for metadata in &delta.metadata {
+            let ModuleMetadata::BuildVersion(info) = metadata else {
+                continue;
+            };
+            let expected = wire::build_info();
+            if **info != expected {
+                let reason = ResyncReason::BuildVersion {
+                    expected: expected.version.to_string(),
+                    actual: info.version.to_string(),
+                };
+                return Err(match header.kind {
+                    FrameKind::Full => WireError::Incompatible(reason),
+                    _ => WireError::NeedsFullSnapshot(reason),
+                });
+            }
+        }
+
+        if header.kind == FrameKind::Full {
+            self.reset();
+        }
+        self.merge_delta(delta);
+        self.revision = header.revision;
+        Ok(header.kind)
+    }
+
+    /// Creates a frame requesting a full frame from server.
+    pub fn resync_request(&self) -> Vec<u8> {
+        let header = FrameHeader {
+            kind: FrameKind::Resync,
+            base: self.revision,
+            revision: self.revision,
+        };
+        header.encode(&[])
+    }
+
     /// Merge the delta from server.
     pub fn merge_delta(&mut self, delta: FlatModule) {
         self.doc.merge_delta(&delta);
@@ -40,6 +110,40 @@ impl IncrDocClient {
         }
     }
 
+    /// Resets the document, keeping the layout selector.
+    pub fn reset(&mut self) {
+        *self = Self {
+            layout_selector: std::mem::take(&mut self.layout_selector),
+            ..Self::default()
+        };
+    }
+
+    /// Selects the layout region of the given kind, e.g. `width`, walking down
+    /// the nested layouts by [`Self::layout_selector`].
+    pub fn select_region(&self, kind: &str) -> Result<&LayoutRegion> {
+        self.nest_selector().select_region(kind)
+    }
+
+    /// Selects the layouts by width, walking down the nested layouts by
+    /// [`Self::layout_selector`].
+    pub fn width_layouts(&self) -> Option<&[(Scalar, LayoutRegionNode)]> {
+        self.select_region("width").ok()?.by_scalar()
+    }
+
+    /// Selects the layout by [`Self::layout_selector`].
+    pub fn select_layout(&self) -> Result<LayoutRegionNode> {
+        let root = self.doc.layouts.first();
+        let root = root.ok_or_else(|| error_once!("IncrDocClient: no layout"))?;
+        root.by_selector(&self.nest_selector())
+    }
+
+    fn nest_selector(&self) -> LayoutNestSelector<'_, &LayoutMappingSelector> {
+        LayoutNestSelector {
+            layouts: &self.doc.layouts,
+            inner: &self.layout_selector,
+        }
+    }
+
     /// Set the current layout of the document.
     /// This is so bare-bone that stupidly takes a selected layout.
     ///
@@ -81,14 +81,21 @@ pub struct VecDocument {
     /// References to the page frames.
     /// Use [`Module::get_item`] to get the actual item.
     pub pages: Vec<Page>,
+    /// The outline of the document.
+    pub outline: Option<Arc<Outline>>,
 }
 
 impl VecDocument {
     pub fn to_multi(self) -> MultiVecDocument {
-        let Self { pages, module } = self;
+        let Self {
+            pages,
+            module,
+            outline,
+        } = self;
 
         MultiVecDocument {
             module,
+            outline,
             layouts: vec![LayoutRegion::ByScalar(LayoutRegionRepr {
                 kind: "width".into(),
                 layouts: vec![(
@@ -115,6 +122,9 @@ pub struct MultiVecDocument {
     /// References to the page frames.
     /// Use [`Module::get_item`] to get the actual item.
     pub layouts: Vec<LayoutRegion>,
+    /// The outline of the document shared by all layouts. Layouts having their
+    /// own outlines carry them, see [`LayoutRegionNode::outline`].
+    pub outline: Option<Arc<Outline>>,
 }
 
 impl Default for MultiVecDocument {
@@ -123,6 +133,7 @@ impl Default for MultiVecDocument {
         Self {
             module: Default::default(),
             layouts: vec![LayoutRegion::new_single(pages)],
+            outline: None,
         }
     }
 }
@@ -130,6 +141,9 @@ impl Default for MultiVecDocument {
 impl MultiVecDocument {
     pub fn merge_delta(&mut self, v: impl ModuleStream) {
         self.layouts = v.layouts().take();
+        if let Some(outline) = v.outline() {
+            self.outline = Some(outline);
+        }
         self.module.merge_delta(v);
     }
 }
@@ -145,9 +159,12 @@ impl MultiVecDocument {
     }
 
     pub fn to_bytes(self) -> Vec<u8> {
-        let mut m = FlatModule::with_capacity(4);
+        let mut m = FlatModule::with_capacity(5);
         m.add_module(self.module);
         m.push(ModuleMetadata::Layout(Arc::new(self.layouts)));
+        if let Some(outline) = self.outline {
+            m.push(ModuleMetadata::Outline(outline));
+        }
         m.to_bytes()
     }
 }
@@ -11,17 +11,24 @@ pub struct Page {
     pub content: Fingerprint,
     /// Page size for cropping content
     pub size: Size,
+    /// The logical page number displayed to readers, e.g. `iii` or `A-1`.
+    /// It is `None` if the page is not numbered or numbered by a function.
+    pub label: Option<ImmutStr>,
 }
 
 impl fmt::Debug for Page {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "Page({}, {:.3}x{:.3})",
+            "Page({}, {:.3}x{:.3}",
             self.content.as_svg_id(""),
             self.size.x.0,
             self.size.y.0
-        )
+        )?;
+        if let Some(label) = &self.label {
+            write!(f, ", {label:?}")?;
+        }
+        write!(f, ")")
     }
 }
 
@@ -58,6 +65,17 @@ pub type GlyphPack = Vec<(GlyphRef, FlatGlyphItem)>;
 #[cfg_attr(feature = "rkyv-validation", archive(check_bytes))]
 pub struct ItemPack(pub Vec<(Fingerprint, VecItem)>);
 
+/// Lifetimes of items maintained by an incremental server.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "rkyv", derive(Archive, rDeser, rSer))]
+#[cfg_attr(feature = "rkyv-validation", archive(check_bytes))]
+pub struct IncrLifetimePack {
+    /// The lifetime of the server when the pack is created.
+    pub lifetime: u64,
+    /// The last lifetime at which each item is used.
+    pub items: Vec<(Fingerprint, u64)>,
+}
+
 /// Flatten mapping fingerprints to glyph items.
 #[derive(Debug, Clone, Default)]
 #[cfg_attr(feature = "rkyv", derive(Archive, rDeser, rSer))]
@@ -10,7 +10,7 @@ use std::{
 use rkyv::{Archive, Deserialize as rDeser, Serialize as rSer};
 use serde::{Deserialize, Serialize};
 
-use super::{Module, ModuleView, Page, PageMetadata, Scalar, SourceMappingNode};
+use super::{Module, ModuleView, Outline, Page, PageMetadata, Scalar, SourceMappingNode};
 use crate::{error::prelude::*, ImmutBytes, ImmutStr, TakeAs};
 
 /// Describing
@@ -32,6 +32,12 @@ impl LayoutRegionNode {
         Self::Pages(Arc::new((Default::default(), pages)))
     }
 
+    /// Creates a pages layout carrying the outline of the document in the
+    /// layout.
+    pub fn new_pages_with_outline(pages: Vec<Page>, outline: Arc<Outline>) -> Self {
+        Self::Pages(Arc::new((vec![PageMetadata::Outline(outline)], pages)))
+    }
+
     pub fn new_source_mapping(source_mapping: Vec<SourceMappingNode>) -> Self {
         Self::SourceMapping(Arc::new((Default::default(), source_mapping)))
     }
@@ -94,6 +100,18 @@ impl LayoutRegionNode {
         }
     }
 
+    /// Gets the outline of the document in the layout, if any.
+    pub fn outline(&self) -> Option<&Arc<Outline>> {
+        let Self::Pages(v) = self else {
+            return None;
+        };
+
+        v.0.iter().find_map(|meta| match meta {
+            PageMetadata::Outline(outline) => Some(outline),
+            _ => None,
+        })
+    }
+
     pub fn customs(v: &[PageMetadata]) -> impl Iterator<Item = &'_ (ImmutStr, ImmutBytes)> {
         v.iter()
             .flat_map(move |meta| match meta {
@@ -166,6 +184,28 @@ pub trait LayoutSelector {
     }
 }
 
+impl<T: LayoutSelector + ?Sized> LayoutSelector for &T {
+    fn select_by_scalar(
+        &self,
+        kind: &str,
+        layouts: &[(Scalar, LayoutRegionNode)],
+    ) -> Result<LayoutRegionNode> {
+        (**self).select_by_scalar(kind, layouts)
+    }
+
+    fn select_by_str(
+        &self,
+        kind: &str,
+        layouts: &[(ImmutStr, LayoutRegionNode)],
+    ) -> Result<LayoutRegionNode> {
+        (**self).select_by_str(kind, layouts)
+    }
+
+    fn resolve_indirect(&self, ind: usize) -> Result<&LayoutRegion> {
+        (**self).resolve_indirect(ind)
+    }
+}
+
 /// Describing
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "rkyv", derive(Archive, rDeser, rSer))]
@@ -196,6 +236,17 @@ impl LayoutRegion {
         Self::ByScalar(LayoutRegionRepr { kind, layouts })
     }
 
+    pub fn new_by_str(kind: ImmutStr, layouts: Vec<(ImmutStr, LayoutRegionNode)>) -> Self {
+        Self::ByStr(LayoutRegionRepr { kind, layouts })
+    }
+
+    pub fn kind(&self) -> &str {
+        match self {
+            Self::ByScalar(v) => &v.kind,
+            Self::ByStr(v) => &v.kind,
+        }
+    }
+
     pub fn is_empty(&self) -> bool {
         match self {
             Self::ByScalar(v) => v.layouts.is_empty(),
@@ -446,6 +497,33 @@ pub struct LayoutNestSelector<'l, T> {
     pub inner: T,
 }
 
+impl<'l, T: LayoutSelector> LayoutNestSelector<'l, T> {
+    /// Walks down the nested regions from the first region, until reaching a
+    /// region of the given kind.
+    pub fn select_region(&self, kind: &str) -> Result<&'l LayoutRegion> {
+        let mut region = self.layouts.first().ok_or_else(
+            || error_once!("LayoutNestSelector: no layout found by kind", kind: kind.to_owned(), is_not_found: true),
+        )?;
+        while region.kind() != kind {
+            let next = match region {
+                LayoutRegion::ByScalar(v) => self.inner.select_by_scalar(&v.kind, &v.layouts),
+                LayoutRegion::ByStr(v) => self.inner.select_by_str(&v.kind, &v.layouts),
+            }?;
+
+            let LayoutRegionNode::Indirect(ind) = next else {
+                return Err(
+                    error_once!("LayoutNestSelector: no layout found by kind", kind: kind.to_owned(), is_not_found: true),
+                );
+            };
+            region = self.layouts.get(ind).ok_or_else(
+                || error_once!("LayoutNestSelector: indirect layout not found", ind: ind),
+            )?;
+        }
+
+        Ok(region)
+    }
+}
+
 impl<T: LayoutSelector> LayoutSelector for LayoutNestSelector<'_, T> {
     fn select_by_scalar(
         &self,
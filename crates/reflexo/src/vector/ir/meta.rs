@@ -9,6 +9,51 @@ pub struct LinkItem {
     pub href: ImmutStr,
     /// The box size of the link item.
     pub size: Size,
+    /// The target of an intra-document link, if the link points to a
+    /// location in the document.
+    pub target: Option<Arc<LinkTarget>>,
+}
+
+/// The target location of an intra-document link.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "rkyv", derive(Archive, rDeser, rSer))]
+#[cfg_attr(feature = "rkyv-validation", archive(check_bytes))]
+pub struct LinkTarget {
+    /// The one-based page number of the target.
+    pub page: u32,
+    /// The position of the target on the page.
+    pub point: Point,
+    /// A stable anchor of the target, which doesn't change when the target
+    /// moves.
+    pub anchor: ImmutStr,
+    /// The label of the target element, if any.
+    pub label: Option<ImmutStr>,
+}
+
+/// The outline of a document, which lists the headings in document order.
+#[derive(Debug, Default, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "rkyv", derive(Archive, rDeser, rSer))]
+#[cfg_attr(feature = "rkyv-validation", archive(check_bytes))]
+pub struct Outline {
+    pub items: Vec<OutlineItem>,
+}
+
+/// An entry in the [`Outline`]. The tree of entries is restored by their
+/// levels.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "rkyv", derive(Archive, rDeser, rSer))]
+#[cfg_attr(feature = "rkyv-validation", archive(check_bytes))]
+pub struct OutlineItem {
+    /// The plain text of the heading.
+    pub title: ImmutStr,
+    /// The one-based level of the heading.
+    pub level: u32,
+    /// The one-based page number of the heading.
+    pub page: u32,
+    /// The vertical offset of the heading on the page.
+    pub offset: Scalar,
+    /// The span of the heading.
+    pub span: SpanId,
 }
 
 /// Source mapping from vec item to source span.
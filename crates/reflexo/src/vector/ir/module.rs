@@ -46,6 +46,10 @@ pub trait ModuleStream {
         // never gc items
         None
     }
+    fn outline(&self) -> Option<Arc<Outline>> {
+        // keep the outline
+        None
+    }
 }
 
 /// A finished module that stores all the vector items.
@@ -212,6 +216,8 @@ pub enum PageMetadata {
     Item(ItemPack),
     Glyph(Arc<IncrGlyphPack>),
     Custom(Vec<(ImmutStr, ImmutBytes)>),
+    /// The outline of the document in the layout.
+    Outline(Arc<Outline>),
 }
 
 impl fmt::Debug for PageMetadata {
@@ -227,6 +233,10 @@ impl fmt::Debug for PageMetadata {
                 .field("len", &v.items.len())
                 .field("base", &v.incremental_base)
                 .finish(),
+            PageMetadata::Outline(v) => f
+                .debug_struct("Outline")
+                .field("len", &v.items.len())
+                .finish(),
             PageMetadata::Custom(v) => {
                 write!(f, "Custom")?;
                 f.debug_map()
@@ -262,6 +272,8 @@ pub enum ModuleMetadata {
     Font(Arc<IncrFontPack>),
     Glyph(Arc<IncrGlyphPack>),
     Layout(Arc<Vec<LayoutRegion>>),
+    Lifetime(Arc<IncrLifetimePack>),
+    Outline(Arc<Outline>),
 }
 
 const _: () = assert!(core::mem::size_of::<ModuleMetadata>() == 32);
@@ -277,6 +289,8 @@ enum MetaIndices {
     Font,
     Glyph,
     Layout,
+    Lifetime,
+    Outline,
     Max,
 }
 
@@ -467,4 +481,13 @@ impl ModuleStream for &FlatModule {
         }
         None
     }
+
+    fn outline(&self) -> Option<Arc<Outline>> {
+        for m in &self.metadata {
+            if let ModuleMetadata::Outline(v) = m {
+                return Some(v.clone());
+            }
+        }
+        None
+    }
 }
@@ -1,4 +1,6 @@
 use super::ir::{ArchivedFlatModule, FlatModule};
+#[cfg(feature = "rkyv-validation")]
+use crate::error::prelude::*;
 use rkyv::de::deserializers::SharedDeserializeMap;
 use rkyv::{AlignedVec, Deserialize};
 
@@ -66,4 +68,15 @@ impl<'a> BytesModuleStream<'a> {
         let mut dmap = SharedDeserializeMap::default();
         v.deserialize(&mut dmap).unwrap()
     }
+
+    /// Same as [`Self::checkout_owned`], but returns an error instead of
+    /// panicking if the data is not a valid module.
+    #[cfg(feature = "rkyv-validation")]
+    pub fn try_checkout_owned(&self) -> Result<FlatModule> {
+        let v = rkyv::check_archived_root::<FlatModule>(self.data.as_ref())
+            .map_err(|e| error_once!("invalid module data", err: e.to_string()))?;
+        let mut dmap = SharedDeserializeMap::default();
+        v.deserialize(&mut dmap)
+            .map_err(|e| error_once!("cannot deserialize module", err: e.to_string()))
+    }
 }
@@ -0,0 +1,273 @@
+//! The wire protocol of incremental vector data.
+//!
+//! Each message is a frame starting with a fixed 32-byte header, followed by
+//! an rkyv-archived [`FlatModule`] if the frame carries a module:
+//!
+//! | offset | size | field                                 |
+//! |--------|------|---------------------------------------|
+//! | 0      | 4    | magic, `b"tsvd"`                      |
+//! | 4      | 2    | protocol version, [`WIRE_VERSION`]    |
+//! | 6      | 1    | frame kind, see [`FrameKind`]         |
+//! | 7      | 1    | reserved, must be zero                |
+//! | 8      | 8    | schema hash, [`SCHEMA_HASH`]          |
+//! | 16     | 8    | base revision                         |
+//! | 24     | 8    | revision                              |
+//!
+//! All integers are little-endian.
+//!
+//! A [`FrameKind::Full`] frame replaces the entire state of the client. A
+//! [`FrameKind::Delta`] frame can only be applied to a client at its base
+//! revision. Whenever a client cannot apply a frame, it sends a
+//! [`FrameKind::Resync`] frame to the server, which replies with a full frame.
+
+use std::fmt;
+
+use super::ir::*;
+
+/// The magic header of frames.
+pub const WIRE_MAGIC: [u8; 4] = *b"tsvd";
+/// The version of the wire protocol.
+pub const WIRE_VERSION: u16 = 1;
+/// The size of the frame header.
+pub const FRAME_HEADER_SIZE: usize = 32;
+
+/// Hashes the names and layouts of the given types in FNV-1a.
+macro_rules! schema_hash {
+    ($($ty:ty),* $(,)?) => {{
+        let mut hash = fnv1a(0xcbf2_9ce4_8422_2325, env!("CARGO_PKG_VERSION").as_bytes());
+        $(
+            hash = fnv1a(hash, stringify!($ty).as_bytes());
+            hash = fnv1a(hash, &(core::mem::size_of::<$ty>() as u64).to_le_bytes());
+            hash = fnv1a(hash, &(core::mem::align_of::<$ty>() as u64).to_le_bytes());
+        )*
+        hash
+    }};
+}
+
+/// The hash of the archived layout of the vector IR. Peers with different
+/// schema hashes cannot read the modules from each other.
+pub const SCHEMA_HASH: u64 = schema_hash!(
+    ArchivedFlatModule,
+    ArchivedModuleMetadata,
+    ArchivedBuildInfo,
+    ArchivedItemPack,
+    ArchivedVecItem,
+    ArchivedTransformedRef,
+    ArchivedGroupRef,
+    ArchivedPage,
+    ArchivedPageMetadata,
+    ArchivedIncrGlyphPack,
+    ArchivedIncrLifetimePack,
+    ArchivedFlatGlyphItem,
+    ArchivedFontItem,
+    ArchivedTextItem,
+    ArchivedPathItem,
+    ArchivedImageItem,
+    ArchivedLinkItem,
+    ArchivedLinkTarget,
+    ArchivedOutline,
+    ArchivedOutlineItem,
+    ArchivedLayoutRegion,
+    ArchivedLayoutRegionNode,
+    ArchivedLayoutSourceMapping,
+    ArchivedSourceMappingNode,
+);
+
+const fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    let mut i = 0;
+    while i < bytes.len() {
+        hash ^= bytes[i] as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+        i += 1;
+    }
+    hash
+}
+
+/// The build information of the vector IR, which is attached to full frames
+/// and snapshots.
+pub fn build_info() -> BuildInfo {
+    BuildInfo {
+        version: env!("CARGO_PKG_VERSION").into(),
+        compiler: "reflexo".into(),
+    }
+}
+
+/// The kind of a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum FrameKind {
+    /// The frame carries the entire module.
+    Full = 0,
+    /// The frame carries the difference from the base revision.
+    Delta = 1,
+    /// The frame requests a full frame from the server.
+    Resync = 2,
+}
+
+/// The decoded header of a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameHeader {
+    pub kind: FrameKind,
+    /// The revision that a delta frame applies to.
+    pub base: u64,
+    /// The revision of the state after applying the frame.
+    pub revision: u64,
+}
+
+impl FrameHeader {
+    /// Encodes the header and payload into a frame.
+    pub fn encode(&self, payload: &[u8]) -> Vec<u8> {
+        let mut frame = Vec::with_capacity(FRAME_HEADER_SIZE + payload.len());
+        frame.extend_from_slice(&WIRE_MAGIC);
+        frame.extend_from_slice(&WIRE_VERSION.to_le_bytes());
+        frame.push(self.kind as u8);
+        frame.push(0);
+        frame.extend_from_slice(&SCHEMA_HASH.to_le_bytes());
+        frame.extend_from_slice(&self.base.to_le_bytes());
+        frame.extend_from_slice(&self.revision.to_le_bytes());
+        frame.extend_from_slice(payload);
+        frame
+    }
+
+    /// Decodes a frame into its header and payload.
+    pub fn decode(data: &[u8]) -> Result<(Self, &[u8]), WireError> {
+        if !is_frame(data) {
+            return Err(WireError::Malformed("bad magic header"));
+        }
+        if data.len() < FRAME_HEADER_SIZE {
+            return Err(WireError::Malformed("truncated frame header"));
+        }
+
+        let u64_at = |pos: usize| u64::from_le_bytes(data[pos..pos + 8].try_into().unwrap());
+        let version = u16::from_le_bytes([data[4], data[5]]);
+        let kind = match data[6] {
+            0 => FrameKind::Full,
+            1 => FrameKind::Delta,
+            2 => FrameKind::Resync,
+            _ => return Err(WireError::Malformed("unknown frame kind")),
+        };
+        let schema_hash = u64_at(8);
+
+        let mismatch = if version != WIRE_VERSION {
+            Some(ResyncReason::ProtocolVersion {
+                expected: WIRE_VERSION,
+                actual: version,
+            })
+        } else if schema_hash != SCHEMA_HASH {
+            Some(ResyncReason::SchemaHash {
+                expected: SCHEMA_HASH,
+                actual: schema_hash,
+            })
+        } else {
+            None
+        };
+        if let Some(reason) = mismatch {
+            // A full frame from the same peer would not be readable either.
+            return Err(match kind {
+                FrameKind::Full => WireError::Incompatible(reason),
+                _ => WireError::NeedsFullSnapshot(reason),
+            });
+        }
+
+        let header = Self {
+            kind,
+            base: u64_at(16),
+            revision: u64_at(24),
+        };
+        Ok((header, &data[FRAME_HEADER_SIZE..]))
+    }
+}
+
+/// Checks whether the data starts with the magic header of frames.
+pub fn is_frame(data: &[u8]) -> bool {
+    data.starts_with(&WIRE_MAGIC)
+}
+
+/// The reason why a frame cannot be applied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResyncReason {
+    ProtocolVersion { expected: u16, actual: u16 },
+    SchemaHash { expected: u64, actual: u64 },
+    BuildVersion { expected: String, actual: String },
+    Revision { expected: u64, actual: u64 },
+}
+
+impl fmt::Display for ResyncReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ProtocolVersion { expected, actual } => {
+                write!(f, "protocol version {actual} (expected {expected})")
+            }
+            Self::SchemaHash { expected, actual } => {
+                write!(f, "schema hash {actual:016x} (expected {expected:016x})")
+            }
+            Self::BuildVersion { expected, actual } => {
+                write!(f, "build version {actual} (expected {expected})")
+            }
+            Self::Revision { expected, actual } => {
+                write!(f, "base revision {actual} (expected {expected})")
+            }
+        }
+    }
+}
+
+/// The error when a frame cannot be applied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WireError {
+    /// The frame cannot be applied to the current state. The client should
+    /// send a resync request and wait for a full frame.
+    NeedsFullSnapshot(ResyncReason),
+    /// The full frame is produced by an incompatible peer.
+    Incompatible(ResyncReason),
+    /// The data is not a valid frame.
+    Malformed(&'static str),
+}
+
+impl fmt::Display for WireError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NeedsFullSnapshot(reason) => write!(f, "needs full snapshot: {reason}"),
+            Self::Incompatible(reason) => write!(f, "incompatible frame: {reason}"),
+            Self::Malformed(reason) => write!(f, "malformed frame: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for WireError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_roundtrip() {
+        let header = FrameHeader {
+            kind: FrameKind::Delta,
+            base: 2,
+            revision: 4,
+        };
+        let frame = header.encode(b"payload");
+        assert!(is_frame(&frame));
+        assert_eq!(FrameHeader::decode(&frame), Ok((header, &b"payload"[..])));
+    }
+
+    #[test]
+    fn test_frame_mismatch() {
+        let header = FrameHeader {
+            kind: FrameKind::Delta,
+            base: 0,
+            revision: 2,
+        };
+        let mut frame = header.encode(&[]);
+        frame[4] = 0;
+        let reason = ResyncReason::ProtocolVersion {
+            expected: WIRE_VERSION,
+            actual: 0,
+        };
+        let err = FrameHeader::decode(&frame).unwrap_err();
+        assert_eq!(err, WireError::NeedsFullSnapshot(reason));
+
+        let err = FrameHeader::decode(b"diff-v1,").unwrap_err();
+        assert_eq!(err, WireError::Malformed("bad magic header"));
+    }
+}
@@ -78,6 +78,8 @@ typst-ts-cli compile ... --format nothing
 typst-ts-cli compile ... --format vector
 # into multiple formats at the same time
 typst-ts-cli compile ... --format svg --format svg_html
+# into text runs with positions, grouped into lines and blocks
+typst-ts-cli compile ... --format text-json
 ```
 
 === `--dynamic-layout` option
@@ -137,6 +139,16 @@ typst-ts-cli compile \
   --format svg_html
 ```
 
+=== Example: compile a document into PNG images
+
+Each page is rendered from the #term.vector-format, the same as the canvas renderer does in browsers.
+
+```bash
+typst-ts-cli compile \
+  -e "fuzzers/corpora/math/main.typ"
+  --format png --ppi 288 --pages 1-3,7 --page-template "math-{n}-of-{t}"
+```
+
 === Example: compile a document into the #term.vector-format
 
 ```bash
@@ -153,6 +165,31 @@ typst-ts-cli compile \
   --dynamic-layout
 ```
 
+== The query command
+
+=== Example: query metadata of a html document in yaml
+
+The `--target` option selects the document to query against, which is either `paged` (default) or `html`. The `--output-format` option accepts `json` (default), `yaml`, `toml` and `json-lines`, and `--pretty=false` disables pretty-printing. It is not named `--format`, since the query command accepts the options of the compile command, whose `--format` selects the export formats. With `--watch`, the query is run again on each change of the document.
+
+```bash
+typst-ts-cli query \
+  -e "main.typ" \
+  --target html \
+  --selector "<meta>" \
+  --field value \
+  --output-format yaml
+```
+
+== Font commands
+
+=== Example: measure fonts into a font manifest
+
+The manifest records the font info (family, variant and coverage), units per em, collection index, hash and path of each discovered font face, which helps browser clients select fonts to download.
+
+```bash
+typst-ts-cli font measure --font-path assets/fonts --output fonts.json
+```
+
 // == Package commands
 
 // === Example: list packages in `@preview` namespace
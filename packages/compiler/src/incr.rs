@@ -15,6 +15,8 @@ impl Default for IncrServer {
             inner: IncrDocServer::default(),
         };
         this.inner.set_should_attach_debug_info(true);
+        // Required by `current` and `resync`.
+        this.inner.set_retain_items(true);
         this
     }
 }
@@ -38,7 +40,12 @@ impl IncrServer {
         self.inner.pack_current()
     }
 
+    pub fn resync(&mut self, request: &[u8]) -> Result<Option<Vec<u8>>, JsValue> {
+        Ok(self.inner.handle_resync(request)?)
+    }
+
     pub fn reset(&mut self) {
         self.inner = IncrDocServer::default();
+        self.inner.set_retain_items(true);
     }
 }
@@ -362,17 +362,30 @@ impl TypstCompiler {
         inputs: Option<Vec<js_sys::Array>>,
         selector: String,
         field: Option<String>,
+        target: Option<String>,
     ) -> Result<String, JsValue> {
         self.set_compiler_options(main_file_path, inputs)?;
 
         let graph = self.verse.computation();
 
         // todo: diagnostics
-        let doc = graph.compile().output.map_err(|e| format!("{e:?}"))?;
-        // todo: query html?
-        let elements: Vec<typst::foundations::Content> = graph
-            .query(selector, &TypstDocument::Paged(doc))
-            .map_err(|e| format!("{e:?}"))?;
+        let doc = match target.as_deref().unwrap_or("paged") {
+            "paged" => {
+                let doc = graph.compile().output.map_err(|e| format!("{e:?}"))?;
+                TypstDocument::Paged(doc)
+            }
+            "html" => {
+                let _ = graph.provide::<FlagTask<HtmlCompilationTask>>(Ok(FlagTask::flag(true)));
+                let doc = graph
+                    .compute::<OptionDocumentTask<TypstHtmlDocument>>()
+                    .map_err(|e| format!("{e:?}"))?;
+                let doc = doc.as_ref().clone();
+                TypstDocument::Html(doc.ok_or_else(|| error_once!("no html document found"))?)
+            }
+            target => return Err(error_once!("Unsupported query target", target: target).into()),
+        };
+        let elements: Vec<typst::foundations::Content> =
+            graph.query(selector, &doc).map_err(|e| format!("{e:?}"))?;
 
         let mapped: Vec<_> = elements
             .into_iter()
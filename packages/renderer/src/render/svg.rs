@@ -1,6 +1,6 @@
 use js_sys::Uint8Array;
 use reflexo_typst::error::prelude::*;
-use reflexo_typst::svg::{DefaultExportFeature, SvgDataSelection, SvgExporter};
+use reflexo_typst::svg::{DefaultExportFeature, SvgDataSelection, SvgExportOptions, SvgExporter};
 use reflexo_typst2vec::geom::{Axes, Scalar};
 use wasm_bindgen::prelude::*;
 
@@ -28,6 +28,22 @@ impl RenderSession {
             },
         )
     }
+
+    /// Sets the runtime options of svg rendering, which take effect on the
+    /// pages rendered afterwards.
+    pub fn set_svg_options(&mut self, options: JsValue) -> Result<()> {
+        let options = parse_svg_options(options)?;
+        self.svg_kern.lock().unwrap().set_options(options);
+        Ok(())
+    }
+}
+
+/// Parses the runtime options of svg rendering, where `undefined` means the
+/// default options.
+fn parse_svg_options(options: JsValue) -> Result<SvgExportOptions> {
+    let options: Option<SvgExportOptions> = serde_wasm_bindgen::from_value(options)
+        .map_err(|e| error_once!("Renderer.InvalidSvgOptions", err: e.to_string()))?;
+    Ok(options.unwrap_or_default())
 }
 
 #[wasm_bindgen]
@@ -43,9 +59,16 @@ impl TypstRenderer {
         session.render_in_window(rect_lo_x, rect_lo_y, rect_hi_x, rect_hi_y)
     }
 
-    pub fn svg_data(&mut self, session: &mut RenderSession, parts: Option<u32>) -> Result<String> {
+    pub fn svg_data(
+        &mut self,
+        session: &mut RenderSession,
+        parts: Option<u32>,
+        options: JsValue,
+    ) -> Result<String> {
         type UsingExporter = SvgExporter<DefaultExportFeature>;
 
+        let options = parse_svg_options(options)?;
+
         let client = session.client.lock().unwrap();
         let Some(layout) = &client.layout else {
             return Err(error_once!("Renderer.MissingLayout"));
@@ -60,7 +83,7 @@ impl TypstRenderer {
             js: 0 != (parts & (1 << 3)),
         });
 
-        let svg = UsingExporter::render_flat_svg(view.module(), view.pages(), parts);
+        let svg = UsingExporter::render_flat_svg(view.module(), view.pages(), parts, &options);
 
         Ok(svg)
     }
@@ -85,7 +108,8 @@ impl TypstRenderer {
         type UsingExporter = SvgExporter<DefaultExportFeature>;
         // todo: leaking abstraction
         let mut client = session.client.lock().unwrap();
-        let layouts = client.doc.layouts[0].by_scalar().unwrap();
+        let layouts = client.width_layouts();
+        let layouts = layouts.ok_or_else(|| error_once!("Renderer.MissingLayout"))?;
         let mut layout = layouts.first().unwrap();
 
         // base scale = 2
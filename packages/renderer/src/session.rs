@@ -1,12 +1,15 @@
+use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 
 use reflexo_typst::error::prelude::*;
 #[cfg(feature = "render_svg")]
 use reflexo_typst::svg::IncrSvgDocClient;
-use reflexo_typst::vector::ir::{Page, Scalar};
+use reflexo_typst::vector::ir::{LayoutRegionNode, LayoutSelectorExpr, Page, Scalar};
+use reflexo_typst::vector::wire::FrameKind;
 use reflexo_typst2vec::incr::IncrDocClient;
 #[cfg(feature = "render_canvas")]
 use reflexo_vec2canvas::IncrCanvasDocClient;
+use reflexo_vec2sema::{search, SearchOptions};
 #[cfg(feature = "rkyv")]
 use rkyv::{Archive, Deserialize, Serialize};
 use wasm_bindgen::prelude::*;
@@ -97,6 +100,7 @@ pub struct PageInfo {
     pub(crate) page_off: usize,
     pub(crate) width: f64,
     pub(crate) height: f64,
+    pub(crate) label: Option<String>,
 }
 
 #[wasm_bindgen]
@@ -115,6 +119,11 @@ impl PageInfo {
     pub fn height_pt(&self) -> f64 {
         self.height
     }
+
+    #[wasm_bindgen(getter)]
+    pub fn label(&self) -> Option<String> {
+        self.label.clone()
+    }
 }
 
 #[wasm_bindgen]
@@ -158,6 +167,74 @@ impl PagesInfo {
     }
 }
 
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct OutlineItemInfo {
+    pub(crate) title: String,
+    pub(crate) level: u32,
+    pub(crate) page: u32,
+    pub(crate) offset: f32,
+    pub(crate) span: u64,
+}
+
+#[wasm_bindgen]
+impl OutlineItemInfo {
+    #[wasm_bindgen(getter)]
+    pub fn title(&self) -> String {
+        self.title.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn level(&self) -> u32 {
+        self.level
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn page(&self) -> u32 {
+        self.page
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn offset_pt(&self) -> f32 {
+        self.offset
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn span(&self) -> u64 {
+        self.span
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct SearchMatchInfo {
+    pub(crate) page_off: usize,
+    pub(crate) rects: Vec<f32>,
+    pub(crate) span: Option<String>,
+}
+
+#[wasm_bindgen]
+impl SearchMatchInfo {
+    #[wasm_bindgen(getter)]
+    pub fn page_off(&self) -> usize {
+        self.page_off
+    }
+
+    /// The rectangles covering the matched text, flattened as `[x0, y0, x1,
+    /// y1, ...]` in pt.
+    #[wasm_bindgen(getter)]
+    pub fn rects(&self) -> Vec<f32> {
+        self.rects.clone()
+    }
+
+    /// The source span of the match, as returned by
+    /// [`RenderSession::source_span`].
+    #[wasm_bindgen(getter)]
+    pub fn span(&self) -> Option<String> {
+        self.span.clone()
+    }
+}
+
 #[derive(Default)]
 #[wasm_bindgen]
 pub struct RenderSession {
@@ -230,9 +307,95 @@ impl RenderSession {
         self.client().kern().source_span(path)
     }
 
+    /// Searches the text in the current layout of the document.
+    pub fn search(
+        &self,
+        pattern: &str,
+        ignore_case: bool,
+        whole_word: bool,
+    ) -> Vec<SearchMatchInfo> {
+        let client = self.client();
+        let layout = client.layout.as_ref();
+        let Some(pages) = layout.and_then(|l| l.pages(&client.doc.module)) else {
+            return vec![];
+        };
+
+        let options = SearchOptions {
+            ignore_case,
+            whole_word,
+        };
+        let kern = client.kern();
+        let resolve_span = |path: &[u32]| kern.source_span(path).ok().flatten();
+        search(
+            pages.module(),
+            pages.pages(),
+            pattern,
+            options,
+            resolve_span,
+        )
+        .into_iter()
+        .map(|m| SearchMatchInfo {
+            page_off: m.page,
+            rects: m
+                .rects
+                .iter()
+                .flat_map(|r| [r.lo.x.0, r.lo.y.0, r.hi.x.0, r.hi.y.0])
+                .collect(),
+            span: m.span,
+        })
+        .collect()
+    }
+
+    /// Gets the outline of the document, in which the headings are listed
+    /// in document order.
+    pub fn outline(&self) -> Vec<OutlineItemInfo> {
+        let client = self.client();
+        let layout_outline = client.layout.as_ref().and_then(LayoutRegionNode::outline);
+        let Some(outline) = layout_outline.or(client.doc.outline.as_ref()) else {
+            return vec![];
+        };
+
+        outline
+            .items
+            .iter()
+            .map(|item| OutlineItemInfo {
+                title: item.title.to_string(),
+                level: item.level,
+                page: item.page,
+                offset: item.offset.0,
+                span: item.span,
+            })
+            .collect()
+    }
+
+    /// Sets the selectors picking among the nested layouts of the document,
+    /// keyed by the kind of the layouts, e.g. `x-target`. The layouts without
+    /// a selector are selected by `Any`.
+    pub fn set_layout_selector(&mut self, selectors: JsValue) -> Result<()> {
+        let selectors: Option<HashMap<String, LayoutSelectorExpr>> =
+            serde_wasm_bindgen::from_value(selectors)
+                .map_err(|e| error_once!("Renderer.InvalidLayoutSelector", err: e.to_string()))?;
+
+        let mut client = self.client.lock().unwrap();
+        client.layout_selector.selectors = selectors.unwrap_or_default();
+        Self::checkout_pages(&mut self.pages_info, &mut client)
+    }
+
+    /// Creates a request to send to the server when merging a frame fails
+    /// with `Renderer.NeedsFullSnapshot`.
+    pub fn resync_request(&self) -> Vec<u8> {
+        self.client().resync_request()
+    }
+
     pub(crate) fn reset(&mut self) {
         let mut client = self.client.lock().unwrap();
-        *client = IncrDocClient::default();
+        client.reset();
+        self.reset_kernels();
+    }
+
+    /// Resets the incremental states of rendering, which are invalidated when
+    /// the entire document is replaced.
+    fn reset_kernels(&self) {
         if cfg!(feature = "render_canvas") {
             let mut canvas_kern = self.canvas_kern.lock().unwrap();
             canvas_kern.reset();
@@ -245,29 +408,43 @@ impl RenderSession {
 
     pub(crate) fn reset_current(&mut self, delta: &[u8]) -> Result<()> {
         let mut client = self.client.lock().unwrap();
-        *client = IncrDocClient::default();
-        if cfg!(feature = "render_canvas") {
-            let mut canvas_kern = self.canvas_kern.lock().unwrap();
-            canvas_kern.reset();
-        }
-        if cfg!(feature = "render_svg") {
-            let mut svg_kern = self.svg_kern.lock().unwrap();
-            svg_kern.reset();
-        }
-        Self::merge_delta_inner(&mut self.pages_info, &mut client, delta)
+        client.reset();
+        self.reset_kernels();
+        Self::merge_delta_inner(&mut self.pages_info, &mut client, delta)?;
+        Ok(())
     }
 
     pub(crate) fn merge_delta(&mut self, delta: &[u8]) -> Result<()> {
         let mut client = self.client.lock().unwrap();
-        Self::merge_delta_inner(&mut self.pages_info, &mut client, delta)
+        let kind = Self::merge_delta_inner(&mut self.pages_info, &mut client, delta)?;
+        // A full frame replaces the entire document, as `reset_current` does.
+        if kind == Some(FrameKind::Full) {
+            self.reset_kernels();
+        }
+        Ok(())
     }
 
+    /// Merges the delta, returning the kind of the frame if the delta is
+    /// framed.
     pub(crate) fn merge_delta_inner(
         pages_info: &mut PagesInfo,
         client: &mut IncrDocClient,
         delta: &[u8],
-    ) -> Result<()> {
+    ) -> Result<Option<FrameKind>> {
         use reflexo_typst2vec::stream::BytesModuleStream;
+        use reflexo_typst2vec::wire::{self, WireError};
+
+        // Frames carry their own revisions, see `reflexo::vector::wire`.
+        if wire::is_frame(delta) {
+            let kind = client.merge_frame(delta).map_err(|err| match err {
+                WireError::NeedsFullSnapshot(reason) => {
+                    error_once!("Renderer.NeedsFullSnapshot", reason: reason.to_string())
+                }
+                err => error_once!("Renderer.InvalidFrame", err: err.to_string()),
+            })?;
+            Self::checkout_pages(pages_info, client)?;
+            return Ok(Some(kind));
+        }
 
         let delta = BytesModuleStream::from_slice(delta).checkout_owned();
         let _delta_ref = &delta;
@@ -285,11 +462,13 @@ impl RenderSession {
         );
 
         client.merge_delta(delta);
+        Self::checkout_pages(pages_info, client)?;
+        Ok(None)
+    }
+
+    fn checkout_pages(pages_info: &mut PagesInfo, client: &mut IncrDocClient) -> Result<()> {
         // checkout the current layout
-        // todo: multiple layout
-        let layouts = &client.doc.layouts[0];
-        if !layouts.is_empty() {
-            let layout = layouts.unwrap_single();
+        if let Ok(layout) = client.select_layout() {
             client.set_layout(layout);
         }
 
@@ -300,11 +479,12 @@ impl RenderSession {
             if let Some(view) = view {
                 // Vec::with_capacity(client.elements.pages.len());
                 pages.reserve(view.pages().len());
-                for (i, Page { size, .. }) in view.pages().iter().enumerate() {
+                for (i, Page { size, label, .. }) in view.pages().iter().enumerate() {
                     pages.push(PageInfo {
                         page_off: i,
                         width: size.x.0 as f64,
                         height: size.y.0 as f64,
+                        label: label.as_deref().map(From::from),
                     });
                 }
             }
@@ -1,6 +1,6 @@
 import test from 'ava';
 
-import { NodeCompiler, PdfStandard } from '../index';
+import { DynLayoutCompiler, NodeCompiler, PdfStandard, ProjectWatcher } from '../index';
 
 // Switch to the current directory for the tests interacting with FS
 process.chdir(__dirname);
@@ -231,3 +231,54 @@ test('it throws error when entry file does not exist', t => {
   const fileNotFound = diags!.find(d => d.message.includes('file not found'));
   t.truthy(fileNotFound);
 });
+
+test('it keeps a registry of the watched projects', async t => {
+  const watcher = ProjectWatcher.create({ workspace: '.' });
+  watcher.add(['inputs/post1.typ', 'inputs/post2.typ'], () => {});
+
+  const pending = watcher.list().map(p => p.status);
+  t.deepEqual(pending, ['pending', 'pending']);
+  // Settles immediately since the projects are never watched.
+  t.is((await watcher.settled('inputs/post1.typ')).status, 'pending');
+
+  watcher.watch();
+  const info = await watcher.settled('inputs/post1.typ');
+  t.is(info.status, 'ok');
+  t.true(info.main?.endsWith('inputs/post1.typ'));
+  t.is(typeof info.revision, 'number');
+  t.true(info.dependencies.some(dep => dep.endsWith('post1.typ')));
+
+  watcher.remove('inputs/post2.typ');
+  t.is(watcher.list().length, 1);
+  t.falsy(watcher.get('inputs/post2.typ'));
+  await t.throwsAsync(watcher.settled('inputs/post2.typ'));
+
+  watcher.clear();
+  watcher.watch();
+  t.is(watcher.list().length, 0);
+});
+
+test('it recompiles the watched projects after eviction', async t => {
+  const watcher = ProjectWatcher.create({ workspace: '.' });
+  watcher.add('inputs/post1.typ', () => {});
+  watcher.watch();
+  t.is((await watcher.settled('inputs/post1.typ')).status, 'ok');
+
+  watcher.evict('inputs/post1.typ', 0);
+  watcher.evictCache(0);
+  watcher.compile('inputs/post1.typ');
+  t.is(watcher.get('inputs/post1.typ')?.status, 'pending');
+  t.is((await watcher.settled('inputs/post1.typ')).status, 'ok');
+
+  watcher.clear();
+  watcher.watch();
+});
+
+test('it rejects invalid adaptive layout widths', t => {
+  const compiler = DynLayoutCompiler.fromBoxed(NodeCompiler.create().intoBoxed());
+  t.throws(() => compiler.setAdaptiveLayoutWidths(0, 750));
+  t.throws(() => compiler.setAdaptiveLayoutWidths(750, 360));
+  t.throws(() => compiler.setAdaptiveLayoutWidths(360, Infinity));
+  t.notThrows(() => compiler.setAdaptiveLayoutWidths(360, 360));
+  t.notThrows(() => compiler.setAdaptiveLayoutWidths(360, 750, 10));
+});
@@ -6,8 +6,9 @@ use reflexo_typst::syntax::Span;
 use reflexo_typst::typst::diag::At;
 use reflexo_typst::{error::WithContext, DocumentQuery, ExportComputation, ExportWebSvgModuleTask};
 use reflexo_typst::{
-    ArcInto, Bytes, ExportDynSvgModuleTask, ShadowApi, SystemCompilerFeat, TypstAbs, TypstDocument,
-    TypstDocumentTrait, TypstPagedDocument, TypstSystemWorld,
+    error_once, AdaptiveLayoutWidths, ArcInto, Bytes, ExportDynSvgModuleTask, ShadowApi,
+    SystemCompilerFeat, TypstAbs, TypstDocument, TypstDocumentTrait, TypstPagedDocument,
+    TypstSystemWorld,
 };
 
 use crate::error::*;
@@ -314,14 +315,22 @@ impl NodeCompiler {
     }
 
     /// Simply compiles the document as a rich-contented SVG (for browsers).
-    #[napi(ts_args_type = "compiledOrBy: NodeTypstDocument | CompileDocArgs")]
+    #[napi(ts_args_type = "compiledOrBy: NodeTypstDocument | CompileDocArgs, opts?: RenderSvgOpts")]
     #[cfg(feature = "svg")]
-    pub fn svg(&mut self, compiled_or_by: MayCompileOpts) -> Result<String, NodeError> {
+    pub fn svg(
+        &mut self,
+        compiled_or_by: MayCompileOpts,
+        opts: Option<crate::RenderSvgOpts>,
+    ) -> Result<String, NodeError> {
         use reflexo_typst::ExportWebSvgTask;
         use reflexo_vec2svg::DefaultExportFeature;
 
         type Export = reflexo_typst::WebSvgExport<DefaultExportFeature>;
-        self.compile_as::<Export, _>(compiled_or_by, &ExportWebSvgTask::default())
+        let e = ExportWebSvgTask {
+            options: opts.map(From::from).unwrap_or_default(),
+            ..ExportWebSvgTask::default()
+        };
+        self.compile_as::<Export, _>(compiled_or_by, &e)
     }
 
     // todo: when feature is disabled, it results a compile error
@@ -338,16 +347,26 @@ impl NodeCompiler {
     }
 
     /// Compiles the document as a HTML.
-    #[napi(ts_args_type = "compiledOrBy: NodeTypstDocument | CompileDocArgs")]
+    #[napi(
+        ts_args_type = "compiledOrBy: NodeTypstDocument | CompileDocArgs, opts?: RenderHtmlOpts"
+    )]
     #[cfg(feature = "html")]
-    pub fn try_html(&mut self, compiled_or_by: MayCompileOpts) -> NodeHtmlOutputExecResult {
-        use reflexo_typst::ExportHtmlTask;
-
+    pub fn try_html(
+        &mut self,
+        compiled_or_by: MayCompileOpts,
+        opts: Option<crate::RenderHtmlOpts>,
+    ) -> NodeHtmlOutputExecResult {
         use crate::NodeHtmlOutput;
 
         type Export = reflexo_typst::HtmlOutputExport;
-        let res = self
-            .compile_as_html::<Export, _>(compiled_or_by, &ExportHtmlTask::default())
+        let res = opts
+            .map(reflexo_typst::ExportHtmlOutputTask::try_from)
+            .transpose()
+            .map_err(NodeError::from)
+            .and_then(|config| {
+                let config = config.unwrap_or_default();
+                self.compile_as_html::<Export, _>(compiled_or_by, &config)
+            })
             .map(|res| {
                 res.flatten().map(|inner| NodeHtmlOutput {
                     inner: Arc::new(inner),
@@ -387,6 +406,43 @@ impl DynLayoutCompiler {
             .set_layout_widths(layout_widths.into_iter().map(TypstAbs::pt).collect());
     }
 
+    /// Discovers the widths (in pts) of the layouts by bisecting between
+    /// `min` and `max`, keeping only the widths at which the layout changes.
+    /// At most `budget` compilations are run per combination of the layout
+    /// axes.
+    ///
+    /// Throws an error unless `0 < min <= max`.
+    #[napi]
+    pub fn set_adaptive_layout_widths(
+        &mut self,
+        min: f64,
+        max: f64,
+        budget: Option<u32>,
+    ) -> Result<(), NodeError> {
+        if !(0. < min && min <= max && max.is_finite()) {
+            return Err(error_once!("invalid adaptive layout widths", min: min, max: max))
+                .map_err(map_node_error);
+        }
+
+        let default = AdaptiveLayoutWidths::default();
+        self.task.set_adaptive_widths(Some(AdaptiveLayoutWidths {
+            min: TypstAbs::pt(min),
+            max: TypstAbs::pt(max),
+            budget: budget.map_or(default.budget, |budget| budget as usize),
+            ..default
+        }));
+        Ok(())
+    }
+
+    /// Adds a dimension of the layouts, along which the document is compiled
+    /// once per value of `sys.inputs[key]`, e.g. `x-target` with values
+    /// `web-light` and `web-dark`.
+    #[napi]
+    pub fn add_layout_axis(&mut self, key: String, values: Vec<String>) {
+        let values = values.into_iter().map(From::from).collect();
+        self.task.add_layout_axis(key.into(), values);
+    }
+
     /// Exports the document as a vector IR containing multiple layouts.
     #[napi]
     pub fn vector(&mut self, compile_by: CompileDocArgs) -> Result<Buffer, NodeError> {
@@ -8,13 +8,15 @@ use tokio::sync::mpsc;
 
 use napi::bindgen_prelude::*;
 use napi::threadsafe_function::{ErrorStrategy, ThreadsafeFunction, ThreadsafeFunctionCallMode};
+use napi::{Env, JsDeferred, JsObject};
 use napi_derive::napi;
-use reflexo_typst::error::WithContextUntyped;
+use reflexo_typst::error::{long_diag_from_std, WithContextUntyped};
+use reflexo_typst::path::unix_slash;
 use reflexo_typst::{
     error_once, watch_deps, ArcInto, Bytes, CompilationTask, CompileSnapshot, DocumentQuery,
     EntryReader, EntryState, ExportComputation, ExportWebSvgModuleTask, FlagTask, ProjectInsId,
     SystemCompilerFeat, TaskInputs, TypstDocument, TypstDocumentTrait, TypstPagedDocument,
-    TypstSystemUniverse, TypstSystemWorld, MEMORY_MAIN_ENTRY,
+    TypstSystemUniverse, TypstSystemWorld, TypstWorld, MEMORY_MAIN_ENTRY,
 };
 use tinymist_project::{
     CompileHandler, CompileServerOpts, CompileSignal, CompiledArtifact, Interrupt,
@@ -32,6 +34,7 @@ type WatchFunction = Arc<ThreadsafeFunction<NodeTypstProject, ErrorStrategy::Fat
 pub struct ProjectWatcher {
     entry: EntryState,
     tx: mpsc::UnboundedSender<Message>,
+    registry: Arc<Mutex<ProjectRegistry>>,
 }
 
 #[napi]
@@ -55,9 +58,11 @@ impl ProjectWatcher {
     pub fn create(args: Option<CompileArgs>) -> Result<ProjectWatcher, NodeError> {
         let verse = create_universe(args).map_err(map_node_error)?;
         let entry = verse.entry_state();
+        let registry = Arc::new(Mutex::new(ProjectRegistry::default()));
         let (tx, rx) = mpsc::unbounded_channel();
+        let worker_registry = registry.clone();
         std::thread::spawn(move || {
-            let worker = ProjectBackgroundWorker::new(verse, rx);
+            let worker = ProjectBackgroundWorker::new(verse, rx, worker_registry);
 
             tokio::runtime::Builder::new_current_thread()
                 .enable_all()
@@ -65,7 +70,11 @@ impl ProjectWatcher {
                 .unwrap()
                 .block_on(worker.run());
         });
-        Ok(ProjectWatcher { entry, tx })
+        Ok(ProjectWatcher {
+            entry,
+            tx,
+            registry,
+        })
     }
 
     /// Evict the **global** cache.
@@ -80,7 +89,7 @@ impl ProjectWatcher {
     #[napi]
     pub fn evict_cache(&mut self, max_age: u32) -> Result<(), NodeError> {
         self.tx
-            .send(Message::EvictCache(max_age))
+            .send(Message::EvictCache(convert_max_age(max_age)?))
             .map_err(|_| "send watch message failed")
             .context_ut("failed to watch")
             .map_err(map_node_error)
@@ -118,6 +127,12 @@ impl ProjectWatcher {
     /// ```
     #[napi]
     pub fn watch(&self) -> Result<(), NodeError> {
+        let mut registry = self.registry.lock().unwrap();
+        for record in registry.entries.values_mut() {
+            record.schedule();
+        }
+        drop(registry);
+
         self.tx
             .send(Message::Watch)
             .map_err(|_| "send watch message failed")
@@ -138,11 +153,12 @@ impl ProjectWatcher {
                 .context_ut("failed to create threadsafe function")
                 .map_err(map_node_error)?,
         );
-        self.tx
-            .send(Message::Add(convert_items(items, &self.entry)?, tsfn))
-            .map_err(|_| "send add message failed")
-            .context_ut("failed to add")
-            .map_err(map_node_error)?;
+        let items = convert_items(items, &self.entry)?;
+
+        let mut registry = self.registry.lock().unwrap();
+        for item in items {
+            registry.insert(item, tsfn.clone());
+        }
         Ok(())
     }
 
@@ -157,40 +173,117 @@ impl ProjectWatcher {
                 .context_ut("failed to create threadsafe function")
                 .map_err(map_node_error)?,
         );
-        self.tx
-            .send(Message::Update(convert_items(items, &self.entry)?, tsfn))
-            .map_err(|_| "send update message failed")
-            .context_ut("failed to update")
-            .map_err(map_node_error)?;
+        let items = convert_items(items, &self.entry)?;
+
+        let mut registry = self.registry.lock().unwrap();
+        registry.retain(|entry| items.contains(entry));
+        for item in items {
+            registry.insert(item, tsfn.clone());
+        }
         Ok(())
     }
 
     /// Removes multiple documents from the compiler.
     #[napi(ts_args_type = "items: types.ProjectWatchItems")]
     pub fn remove(&self, items: serde_json::Value) -> Result<(), NodeError> {
-        self.tx
-            .send(Message::Remove(convert_items(items, &self.entry)?))
-            .map_err(|_| "send remove message failed")
-            .context_ut("failed to remove")
-            .map_err(map_node_error)?;
+        let items = convert_items(items, &self.entry)?;
+
+        let mut registry = self.registry.lock().unwrap();
+        registry.retain(|entry| !items.contains(entry));
         Ok(())
     }
 
     /// Clears all documents in the compiler.
     #[napi]
     pub fn clear(&self) -> Result<(), NodeError> {
-        self.tx
-            .send(Message::Clear)
-            .map_err(|_| "send clear message failed")
-            .context_ut("failed to clear")
-            .map_err(map_node_error)?;
+        self.registry.lock().unwrap().retain(|_| false);
         Ok(())
     }
 
     /// Gets the list of documents in the compiler.
     #[napi]
-    pub fn list(&self) -> Result<Vec<String>, NodeError> {
-        Ok(vec![])
+    pub fn list(&self) -> Result<Vec<ProjectInfo>, NodeError> {
+        let registry = self.registry.lock().unwrap();
+        Ok(registry
+            .entries
+            .iter()
+            .map(|(entry, r)| r.info(entry))
+            .collect())
+    }
+
+    /// Gets the state of a document in the compiler.
+    #[napi(ts_args_type = "item: types.ProjectWatchItem")]
+    pub fn get(&self, item: serde_json::Value) -> Result<Option<ProjectInfo>, NodeError> {
+        let entry = resolve_item(item, &self.entry)?;
+
+        let registry = self.registry.lock().unwrap();
+        Ok(registry.entries.get(&entry).map(|r| r.info(&entry)))
+    }
+
+    /// Compiles multiple watched documents, even if they are not changed.
+    ///
+    /// The documents are compiled only after they are watched by
+    /// {@link ProjectWatcher.watch}.
+    #[napi(ts_args_type = "items: types.ProjectWatchItems")]
+    pub fn compile(&self, items: serde_json::Value) -> Result<(), NodeError> {
+        let items = convert_items(items, &self.entry)?;
+
+        let mut registry = self.registry.lock().unwrap();
+        let records = items.iter().flat_map(|item| registry.entries.get_mut(item));
+        for record in records.filter(|record| record.scheduled) {
+            record.schedule();
+        }
+        drop(registry);
+
+        self.tx
+            .send(Message::Compile(items))
+            .map_err(|_| "send compile message failed")
+            .context_ut("failed to compile")
+            .map_err(map_node_error)
+    }
+
+    /// Evict the cache of multiple watched documents.
+    ///
+    /// See {@link ProjectWatcher.evictCache} for the meaning of `max_age`.
+    #[napi(ts_args_type = "items: types.ProjectWatchItems, maxAge: number")]
+    pub fn evict(&self, items: serde_json::Value, max_age: u32) -> Result<(), NodeError> {
+        let items = convert_items(items, &self.entry)?;
+        self.tx
+            .send(Message::Evict(items, convert_max_age(max_age)?))
+            .map_err(|_| "send evict message failed")
+            .context_ut("failed to evict")
+            .map_err(map_node_error)
+    }
+
+    /// Waits for a document to settle, i.e. to finish the pending compilation.
+    ///
+    /// The promise is resolved with the state of the document once it is
+    /// compiled, and it is rejected if the document is removed from the
+    /// compiler before that. If the document is not scheduled to compile by
+    /// {@link ProjectWatcher.watch}, the promise is resolved immediately.
+    #[napi(
+        ts_args_type = "item: types.ProjectWatchItem",
+        ts_return_type = "Promise<ProjectInfo>"
+    )]
+    pub fn settled(&self, env: Env, item: serde_json::Value) -> Result<JsObject, NodeError> {
+        let entry = resolve_item(item, &self.entry)?;
+        let (deferred, promise) = env
+            .create_deferred::<ProjectInfo, SettleResolver>()
+            .map_err(map_node_error)?;
+
+        let mut registry = self.registry.lock().unwrap();
+        let Some(record) = registry.entries.get_mut(&entry) else {
+            deferred.reject(napi::Error::from_reason("the document is not watched"));
+            return Ok(promise);
+        };
+
+        if record.is_settled() || !record.scheduled {
+            let info = record.info(&entry);
+            deferred.resolve(Box::new(move |_| Ok(info)));
+        } else {
+            record.waiters.push(deferred);
+        }
+        Ok(promise)
     }
 }
 
@@ -231,6 +324,12 @@ fn convert_items(
     }
 }
 
+fn convert_max_age(max_age: u32) -> Result<usize, NodeError> {
+    usize::try_from(max_age)
+        .map_err(|e| error_once!("invalid max age", max_age: max_age, err: e.to_string()))
+        .map_err(map_node_error)
+}
+
 fn resolve_item(item: serde_json::Value, base: &EntryState) -> Result<EntryState, NodeError> {
     convert_item(item)?.select_in(base).map_err(map_node_error)
 }
@@ -248,15 +347,201 @@ fn convert_item(item: serde_json::Value) -> Result<Item, NodeError> {
 }
 
 enum Message {
-    EvictCache(u32),
+    EvictCache(usize),
 
-    Add(Vec<EntryState>, WatchFunction),
-    Update(Vec<EntryState>, WatchFunction),
-    Remove(Vec<EntryState>),
-    Clear,
+    Compile(Vec<EntryState>),
+    Evict(Vec<EntryState>, usize),
     Watch,
 }
 
+/// The state of a document in the compiler.
+#[napi(object)]
+#[derive(Debug, Clone)]
+pub struct ProjectInfo {
+    /// The path to the main file.
+    pub main: Option<String>,
+    /// The path to the workspace.
+    pub workspace: Option<String>,
+    /// The revision of the file system at the last compilation.
+    pub revision: Option<u32>,
+    /// The status of the document, which is one of `pending`, `compiling`,
+    /// `ok`, `warning`, or `error`.
+    pub status: String,
+    /// The diagnostics of the last compilation.
+    pub diagnostics: Vec<serde_json::Value>,
+    /// The files depended by the last compilation.
+    pub dependencies: Vec<String>,
+}
+
+type SettleResolver = Box<dyn FnOnce(Env) -> napi::Result<ProjectInfo> + Send>;
+type SettleDeferred = JsDeferred<ProjectInfo, SettleResolver>;
+
+/// The registry of the documents in the compiler, which is shared by the
+/// watcher and the background worker.
+#[derive(Default)]
+struct ProjectRegistry {
+    // todo: rpds
+    entries: FxHashMap<EntryState, ProjectRecord>,
+}
+
+impl ProjectRegistry {
+    /// Adds a document or replaces the callback of the document.
+    fn insert(&mut self, entry: EntryState, watch_fn: WatchFunction) {
+        match self.entries.get_mut(&entry) {
+            Some(record) => record.watch_fn = watch_fn,
+            None => {
+                self.entries.insert(entry, ProjectRecord::new(watch_fn));
+            }
+        }
+    }
+
+    /// Retains the documents matching the predicate, and rejects the waiters
+    /// of the others.
+    fn retain(&mut self, mut f: impl FnMut(&EntryState) -> bool) {
+        self.entries.retain(|entry, record| {
+            let keep = f(entry);
+            if !keep {
+                for waiter in record.waiters.drain(..) {
+                    waiter.reject(napi::Error::from_reason("the document is removed"));
+                }
+            }
+            keep
+        });
+    }
+
+    fn by_id_mut(&mut self, id: &ProjectInsId) -> Option<(&EntryState, &mut ProjectRecord)> {
+        self.entries
+            .iter_mut()
+            .find(|(_, record)| record.id.as_ref() == Some(id))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ProjectStatus {
+    Pending,
+    Compiling,
+    Ok,
+    Warning,
+    Error,
+}
+
+impl ProjectStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Compiling => "compiling",
+            Self::Ok => "ok",
+            Self::Warning => "warning",
+            Self::Error => "error",
+        }
+    }
+}
+
+struct ProjectRecord {
+    watch_fn: WatchFunction,
+    /// The id of the dedicated project, if it is watched.
+    id: Option<ProjectInsId>,
+    /// Whether the document is scheduled to compile by a watch.
+    scheduled: bool,
+    status: ProjectStatus,
+    revision: Option<usize>,
+    diagnostics: Vec<serde_json::Value>,
+    dependencies: Vec<String>,
+    waiters: Vec<SettleDeferred>,
+}
+
+impl ProjectRecord {
+    fn new(watch_fn: WatchFunction) -> Self {
+        Self {
+            watch_fn,
+            id: None,
+            scheduled: false,
+            status: ProjectStatus::Pending,
+            revision: None,
+            diagnostics: vec![],
+            dependencies: vec![],
+            waiters: vec![],
+        }
+    }
+
+    /// Marks the document as pending until the next compilation settles.
+    fn schedule(&mut self) {
+        self.scheduled = true;
+        self.status = ProjectStatus::Pending;
+    }
+
+    /// Unschedules the document and rejects the waiters, since it will not
+    /// be compiled.
+    fn fail(&mut self, reason: &str) {
+        self.scheduled = false;
+        for waiter in self.waiters.drain(..) {
+            waiter.reject(napi::Error::from_reason(reason));
+        }
+    }
+
+    fn is_settled(&self) -> bool {
+        !matches!(
+            self.status,
+            ProjectStatus::Pending | ProjectStatus::Compiling
+        )
+    }
+
+    fn info(&self, entry: &EntryState) -> ProjectInfo {
+        let root = entry.root();
+        let main = entry
+            .main()
+            .zip(root.as_ref())
+            .and_then(|(main, root)| main.vpath().resolve(root));
+
+        ProjectInfo {
+            main: main.as_deref().map(unix_slash),
+            workspace: root.as_deref().map(unix_slash),
+            revision: self.revision.map(|rev| rev as u32),
+            status: self.status.as_str().to_owned(),
+            diagnostics: self.diagnostics.clone(),
+            dependencies: self.dependencies.clone(),
+        }
+    }
+
+    /// Updates the record by the compilation and resolves the waiters.
+    fn settle(&mut self, entry: &EntryState, compiled: &CompiledArtifact<SystemCompilerFeat>) {
+        use reflexo_typst::typst::diag::Severity;
+
+        let world = compiled.world();
+        let has = |severity| compiled.diagnostics().any(|diag| diag.severity == severity);
+
+        self.status = if has(Severity::Error) {
+            ProjectStatus::Error
+        } else if has(Severity::Warning) {
+            ProjectStatus::Warning
+        } else {
+            ProjectStatus::Ok
+        };
+        self.revision = Some(world.vfs().revision().get());
+        self.diagnostics = compiled
+            .diagnostics()
+            .flat_map(|diag| long_diag_from_std(diag.clone(), Some(world as &dyn TypstWorld)))
+            .filter_map(|diag| serde_json::to_value(diag).ok())
+            .collect();
+        self.dependencies = compiled
+            .depended_files()
+            .iter()
+            .map(|id| {
+                let path = unix_slash(id.vpath().as_rooted_path());
+                match id.package() {
+                    Some(package) => format!("{package}{path}"),
+                    None => path,
+                }
+            })
+            .collect();
+
+        for waiter in self.waiters.drain(..) {
+            let info = self.info(entry);
+            waiter.resolve(Box::new(move |_| Ok(info)));
+        }
+    }
+}
+
 struct ProjectBackgroundWorker {
     compiler: ProjectCompilerBase<SystemCompilerFeat, ProjectInsStateExt>,
     dep_rx: mpsc::UnboundedReceiver<NotifyMessage>,
@@ -265,19 +550,23 @@ struct ProjectBackgroundWorker {
     intr_rx: mpsc::UnboundedReceiver<Interrupt<SystemCompilerFeat>>,
 
     rx: mpsc::UnboundedReceiver<Message>,
-    // todo: rpds
-    view: FxHashMap<EntryState, WatchFunction>,
+    registry: Arc<Mutex<ProjectRegistry>>,
     handler: Arc<ProjectHandler>,
 }
 
 impl ProjectBackgroundWorker {
-    fn new(verse: TypstSystemUniverse, rx: mpsc::UnboundedReceiver<Message>) -> Self {
+    fn new(
+        verse: TypstSystemUniverse,
+        rx: mpsc::UnboundedReceiver<Message>,
+        registry: Arc<Mutex<ProjectRegistry>>,
+    ) -> Self {
         let (intr_tx, intr_rx) = mpsc::unbounded_channel();
         let (dep_tx, dep_rx) = mpsc::unbounded_channel();
 
         let handler = Arc::new(ProjectHandler {
             intr_tx: intr_tx.clone(),
             watch: Arc::default(),
+            registry: registry.clone(),
         });
 
         let compiler = ProjectCompilerBase::new(
@@ -297,7 +586,7 @@ impl ProjectBackgroundWorker {
             dep_rx,
             rx,
             handler,
-            view: FxHashMap::default(),
+            registry,
         }
     }
 
@@ -319,6 +608,12 @@ impl ProjectBackgroundWorker {
                             proj.ext.is_compiling = false;
                             proj.ext.last_compilation = Some(compiled.clone());
                         }
+
+                        let mut registry = self.registry.lock().unwrap();
+                        if let Some((entry, record)) = registry.by_id_mut(compiled.id()) {
+                            let entry = entry.clone();
+                            record.settle(&entry, compiled);
+                        }
                     }
 
                     self.compiler.process(intr);
@@ -330,20 +625,40 @@ impl ProjectBackgroundWorker {
 
             match msg {
                 Message::EvictCache(max_age) => {
-                    let max_age = usize::try_from(max_age).unwrap();
                     comemo::evict(max_age);
 
                     for proj in self.compiler.projects() {
                         proj.verse.evict(max_age);
                     }
                 }
+                Message::Compile(items) => {
+                    let ids = self.dedicated_ids(&items);
+                    for proj in self.compiler.projects() {
+                        if ids.contains(&proj.id) {
+                            proj.reason.by_entry_update = true;
+                        }
+                    }
 
+                    self.compiler
+                        .handler
+                        .clone()
+                        .on_any_compile_reason(&mut self.compiler);
+                }
+                Message::Evict(items, max_age) => {
+                    let ids = self.dedicated_ids(&items);
+                    for proj in self.compiler.projects() {
+                        if ids.contains(&proj.id) {
+                            proj.verse.evict(max_age);
+                        }
+                    }
+                }
                 Message::Watch => {
-                    let view = &self.view;
+                    let mut registry = self.registry.lock().unwrap();
                     let mut watch_fns = self.handler.watch.lock().unwrap();
 
                     self.compiler.clear_dedicates();
-                    for (idx, (entry, watch_fn)) in view.iter().enumerate() {
+                    watch_fns.clear();
+                    for (idx, (entry, record)) in registry.entries.iter_mut().enumerate() {
                         let id = format!("project-{idx}");
 
                         // todo: html
@@ -351,43 +666,36 @@ impl ProjectBackgroundWorker {
 
                         match id {
                             Ok(id) => {
-                                watch_fns.insert(id, watch_fn.clone());
+                                watch_fns.insert(id.clone(), record.watch_fn.clone());
+                                record.id = Some(id);
                             }
                             Err(e) => {
+                                record.id = None;
                                 // todo: error handler
                                 eprintln!("failed to restart project: {e}");
+                                record.fail(&format!("failed to restart project: {e}"));
                             }
                         }
                     }
+                    drop(watch_fns);
+                    drop(registry);
 
                     self.compiler
                         .handler
                         .clone()
                         .on_any_compile_reason(&mut self.compiler);
                 }
-                Message::Add(items, tsfn) => {
-                    for item in items {
-                        self.view.insert(item, tsfn.clone());
-                    }
-                }
-                Message::Update(items, tsfn) => {
-                    self.view.clear();
-                    for item in items {
-                        self.view.insert(item, tsfn.clone());
-                    }
-                }
-                Message::Remove(items) => {
-                    for item in items {
-                        self.view.remove(&item);
-                    }
-                }
-                Message::Clear => {
-                    self.view.clear();
-                }
             };
         }
         eprintln!("exit");
     }
+
+    /// Gets the ids of the dedicated projects of the documents.
+    fn dedicated_ids(&self, items: &[EntryState]) -> Vec<ProjectInsId> {
+        let registry = self.registry.lock().unwrap();
+        let records = items.iter().flat_map(|item| registry.entries.get(item));
+        records.flat_map(|record| record.id.clone()).collect()
+    }
 }
 
 /// Either a compiled document or compile arguments.
@@ -429,7 +737,6 @@ impl NodeTypstProject {
         compile_by: CompileDocArgs,
     ) -> reflexo_typst::Result<Arc<SystemWorldComputeGraph>, NodeError> {
         use reflexo_typst::ShadowApi;
-        use reflexo_typst::TypstWorld;
 
         let graph = &self.graph;
 
@@ -690,14 +997,22 @@ impl NodeTypstProject {
     }
 
     /// Simply compiles the document as a rich-contented SVG (for browsers).
-    #[napi(ts_args_type = "compiledOrBy: NodeTypstDocument | CompileDocArgs")]
+    #[napi(ts_args_type = "compiledOrBy: NodeTypstDocument | CompileDocArgs, opts?: RenderSvgOpts")]
     #[cfg(feature = "svg")]
-    pub fn svg(&mut self, compiled_or_by: MayCompileOpts) -> Result<String, NodeError> {
+    pub fn svg(
+        &mut self,
+        compiled_or_by: MayCompileOpts,
+        opts: Option<crate::RenderSvgOpts>,
+    ) -> Result<String, NodeError> {
         use reflexo_typst::ExportWebSvgTask;
         use reflexo_vec2svg::DefaultExportFeature;
 
         type Export = reflexo_typst::WebSvgExport<DefaultExportFeature>;
-        self.compile_as::<Export, _>(compiled_or_by, &ExportWebSvgTask::default())
+        let e = ExportWebSvgTask {
+            options: opts.map(From::from).unwrap_or_default(),
+            ..ExportWebSvgTask::default()
+        };
+        self.compile_as::<Export, _>(compiled_or_by, &e)
     }
 
     // todo: when feature is disabled, it results a compile error
@@ -714,16 +1029,26 @@ impl NodeTypstProject {
     }
 
     /// Compiles the document as a HTML.
-    #[napi(ts_args_type = "compiledOrBy: NodeTypstDocument | CompileDocArgs")]
+    #[napi(
+        ts_args_type = "compiledOrBy: NodeTypstDocument | CompileDocArgs, opts?: RenderHtmlOpts"
+    )]
     #[cfg(feature = "html")]
-    pub fn try_html(&mut self, compiled_or_by: MayCompileOpts) -> NodeHtmlOutputExecResult {
-        use reflexo_typst::ExportHtmlTask;
-
+    pub fn try_html(
+        &mut self,
+        compiled_or_by: MayCompileOpts,
+        opts: Option<crate::RenderHtmlOpts>,
+    ) -> NodeHtmlOutputExecResult {
         use crate::NodeHtmlOutput;
 
         type Export = reflexo_typst::HtmlOutputExport;
-        let res = self
-            .compile_as_html::<Export, _>(compiled_or_by, &ExportHtmlTask::default())
+        let res = opts
+            .map(reflexo_typst::ExportHtmlOutputTask::try_from)
+            .transpose()
+            .map_err(NodeError::from)
+            .and_then(|config| {
+                let config = config.unwrap_or_default();
+                self.compile_as_html::<Export, _>(compiled_or_by, &config)
+            })
             .map(|res| {
                 res.flatten().map(|inner| NodeHtmlOutput {
                     inner: Arc::new(inner),
@@ -742,6 +1067,7 @@ pub struct ProjectInsStateExt {
 struct ProjectHandler {
     intr_tx: mpsc::UnboundedSender<Interrupt<SystemCompilerFeat>>,
     watch: Arc<Mutex<FxHashMap<ProjectInsId, WatchFunction>>>,
+    registry: Arc<Mutex<ProjectRegistry>>,
 }
 
 impl CompileHandler<SystemCompilerFeat, ProjectInsStateExt> for ProjectHandler {
@@ -800,17 +1126,21 @@ impl CompileHandler<SystemCompilerFeat, ProjectInsStateExt> for ProjectHandler {
                         if !matches!(status, Status::Ok) {
                             eprintln!("failed to call watch function: {status:?}");
                         }
-
-                        intr_tx
-                            .send(Interrupt::<SystemCompilerFeat>::Compiled(res))
-                            .ok();
                     }
+                    drop(watches);
+
+                    intr_tx
+                        .send(Interrupt::<SystemCompilerFeat>::Compiled(res))
+                        .ok();
                 })
             else {
                 continue;
             };
 
             proj.ext.is_compiling = true;
+            if let Some((_, record)) = self.registry.lock().unwrap().by_id_mut(&proj.id) {
+                record.status = ProjectStatus::Compiling;
+            }
             rayon::spawn(move || {
                 may_compile();
             })
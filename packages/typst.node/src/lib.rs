@@ -136,6 +136,16 @@ impl NodeHtmlOutput {
         self.inner.html().unwrap_or_default().to_owned().into()
     }
 
+    /// Gets the external SVG files of the frames, keyed by the paths relative
+    /// to the HTML file.
+    ///
+    /// It is empty unless the frames are rendered as `files`.
+    #[napi]
+    pub fn frame_files(&self) -> HashMap<String, String> {
+        let files = self.inner.frame_files().into_iter();
+        files.map(|(path, svg)| (path, svg.to_owned())).collect()
+    }
+
     /// Gets the [Hast] of the document.
     ///
     /// [hast]: https://github.com/syntax-tree/hast
@@ -196,3 +206,77 @@ pub struct RenderPdfOpts {
     /// This is used when you *enable auto timestamp* in the document.
     pub creation_timestamp: Option<i64>,
 }
+
+/// Arguments to render a HTML.
+#[napi(object)]
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[cfg(feature = "html")]
+pub struct RenderHtmlOpts {
+    /// Whether to attach the source locations of the elements, as the
+    /// `position` of the hast nodes and the `data-typst-span` attributes in
+    /// HTML.
+    pub source_positions: Option<bool>,
+
+    /// How to encode the frames embedded in the document, e.g. equations.
+    ///
+    /// - `svg` (default): inline SVGs, or `<img>` with `data:` URLs in hast.
+    /// - `inline`: inline SVGs sharing the glyph definitions.
+    /// - `files`: `<img>` referencing the external SVG files, see
+    ///   {@link NodeHtmlOutput.frameFiles}.
+    pub frames: Option<String>,
+}
+
+#[cfg(feature = "html")]
+impl TryFrom<RenderHtmlOpts> for reflexo_typst::ExportHtmlOutputTask {
+    type Error = reflexo_typst::error::Error;
+
+    fn try_from(opts: RenderHtmlOpts) -> Result<Self, Self::Error> {
+        use reflexo_typst::error::WithContext;
+
+        let frames = opts
+            .frames
+            .map(|mode| serde_json::from_value(serde_json::Value::String(mode)))
+            .transpose()
+            .context("failed to deserialize HtmlFrameMode")?;
+
+        Ok(Self {
+            source_positions: opts.source_positions.unwrap_or_default(),
+            frames: frames.unwrap_or_default(),
+            ..Self::default()
+        })
+    }
+}
+
+/// Arguments to render a SVG.
+#[napi(object)]
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[cfg(feature = "svg")]
+pub struct RenderSvgOpts {
+    /// Whether to render the selectable and searchable text elements.
+    pub render_text_element: Option<bool>,
+
+    /// Whether to include the builtin css.
+    pub builtin_css: Option<bool>,
+
+    /// Whether to include the script for interactive and responsive actions.
+    pub responsive_js: Option<bool>,
+
+    /// Whether to render the text as images.
+    pub rasterize_text: Option<bool>,
+
+    /// Whether to use the stable glyph ids hashed from the glyphs.
+    pub stable_glyph_id: Option<bool>,
+}
+
+#[cfg(feature = "svg")]
+impl From<RenderSvgOpts> for reflexo_vec2svg::SvgExportOptions {
+    fn from(opts: RenderSvgOpts) -> Self {
+        Self {
+            render_text_element: opts.render_text_element,
+            builtin_css: opts.builtin_css,
+            responsive_js: opts.responsive_js,
+            rasterize_text: opts.rasterize_text,
+            stable_glyph_id: opts.stable_glyph_id,
+        }
+    }
+}
@@ -121,6 +121,10 @@ export interface QueryOptions extends CompileOptionsCommon {
    * cast result by accessing single field.
    */
   field?: string;
+  /**
+   * the document target to query against, defaults to `'paged'`.
+   */
+  target?: 'paged' | 'html';
 }
 
 /**
@@ -158,6 +162,14 @@ export class IncrementalServer {
     return this[kObject].current();
   }
 
+  /**
+   * Handle a resync request sent by a renderer which fails to merge a delta
+   * with `Renderer.NeedsFullSnapshot`, returning the full result to reset it.
+   */
+  resync(request: Uint8Array): Uint8Array | undefined {
+    return this[kObject].resync(request);
+  }
+
   /**
    * Also attach the debug info to the result.
    */
@@ -378,6 +390,7 @@ class TypstCompilerDriver {
             convertInputs(options.inputs),
             options.selector,
             options.field,
+            options.target,
           ),
         ),
       );
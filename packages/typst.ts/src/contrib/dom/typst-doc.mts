@@ -36,6 +36,29 @@ export interface Options {
   retrieveDOMState?: () => ContainerDOMState;
 }
 
+/// The magic header of frames, see `reflexo::vector::wire`.
+const FRAME_MAGIC = [0x74, 0x73, 0x76, 0x64];
+/// The offset of the frame kind in the header of frames.
+const FRAME_KIND_OFFSET = 6;
+/// The kind of frames replacing the entire document.
+const FRAME_KIND_FULL = 0;
+
+/// Checks whether the data is a full frame, which resets the document like
+/// a `new` change.
+function isFullFrame(data: unknown): boolean {
+  return (
+    data instanceof Uint8Array &&
+    data.length > FRAME_KIND_OFFSET &&
+    FRAME_MAGIC.every((b, i) => data[i] === b) &&
+    data[FRAME_KIND_OFFSET] === FRAME_KIND_FULL
+  );
+}
+
+/// Checks whether the change replaces the entire document.
+function isResetChange(change: [string, string]): boolean {
+  return change[0] === 'new' || isFullFrame(change[1]);
+}
+
 export type GConstructor<T = {}> = new (...args: any[]) => T;
 
 interface TypstDocumentFacade {
@@ -317,8 +340,9 @@ export class TypstDocumentContext<O = any> {
     const eventName = svgUpdateEvent[0];
     switch (eventName) {
       case 'new':
-      case 'diff-v1': {
-        if (eventName === 'new') {
+      case 'diff-v1':
+      case 'frame': {
+        if (isResetChange(svgUpdateEvent)) {
           this.reset();
         }
         this.kModule.manipulateData({
@@ -410,7 +434,7 @@ export class TypstDocumentContext<O = any> {
   }
 
   addChangement(change: [string, string]) {
-    if (change[0] === 'new') {
+    if (isResetChange(change)) {
       this.patchQueue.splice(0, this.patchQueue.length);
     }
 
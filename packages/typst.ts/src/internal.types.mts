@@ -5,13 +5,57 @@ export const kObject = Symbol.for('reflexo-obj');
  * @property {number} pageOffset - The offset of the page.
  * @property {number} width - The width of the page in pt.
  * @property {number} height - The height of the page in pt.
+ * @property {string} label - The logical page number, e.g. `iii` or `A-1`.
  */
 export class PageInfo {
   pageOffset: number;
   width: number;
   height: number;
+  label?: string;
 }
 
+/**
+ * A match of searching text in a Typst document.
+ * @property {number} pageOffset - The offset of the page containing the match.
+ * @property {number[][]} rects - The rectangles `[x0, y0, x1, y1]` covering the
+ * matched text in pt.
+ * @property {string} [span] - The source span of the match, as returned by
+ * `getSourceLoc`.
+ */
+export class SearchMatch {
+  pageOffset: number;
+  rects: [number, number, number, number][];
+  span?: string;
+}
+
+/**
+ * An entry of the outline of a Typst document.
+ * @property {string} title - The plain text of the heading.
+ * @property {number} level - The one-based level of the heading.
+ * @property {number} page - The one-based page number of the heading.
+ * @property {number} offset - The vertical offset of the heading on the page in pt.
+ * @property {bigint} span - The span of the heading.
+ */
+export class OutlineItem {
+  title: string;
+  level: number;
+  page: number;
+  offset: number;
+  span: bigint;
+}
+
+/**
+ * Selects among the layouts of a kind, e.g. `x-target` or `width`.
+ * - `Any` and `First` select the first layout, and `Last` the last one.
+ * - `ScalarLB` selects the last layout whose scalar value is less than `v`.
+ * - `ScalarUB` selects the first layout whose scalar value is greater than `v`.
+ * - `StrEQ` selects the layout whose string value equals to `v`.
+ */
+export type LayoutSelectorExpr =
+  | { t: 'Any' | 'First' | 'Last' }
+  | { t: 'ScalarLB' | 'ScalarUB'; v: number }
+  | { t: 'StrEQ'; v: string };
+
 export interface FsAccessModel {
   getMTime(path: string): Date | undefined;
   isFile(path: string): boolean | undefined;
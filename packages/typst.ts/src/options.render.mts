@@ -93,6 +93,37 @@ export interface RenderSvgOptions {
     css: boolean;
     js: boolean;
   };
+  /**
+   * The runtime options of svg export, where an unset option falls back to
+   * the default of the renderer.
+   */
+  exportOptions?: SvgExportOptions;
+}
+
+/**
+ * The runtime options of svg export.
+ */
+export interface SvgExportOptions {
+  /**
+   * Whether to render the selectable and searchable text elements.
+   */
+  renderTextElement?: boolean;
+  /**
+   * Whether to include the builtin css.
+   */
+  builtinCss?: boolean;
+  /**
+   * Whether to include the script for interactive and responsive actions.
+   */
+  responsiveJs?: boolean;
+  /**
+   * Whether to render the text as images.
+   */
+  rasterizeText?: boolean;
+  /**
+   * Whether to use the stable glyph ids hashed from the glyphs.
+   */
+  stableGlyphId?: boolean;
 }
 
 /**
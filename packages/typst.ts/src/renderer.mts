@@ -2,7 +2,15 @@
 import type * as typst from '@myriaddreamin/typst-ts-renderer';
 
 import type { InitOptions } from './options.init.mjs';
-import { PageInfo, RenderCanvasResult, TypstDefaultParams, kObject } from './internal.types.mjs';
+import {
+  LayoutSelectorExpr,
+  OutlineItem,
+  PageInfo,
+  RenderCanvasResult,
+  SearchMatch,
+  TypstDefaultParams,
+  kObject,
+} from './internal.types.mjs';
 import {
   CreateSessionOptions,
   RenderToCanvasOptions,
@@ -12,6 +20,7 @@ import {
   ManipulateDataOptions,
   RenderSvgOptions,
   RenderInSessionOptions,
+  SvgExportOptions,
   MountDomOptions,
   OffscreenRenderCanvasOptions,
 } from './options.render.mjs';
@@ -153,16 +162,72 @@ export class RenderSession {
         pageOffset: pageAst.page_off,
         width: pageAst.width_pt,
         height: pageAst.height_pt,
+        label: pageAst.label,
       });
     }
 
     return pageInfos;
   }
 
+  /**
+   * Retrieve the outline of the document, in which the headings are listed in
+   * document order.
+   */
+  retrieveOutline(): OutlineItem[] {
+    return (this[kObject] as typst.RenderSession).outline().map(item => ({
+      title: item.title,
+      level: item.level,
+      page: item.page,
+      offset: item.offset_pt,
+      span: item.span,
+    }));
+  }
+
+  /**
+   * Search the text in the document, which can be used to highlight the
+   * matches without compiling the document again.
+   */
+  searchText(
+    pattern: string,
+    options?: { ignoreCase?: boolean; wholeWord?: boolean },
+  ): SearchMatch[] {
+    const matches = (this[kObject] as typst.RenderSession).search(
+      pattern,
+      options?.ignoreCase ?? false,
+      options?.wholeWord ?? false,
+    );
+    return matches.map(m => {
+      const flat = m.rects;
+      const rects: SearchMatch['rects'] = [];
+      for (let i = 0; i + 3 < flat.length; i += 4) {
+        rects.push([flat[i], flat[i + 1], flat[i + 2], flat[i + 3]]);
+      }
+      return { pageOffset: m.page_off, rects, span: m.span };
+    });
+  }
+
+  /**
+   * Select among the nested layouts of a document compiled with layout axes,
+   * keyed by the kind of the layouts, e.g.
+   * `{ 'x-target': { t: 'StrEQ', v: 'web-dark' } }`. The layouts without a
+   * selector fall back to the first one.
+   */
+  setLayoutSelector(selectors: Record<string, LayoutSelectorExpr>): void {
+    (this[kObject] as typst.RenderSession).set_layout_selector(selectors);
+  }
+
   getSourceLoc(path: Uint32Array): string | undefined {
     return (this[kObject] as typst.RenderSession).source_span(path);
   }
 
+  /**
+   * Create a request for the full document, which should be sent to the
+   * server when merging data fails with `Renderer.NeedsFullSnapshot`.
+   */
+  resyncRequest(): Uint8Array {
+    return this[kObject].resync_request();
+  }
+
   /**
    * See {@link TypstRenderer#renderSvg} for more details.
    */
@@ -342,6 +407,7 @@ export class TypstWorker {
         pageOffset: pageAst.page_off,
         width: pageAst.width_pt,
         height: pageAst.height_pt,
+        label: pageAst.label,
       });
     }
 
@@ -551,6 +617,23 @@ export interface TypstRenderer extends TypstSvgRenderer {
   render(options: RenderOptions<RenderToCanvasOptions>): Promise<void>;
 }
 
+/**
+ * Converts the svg export options to the form accepted by the renderer.
+ */
+function toSvgExportOptions(options?: SvgExportOptions) {
+  if (!options) {
+    return undefined;
+  }
+
+  return {
+    'render-text-element': options.renderTextElement,
+    'builtin-css': options.builtinCss,
+    'responsive-js': options.responsiveJs,
+    'rasterize-text': options.rasterizeText,
+    'stable-glyph-id': options.stableGlyphId,
+  };
+}
+
 const gRendererModule = (module: typeof typst) =>
   new LazyWasmModule(async (bin?: any) => {
     return await module.default(bin);
@@ -915,11 +998,18 @@ export class TypstRendererDriver {
         }
       }
 
-      return Promise.resolve(this.renderer.svg_data(sessionRef[kObject], parts));
+      const exportOptions = toSvgExportOptions(options.exportOptions);
+      return Promise.resolve(this.renderer.svg_data(sessionRef[kObject], parts, exportOptions));
     });
   }
 
   renderSvgDiff(options: RenderInSessionOptions<RenderSvgOptions>): string {
+    if (options.exportOptions) {
+      (options.renderSession as any)[kObject].set_svg_options(
+        toSvgExportOptions(options.exportOptions),
+      );
+    }
+
     if (!options.window) {
       return this.renderer.render_svg_diff(
         (options.renderSession as any)[kObject],
@@ -1,9 +1,11 @@
+use std::net::SocketAddr;
 use std::path::PathBuf;
 
 use clap::{ArgAction, Parser, Subcommand};
 use reflexo_typst::build_info::VERSION;
 
 pub mod http;
+pub mod preview;
 pub mod utils;
 
 #[derive(Debug, Parser)]
@@ -29,6 +31,8 @@ pub enum Subcommands {
     /// Watches codebase for debugging
     #[clap(subcommand)]
     Watch(WatchSubCommands),
+    /// Previews a document live in browser
+    Preview(PreviewArgs),
 }
 
 #[derive(Debug, Parser)]
@@ -108,3 +112,21 @@ pub struct RunHttpArgs {
     #[clap(long, default_value = "")]
     pub http: String,
 }
+
+#[derive(Debug, Clone, Parser)]
+#[clap(next_help_heading = "Preview options")]
+pub struct PreviewArgs {
+    #[clap(flatten)]
+    pub compile: typst_ts_cli::CompileOnceArgs,
+
+    /// Listen address of the preview page, which also serves the WebSocket
+    /// streaming the document to the page at `/ws`.
+    #[clap(long, default_value = "127.0.0.1:20811")]
+    pub http: SocketAddr,
+
+    /// Opens the source location clicked in the page with the command, in
+    /// which `{file}`, `{line}` and `{column}` are replaced, e.g.
+    /// `code --goto {file}:{line}:{column}`.
+    #[clap(long, value_name = "COMMAND")]
+    pub open_with: Option<String>,
+}
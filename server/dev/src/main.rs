@@ -9,8 +9,8 @@ use reflexo_typst::{EntryReader, TaskInputs};
 use tokio::io::AsyncBufReadExt;
 use typst_ts_cli::export::ReflexoTaskBuilder;
 use typst_ts_dev_server::{
-    http::run_http, utils::async_continue, CompileCorpusArgs, CompileSubCommands, Opts,
-    RunSubCommands, Subcommands, WatchSubCommands,
+    http::run_http, preview::run_preview, utils::async_continue, CompileCorpusArgs,
+    CompileSubCommands, Opts, RunSubCommands, Subcommands, WatchSubCommands,
 };
 
 fn main() {
@@ -37,6 +37,10 @@ fn main() {
             watch(watch_sub).await;
             exit(0);
         }),
+        Subcommands::Preview(args) => async_continue(async move {
+            run_preview(args).await;
+            exit(0);
+        }),
     };
 
     #[allow(unreachable_code)]
@@ -83,7 +87,8 @@ fn compile_corpus(args: CompileCorpusArgs) {
             inputs: None,
         });
 
-        (exporter)(&graph).unwrap();
+        let summary = (exporter)(&graph).unwrap();
+        assert!(summary.is_success(), "some export tasks failed");
     };
 
     // get all corpus in workspace_path
@@ -0,0 +1,382 @@
+//! A live preview server, which compiles an entry on every change and
+//! streams the incremental vector data to the pages over a WebSocket.
+//!
+//! A page sends a binary message to request a full frame once it fails to
+//! merge a delta, and a text message like
+//! `{"event":"sourceJump","path":[4,0,1,0,0,2]}` to jump to the source of an
+//! element. The path is the flattened `(kind, index)` pairs from the page to
+//! the element, see [`reflexo_typst::debug_loc::ElementPoint`].
+
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+
+use futures_util::{SinkExt, StreamExt};
+use reflexo_typst::debug_loc::ElementPoint;
+use reflexo_typst::error::prelude::*;
+use reflexo_typst::metrics::MetricsRecorder;
+use reflexo_typst::task::CancelToken;
+use reflexo_typst::vector::incr::IncrDocServer;
+use reflexo_typst::{
+    CompilationHandle, CompileActor, CompileReport, CompileServerOpts, DiagnosticHandler,
+    DiagnosticsTask, FlagTask, HtmlCompilationTask, ImmutPath, SystemCompilerFeat, TypstDocument,
+    TypstPagedDocument, WorldComputeGraph,
+};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{broadcast, mpsc};
+use typst::{World, WorldExt};
+use warp::http::StatusCode;
+use warp::ws::{Message, WebSocket, Ws};
+use warp::{Filter, Reply};
+
+use crate::PreviewArgs;
+
+/// The page rendering the preview, which connects to the WebSocket at `/ws`
+/// of the same host.
+const PREVIEW_HTML: &str = include_str!("preview.html");
+
+/// Runs the preview server until the process exits.
+pub async fn run_preview(args: PreviewArgs) {
+    let verse = typst_ts_cli::compile::resolve_universe(args.compile);
+    let state = Arc::new(PreviewState::new(args.open_with));
+
+    tokio::spawn(serve(args.http, state.clone()));
+
+    let (intr_tx, intr_rx) = mpsc::unbounded_channel();
+    let handle = Arc::new(PreviewHandler {
+        state,
+        diag: DiagnosticHandler {
+            print_compile_status: true,
+            ..Default::default()
+        },
+    });
+    let actor = CompileActor::new_with(
+        verse,
+        intr_tx,
+        intr_rx,
+        CompileServerOpts {
+            compile_handle: handle,
+            ..Default::default()
+        },
+    )
+    .with_watch(true);
+
+    if let Err(err) = actor.run().await {
+        log::error!("preview compiler stopped: {err}");
+    }
+}
+
+/// Serves the page, the assets of the renderer and the WebSocket streaming
+/// the document to the page.
+async fn serve(http_addr: SocketAddr, state: Arc<PreviewState>) {
+    let data_plane = warp::path("ws")
+        .and(warp::path::end())
+        .and(warp::header::optional::<String>("origin"))
+        .and(warp::header::optional::<String>("host"))
+        .and(warp::ws())
+        .map(
+            move |origin: Option<String>, host: Option<String>, ws: Ws| {
+                if !is_preview_origin(origin.as_deref(), host.as_deref()) {
+                    log::warn!("rejected preview client from origin {origin:?}");
+                    return StatusCode::FORBIDDEN.into_response();
+                }
+
+                let state = state.clone();
+                ws.on_upgrade(move |socket| async move {
+                    if let Err(err) = serve_client(socket, state).await {
+                        log::warn!("preview client disconnected: {err}");
+                    }
+                })
+                .into_response()
+            },
+        );
+
+    let root = (warp::path::end().or(warp::path("index.html")))
+        .map(|_| warp::reply::html(PREVIEW_HTML))
+        .boxed();
+
+    // map these files to the same paths as `run http`
+    let assets = warp::path("typst.ts").and({
+        let renderer = warp::path("renderer")
+            .and(warp::fs::dir("packages/renderer/pkg"))
+            .boxed();
+        let typst_main = warp::path("typst-main.js")
+            .and(warp::fs::file("packages/typst.ts/dist/esm/main.bundle.js"))
+            .boxed();
+
+        renderer.or(typst_main)
+    });
+
+    let routes = data_plane.or(root.or(assets).with(warp::compression::gzip()));
+
+    log::info!("preview page served at http://{http_addr}");
+    warp::serve(routes).run(http_addr).await
+}
+
+/// Checks whether a WebSocket request comes from the preview page, so that
+/// the other websites visited by the user can neither read the document nor
+/// open the source locations.
+fn is_preview_origin(origin: Option<&str>, host: Option<&str>) -> bool {
+    let (Some(origin), Some(host)) = (origin, host) else {
+        return false;
+    };
+    if origin.strip_prefix("http://") != Some(host) {
+        return false;
+    }
+
+    // A domain name could be rebound to the address of the server by a
+    // website, so only the page opened by the address is trusted.
+    let name = match host.rsplit_once(':') {
+        Some((name, port)) if port.parse::<u16>().is_ok() => name,
+        _ => host,
+    };
+    let name = name.trim_start_matches('[').trim_end_matches(']');
+    name.eq_ignore_ascii_case("localhost") || name.parse::<IpAddr>().is_ok()
+}
+
+/// Streams the frames to a page and handles its requests.
+async fn serve_client(socket: WebSocket, state: Arc<PreviewState>) -> Result<(), warp::Error> {
+    let (mut writer, mut reader) = socket.split();
+
+    let (current, mut frames) = state.subscribe();
+    if let Some(frame) = current {
+        writer.send(Message::binary(frame)).await?;
+    }
+
+    loop {
+        tokio::select! {
+            frame = frames.recv() => match frame {
+                Ok(frame) => writer.send(Message::binary(frame.to_vec())).await?,
+                // The page missed some deltas, so it is reset by a full frame.
+                Err(broadcast::error::RecvError::Lagged(..)) => {
+                    if let Some(frame) = state.current() {
+                        writer.send(Message::binary(frame)).await?;
+                    }
+                }
+                Err(broadcast::error::RecvError::Closed) => return writer.close().await,
+            },
+            // The pings are answered by the socket itself.
+            msg = reader.next() => match msg.transpose()? {
+                Some(msg) if msg.is_binary() => match state.resync(msg.as_bytes()) {
+                    Ok(Some(frame)) => writer.send(Message::binary(frame)).await?,
+                    Ok(None) => {}
+                    Err(err) => log::warn!("invalid resync request: {err}"),
+                },
+                Some(msg) if msg.is_text() => {
+                    let event = msg.to_str().unwrap_or_default();
+                    if let Some(event) = state.handle_event(event) {
+                        writer.send(Message::text(event)).await?;
+                    }
+                }
+                Some(msg) if msg.is_close() => return Ok(()),
+                Some(..) => {}
+                None => return Ok(()),
+            },
+        }
+    }
+}
+
+/// An event sent by a page.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "event", rename_all = "camelCase")]
+enum ClientEvent {
+    /// Jumps to the source of an element.
+    SourceJump { path: Vec<u32> },
+}
+
+/// An event sent to the pages.
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "camelCase")]
+enum ServerEvent {
+    /// The source location resolved from a [`ClientEvent::SourceJump`].
+    SourceLocation(SourceLocation),
+}
+
+/// A location in a source file. Both the line and the column are 1-based.
+#[derive(Debug, Serialize)]
+struct SourceLocation {
+    /// The absolute path to the file.
+    file: String,
+    line: usize,
+    column: usize,
+}
+
+/// The state shared by the compiler and the connections of the pages.
+struct PreviewState {
+    /// The incremental state of the vector data. It is locked while a frame
+    /// is broadcast, so that a new page never misses or repeats a delta.
+    incr: Mutex<IncrDocServer>,
+    /// The frames of the compiled documents.
+    frames: broadcast::Sender<Arc<[u8]>>,
+    /// The compilation of the latest frame, to resolve the source spans.
+    graph: Mutex<Option<Arc<WorldComputeGraph<SystemCompilerFeat>>>>,
+    /// The command to open a source location with, e.g.
+    /// `code --goto {file}:{line}:{column}`.
+    open_with: Option<String>,
+}
+
+impl PreviewState {
+    fn new(open_with: Option<String>) -> Self {
+        let mut incr = IncrDocServer::default();
+        // The spans are required to resolve the source locations of the
+        // elements.
+        incr.set_should_attach_debug_info(true);
+        // Reconnected clients are initialized by full frames.
+        incr.set_retain_items(true);
+
+        Self {
+            incr: Mutex::new(incr),
+            frames: broadcast::channel(16).0,
+            graph: Mutex::new(None),
+            open_with,
+        }
+    }
+
+    /// Subscribes to the frames, with a full frame of the current document
+    /// if any.
+    fn subscribe(&self) -> (Option<Vec<u8>>, broadcast::Receiver<Arc<[u8]>>) {
+        let mut incr = self.incr.lock().unwrap();
+        (incr.pack_current(), self.frames.subscribe())
+    }
+
+    /// Packs the current document into a full frame.
+    fn current(&self) -> Option<Vec<u8>> {
+        self.incr.lock().unwrap().pack_current()
+    }
+
+    /// Handles a resync request of a page.
+    fn resync(&self, request: &[u8]) -> Result<Option<Vec<u8>>> {
+        self.incr.lock().unwrap().handle_resync(request)
+    }
+
+    /// Packs and broadcasts the delta of a compiled document.
+    fn update(&self, graph: &Arc<WorldComputeGraph<SystemCompilerFeat>>, doc: TypstDocument) {
+        let mut incr = self.incr.lock().unwrap();
+        let frame = incr.pack_delta(&doc);
+        *self.graph.lock().unwrap() = Some(graph.clone());
+        // Fails only if no page is connected.
+        let _ = self.frames.send(frame.into());
+    }
+
+    /// Handles an event of a page, returning the event to reply with.
+    fn handle_event(&self, event: &str) -> Option<String> {
+        let event = match serde_json::from_str::<ClientEvent>(event) {
+            Ok(event) => event,
+            Err(err) => {
+                log::warn!("invalid preview event: {err}");
+                return None;
+            }
+        };
+
+        match event {
+            ClientEvent::SourceJump { path } => {
+                let Some(loc) = self.resolve_source(&path) else {
+                    log::info!("no source location of the element at {path:?}");
+                    return None;
+                };
+
+                log::info!("jump to {}:{}:{}", loc.file, loc.line, loc.column);
+                self.open(&loc);
+                serde_json::to_string(&ServerEvent::SourceLocation(loc)).ok()
+            }
+        }
+    }
+
+    /// Resolves the source location of the element at the path.
+    fn resolve_source(&self, path: &[u32]) -> Option<SourceLocation> {
+        let path = path
+            .chunks_exact(2)
+            .map(|point| ElementPoint {
+                kind: point[0],
+                index: point[1],
+                // An empty fingerprint skips the check of the element.
+                fingerprint: String::new(),
+            })
+            .collect::<Vec<_>>();
+        let mut incr = self.incr.lock().unwrap();
+        let (start, _) = incr.resolve_span_by_element_path(&path).ok()??;
+
+        let graph = self.graph.lock().unwrap().clone()?;
+        let world = &graph.snap.world;
+        let id = start.span.id()?;
+        let range = world.range(start.span)?;
+        let offset = (range.start + start.offset).min(range.end);
+        let source = world.source(id).ok()?;
+        let file: ImmutPath = world.file_path(id).and_then(|e| e.to_err()).ok()?.into();
+
+        Some(SourceLocation {
+            file: file.display().to_string(),
+            line: source.lines().byte_to_line(offset)? + 1,
+            column: source.lines().byte_to_column(offset)? + 1,
+        })
+    }
+
+    /// Opens the source location with the command, if specified.
+    fn open(&self, loc: &SourceLocation) {
+        let Some(cmd) = &self.open_with else {
+            return;
+        };
+
+        let args = cmd
+            .split(' ')
+            .filter(|arg| !arg.is_empty())
+            .map(|arg| {
+                arg.replace("{file}", &loc.file)
+                    .replace("{line}", &loc.line.to_string())
+                    .replace("{column}", &loc.column.to_string())
+            })
+            .collect::<Vec<_>>();
+        let Some((program, args)) = args.split_first() else {
+            return;
+        };
+
+        if let Err(err) = std::process::Command::new(program).args(args).spawn() {
+            log::error!("cannot open source location with {program}: {err}");
+        }
+    }
+}
+
+/// Compiles the paged document and broadcasts its delta.
+struct PreviewHandler {
+    state: Arc<PreviewState>,
+    diag: DiagnosticHandler,
+}
+
+impl CompilationHandle<SystemCompilerFeat> for PreviewHandler {
+    fn status(&self, _revision: usize, _rep: CompileReport) {}
+
+    fn notify_compile(&self, graph: &Arc<WorldComputeGraph<SystemCompilerFeat>>) {
+        let start = reflexo_typst::time::now();
+        let main = graph.snap.world.main();
+
+        let _ = graph.provide::<FlagTask<HtmlCompilationTask>>(Ok(FlagTask::flag(false)));
+        let doc = MetricsRecorder::compile::<_, TypstPagedDocument>(graph);
+        // The newer compilation updates the preview instead.
+        if CancelToken::of(graph).is_cancelled() {
+            return;
+        }
+
+        match doc {
+            Ok(Some(doc)) => self.state.update(graph, TypstDocument::Paged(doc)),
+            // Keeps the last successful document on errors.
+            Ok(None) => {}
+            Err(err) => log::error!("preview compilation failed: {err}"),
+        }
+
+        let diag = match graph.compute::<DiagnosticsTask>() {
+            Ok(diag) => diag,
+            Err(err) => {
+                log::error!("cannot collect diagnostics: {err}");
+                return;
+            }
+        };
+
+        let elapsed = start.elapsed().unwrap_or_default();
+        let report = if diag.error_cnt() != 0 {
+            CompileReport::CompileError(main, diag.error_cnt(), elapsed)
+        } else {
+            CompileReport::CompileSuccess(main, diag.warning_cnt(), elapsed)
+        };
+        self.diag.status(&report);
+        self.diag.report(&graph.snap.world, diag.diagnostics());
+    }
+}
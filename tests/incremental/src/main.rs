@@ -1,14 +1,14 @@
 use std::path::Path;
 
 use reflexo_typst::config::{entry::EntryOpts, CompileOpts};
-use reflexo_typst::vector::{
-    ir::{Abs, Point, Rect},
-    stream::BytesModuleStream,
-};
+use reflexo_typst::vector::ir::{Abs, Point, Rect};
 use reflexo_typst::{Bytes, TypstDocument, TypstSystemUniverse};
 use reflexo_typst2vec::incr::{IncrDocClient, IncrDocServer};
 use reflexo_vec2svg::IncrSvgDocClient;
 
+#[cfg(test)]
+mod tests;
+
 fn get_driver(workspace_dir: &Path, entry_file_path: &Path) -> TypstSystemUniverse {
     let project_base = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../..");
     let w = project_base.join("fonts");
@@ -48,8 +48,7 @@ pub fn test_compiler(workspace_dir: &Path, entry_file_path: &Path) {
         .output
         .unwrap();
     let server_delta = incr_server.pack_delta(&TypstDocument::Paged(doc));
-    let server_delta = BytesModuleStream::from_slice(&server_delta).checkout_owned();
-    incr_client.merge_delta(server_delta);
+    incr_client.merge_frame(&server_delta).unwrap();
     let _ = incr_svg_client.render_in_window(&mut incr_client, window);
 
     for i in 0..20 {
@@ -66,8 +65,7 @@ pub fn test_compiler(workspace_dir: &Path, entry_file_path: &Path) {
 
         let server_delta = incr_server.pack_delta(&TypstDocument::Paged(doc));
         let sd = server_delta.len();
-        let server_delta = BytesModuleStream::from_slice(&server_delta).checkout_owned();
-        incr_client.merge_delta(server_delta);
+        incr_client.merge_frame(&server_delta).unwrap();
         incr_client.set_layout(incr_client.doc.layouts[0].unwrap_single());
         let cd = incr_svg_client.render_in_window(&mut incr_client, window);
         // std::fs::write(format!("{}.svg", i), cd.clone()).unwrap();
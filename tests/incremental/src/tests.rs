@@ -0,0 +1,305 @@
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use reflexo_typst::task::CancelToken;
+use reflexo_typst::vector::ir::{FlatModule, ModuleStream, Outline};
+use reflexo_typst::vector::stream::BytesModuleStream;
+use reflexo_typst::vector::wire::{FrameHeader, FrameKind};
+use reflexo_typst::{
+    static_html, Bytes, CompilationHandle, CompileActor, CompileReport, CompileServerOpts,
+    EntryReader, EntryState, Interrupt, ProjectInsId, SystemCompilerFeat, TaskInputs,
+    TypstDocument, TypstHtmlDocument, TypstSystemUniverse, WorldComputeGraph,
+};
+use reflexo_typst2vec::incr::{IncrDocClient, IncrDocServer};
+use reflexo_typst2vec::pass::Typst2VecPass;
+use reflexo_vec2svg::DynamicLayoutSvgExporter;
+
+use tokio::sync::{mpsc, oneshot};
+
+use super::get_driver;
+
+fn driver() -> TypstSystemUniverse {
+    let workspace_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    get_driver(workspace_dir, &workspace_dir.join("main.typ"))
+}
+
+fn compile(driver: &TypstSystemUniverse, content: &str) -> TypstDocument {
+    let doc = driver
+        .snapshot_with_entry_content(Bytes::from_string(content.to_owned()), None)
+        .compile()
+        .output
+        .unwrap();
+    TypstDocument::Paged(doc)
+}
+
+fn decode(frame: &[u8]) -> (FrameHeader, FlatModule) {
+    let (header, payload) = FrameHeader::decode(frame).unwrap();
+    (
+        header,
+        BytesModuleStream::from_slice(payload).checkout_owned(),
+    )
+}
+
+#[test]
+fn test_snapshot_restore_delta() {
+    let driver = driver();
+    let first = "#rect(fill: red)\n#circle(fill: blue)";
+    let second = "#rect(fill: red)\n#circle(fill: blue)\n#rect(fill: green)";
+
+    let mut server = IncrDocServer::default();
+    server.set_retain_items(true);
+    let mut client = IncrDocClient::default();
+    client
+        .merge_frame(&server.pack_delta(&compile(&driver, first)))
+        .unwrap();
+
+    let snapshot = server.snapshot().unwrap();
+    let mut server = IncrDocServer::restore(&snapshot).unwrap();
+
+    let frame = server.pack_delta(&compile(&driver, second));
+    let (header, delta) = decode(&frame);
+    assert_eq!(header.kind, FrameKind::Delta);
+    assert_eq!(header.base, client.revision);
+
+    let items = (&delta).items().0;
+    assert!(!items.is_empty());
+    for (fg, _) in &items {
+        assert!(
+            !client.module().items.contains_key(fg),
+            "already sent item {fg:?} is sent again"
+        );
+    }
+
+    assert_eq!(client.merge_frame(&frame).unwrap(), FrameKind::Delta);
+}
+
+#[test]
+fn test_snapshot_requires_retained_items() {
+    let driver = driver();
+
+    let mut server = IncrDocServer::default();
+    server.pack_delta(&compile(&driver, "#rect()"));
+    assert!(server.snapshot().is_none());
+    assert!(server.pack_current().is_none());
+
+    server.set_retain_items(true);
+    server.pack_delta(&compile(&driver, "#rect()"));
+    assert!(server.snapshot().is_some());
+}
+
+fn page_labels(driver: &TypstSystemUniverse, content: &str) -> Vec<Option<String>> {
+    let pages = Typst2VecPass::default().doc(&compile(driver, content));
+    pages
+        .iter()
+        .map(|page| page.label.as_deref().map(str::to_owned))
+        .collect()
+}
+
+#[test]
+fn test_page_labels() {
+    let driver = driver();
+    let label = |label: &str| Some(label.to_owned());
+
+    let roman = page_labels(
+        &driver,
+        "#set page(numbering: \"i\")\na#pagebreak()b#pagebreak()c",
+    );
+    assert_eq!(roman, vec![label("i"), label("ii"), label("iii")]);
+
+    let alpha = page_labels(&driver, "#set page(numbering: \"A-1\")\na#pagebreak()b");
+    assert_eq!(alpha, vec![label("A"), label("B")]);
+
+    let custom = page_labels(&driver, "#set page(numbering: \"- 1 -\")\na#pagebreak()b");
+    assert_eq!(custom, vec![label("- 1 -"), label("- 2 -")]);
+
+    let unnumbered = page_labels(&driver, "a#pagebreak()b");
+    assert_eq!(unnumbered, vec![None, None]);
+
+    // Function numberings are not evaluated, see `page_label`.
+    let func = page_labels(&driver, "#set page(numbering: n => [p#n])\na");
+    assert_eq!(func, vec![None]);
+}
+
+fn outline_titles(outline: &Outline) -> Vec<&str> {
+    outline
+        .items
+        .iter()
+        .map(|item| item.title.as_ref())
+        .collect()
+}
+
+#[test]
+fn test_outline_sent_on_change() {
+    let driver = driver();
+    let mut server = IncrDocServer::default();
+    let mut outline = |content: &str| {
+        let (_, delta) = decode(&server.pack_delta(&compile(&driver, content)));
+        (&delta).outline()
+    };
+
+    let first = outline("= Intro\nfoo");
+    assert_eq!(outline_titles(&first.unwrap()), vec!["Intro"]);
+
+    // The body changes, but the outline does not.
+    assert!(outline("= Intro\nbar").is_none());
+
+    let renamed = outline("= Overview\nbar");
+    assert_eq!(outline_titles(&renamed.unwrap()), vec!["Overview"]);
+}
+
+#[test]
+fn test_outline_per_layout() {
+    let driver = driver();
+    let mut exporter = DynamicLayoutSvgExporter::default();
+
+    let narrow = exporter.render(&compile(&driver, "= Narrow"));
+    let wide = exporter.render(&compile(&driver, "= Wide\n== Section"));
+
+    assert_eq!(outline_titles(narrow.outline().unwrap()), vec!["Narrow"]);
+    assert_eq!(
+        outline_titles(wide.outline().unwrap()),
+        vec!["Wide", "Section"]
+    );
+}
+
+#[test]
+fn test_html_source_positions() {
+    let driver = driver();
+    let content = "Hello\n\n*World* and _Typst_\n";
+    let snap = driver.snapshot_with_entry_content(Bytes::from_string(content.to_owned()), None);
+    let world = snap.world.html_task();
+    let doc = typst::compile::<TypstHtmlDocument>(world.as_ref());
+    let doc = Arc::new(doc.output.unwrap());
+
+    let output = static_html(&doc).unwrap();
+    assert!(!output.html().unwrap().contains("data-typst-span"));
+
+    let output = output.with_source_positions(Arc::new(snap.world.clone()));
+    let body = output.body().unwrap();
+    assert!(
+        body.contains(r#"<strong data-typst-span="/main.typ:3:1">"#),
+        "{body}"
+    );
+    assert!(
+        body.contains(r#"<em data-typst-span="/main.typ:3:13">"#),
+        "{body}"
+    );
+}
+
+/// A handle holding the compilation of a revision until it is released, which
+/// exports the compilations as the exporters do, i.e. skipping the cancelled
+/// ones.
+struct HoldingHandle {
+    held: usize,
+    release: Mutex<Option<std::sync::mpsc::Receiver<()>>>,
+    exported: mpsc::UnboundedSender<usize>,
+    cancelled: mpsc::UnboundedSender<usize>,
+}
+
+impl CompilationHandle<SystemCompilerFeat> for HoldingHandle {
+    fn status(&self, revision: usize, rep: CompileReport) {
+        if matches!(rep, CompileReport::Cancelled(..)) {
+            let _ = self.cancelled.send(revision);
+        }
+    }
+
+    fn notify_compile(&self, g: &Arc<WorldComputeGraph<SystemCompilerFeat>>) {
+        let revision = g.snap.world.revision().get();
+        if revision == self.held {
+            let release = self.release.lock().unwrap().take();
+            if let Some(release) = release {
+                let _ = release.recv();
+            }
+        }
+
+        if !CancelToken::of(g).is_cancelled() {
+            let _ = self.exported.send(revision);
+        }
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_superseded_compile_not_exported() {
+    let verse = driver();
+    let held = verse.revision.get();
+
+    let (release_tx, release_rx) = std::sync::mpsc::channel();
+    let (exported_tx, mut exported) = mpsc::unbounded_channel();
+    let (cancelled_tx, mut cancelled) = mpsc::unbounded_channel();
+    let handle = Arc::new(HoldingHandle {
+        held,
+        release: Mutex::new(Some(release_rx)),
+        exported: exported_tx,
+        cancelled: cancelled_tx,
+    });
+
+    let (intr_tx, intr_rx) = mpsc::unbounded_channel();
+    let actor = CompileActor::new_with(
+        verse,
+        intr_tx.clone(),
+        intr_rx,
+        CompileServerOpts {
+            compile_handle: handle,
+            ..Default::default()
+        },
+    )
+    .with_watch(true);
+    let actor = tokio::spawn(actor.run());
+
+    // The newer revision is compiled without waiting for the held one.
+    intr_tx.send(Interrupt::Compile).unwrap();
+    let revision = exported.recv().await.unwrap();
+    assert!(revision > held);
+
+    release_tx.send(()).unwrap();
+    assert_eq!(cancelled.recv().await, Some(held));
+
+    let (settle_tx, settle_rx) = oneshot::channel();
+    intr_tx.send(Interrupt::Settle(settle_tx)).unwrap();
+    settle_rx.await.unwrap();
+    assert!(actor.await.unwrap().unwrap());
+
+    while let Ok(revision) = exported.try_recv() {
+        assert_ne!(revision, held, "the superseded compilation is exported");
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_current_read_with_suspended_project() {
+    let verse = driver();
+    let root = verse.entry_state().root().unwrap();
+
+    let (intr_tx, intr_rx) = mpsc::unbounded_channel();
+    let actor = CompileActor::new_with(verse, intr_tx.clone(), intr_rx, Default::default())
+        .with_watch(true);
+    let actor = tokio::spawn(actor.run());
+
+    // A project without a main file is suspended, and keeps its reason to
+    // compile on every event.
+    let id = ProjectInsId("suspended".into());
+    let task = TaskInputs {
+        entry: Some(EntryState::new_workspace(root)),
+        inputs: None,
+    };
+    let handle = Arc::new(std::marker::PhantomData::<fn(SystemCompilerFeat)>);
+    intr_tx
+        .send(Interrupt::AddProject(id.clone(), task, handle))
+        .unwrap();
+
+    // The read is answered by the primary project only, which answers it
+    // only if it is suspended. The primary project has a main file, so the
+    // read stays pending instead of being answered by the suspended project.
+    let (read_tx, read_rx) = oneshot::channel();
+    intr_tx.send(Interrupt::CurrentRead(read_tx)).unwrap();
+    let read = tokio::time::timeout(std::time::Duration::from_millis(500), read_rx).await;
+    assert!(
+        read.is_err(),
+        "the read is answered by {:?}",
+        read.map(|read| read.map(|graph| graph.snap.id.clone()))
+    );
+
+    let (settle_tx, settle_rx) = oneshot::channel();
+    intr_tx.send(Interrupt::Settle(settle_tx)).unwrap();
+    settle_rx.await.unwrap();
+    assert!(actor.await.unwrap().unwrap());
+}
@@ -4,7 +4,7 @@ use std::path::Path;
 
 use reflexo_typst::config::{entry::EntryOpts, CompileOpts};
 use reflexo_typst::path::PathClean;
-use reflexo_typst::task::ExportPdfTask;
+use reflexo_typst::ExportPdfOutputTask;
 use reflexo_typst::{ExportWebSvgModuleTask, TypstSystemUniverse};
 use typst_ts_cli::export::ReflexoTaskBuilder;
 
@@ -47,7 +47,7 @@ impl ArtifactCompiler {
 
         let exporter = {
             let mut tb = ReflexoTaskBuilder::new();
-            tb.add_pdf(ExportPdfTask::default());
+            tb.add_pdf(ExportPdfOutputTask::default());
             tb.add_web_svg_module(ExportWebSvgModuleTask::default());
             tb.set_output_path(artifact_dir.join(entry_file_base));
 
@@ -55,7 +55,8 @@ impl ArtifactCompiler {
         };
 
         let verse = get_driver(&real_workspace_dir, &real_entry_file_path);
-        (exporter)(&verse.computation()).unwrap();
+        let summary = (exporter)(&verse.computation()).unwrap();
+        assert!(summary.is_success(), "some export tasks failed");
 
         ArtifactBundle {
             verse,
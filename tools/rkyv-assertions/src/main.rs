@@ -39,9 +39,13 @@ fn main() {
         ArchivedDefId,
         ArchivedAbsoluteRef,
         ArchivedLinkItem,
+        ArchivedLinkTarget,
+        ArchivedOutline,
+        ArchivedOutlineItem,
         ArchivedPathItem,
         ArchivedTransformItem,
         ArchivedIncrGlyphPack,
+        ArchivedIncrLifetimePack,
         ArchivedPage,
         ArchivedBuildInfo,
         // color